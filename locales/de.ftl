@@ -0,0 +1,62 @@
+## Problem titles, by HTTP status
+
+status-400 = Ungültige Anfrage
+status-401 = Nicht autorisiert
+status-403 = Verboten
+status-404 = Nicht gefunden
+status-405 = Methode nicht erlaubt
+status-409 = Konflikt
+status-413 = Anfrage zu groß
+status-415 = Nicht unterstützter Medientyp
+status-429 = Zu viele Anfragen
+status-500 = Interner Serverfehler
+status-503 = Dienst nicht verfügbar
+
+## Problem details, by error code
+
+error-invalid_credentials = E-Mail-Adresse oder Passwort ist falsch.
+error-email_taken = Es gibt bereits ein Konto mit dieser E-Mail-Adresse.
+error-invalid_current_password = Das aktuelle Passwort ist falsch.
+error-token_expired = Das Token ist abgelaufen.
+error-invalid_token = Das Token ist ungültig.
+error-password_policy = Das Passwort erfüllt die Passwortrichtlinie nicht.
+error-validation_failed = Einige Felder sind ungültig.
+error-rate_limited = Zu viele Anfragen, bitte versuche es später erneut.
+error-timeout = Die Anfrage hat zu lange gedauert.
+error-csrf_failed = Das CSRF-Token fehlt oder stimmt nicht mit dem Cookie csrf_token überein.
+error-session_cookie_disabled = Cookie-Sitzungen sind auf diesem Server nicht aktiviert.
+error-admin_required = Dieser Vorgang ist Administratoren vorbehalten.
+
+## Validation errors, by rule
+
+validation-min_length = Das Passwort muss mindestens { $min } Zeichen lang sein.
+validation-max_length = Das Passwort darf höchstens { $max } Zeichen lang sein.
+validation-max_bytes = Das Passwort darf höchstens { $max } Bytes lang sein, Buchstaben außerhalb des lateinischen Grundalphabets belegen bis zu 4 Bytes.
+validation-require_lowercase = Das Passwort muss einen Kleinbuchstaben enthalten.
+validation-require_uppercase = Das Passwort muss einen Großbuchstaben enthalten.
+validation-require_digit = Das Passwort muss eine Ziffer enthalten.
+validation-require_symbol = Das Passwort muss ein Sonderzeichen enthalten.
+validation-personal_info = Das Passwort darf weder deinen Namen noch deine E-Mail-Adresse enthalten.
+validation-strength = Das Passwort ist zu leicht zu erraten.
+validation-breached = Das Passwort ist in einem Datenleck aufgetaucht, bitte wähle ein anderes.
+validation-name_length = Der Name muss mindestens { $min } Zeichen lang sein.
+validation-email = Die E-Mail-Adresse ist ungültig.
+validation-unsupported_locale = Diese Sprache wird nicht unterstützt.
+validation-webhook_url = URL muss eine absolute { $schemes }-URL sein.
+validation-webhook_url_private = URL darf nicht auf eine Adresse in einem privaten Netzwerk zeigen.
+validation-event_types_empty = Mindestens ein Ereignistyp muss abonniert werden.
+validation-unknown_event_type = Unbekannter Ereignistyp { $event_type }.
+
+## Transactional emails
+
+email-welcome-subject = Willkommen, { $first_name }!
+email-welcome-body =
+    Hallo { $first_name },
+
+    dein Konto wurde erstellt, du kannst dich mit { $email } anmelden.
+email-password_changed-subject = Dein Passwort wurde geändert
+email-password_changed-body =
+    Hallo { $first_name },
+
+    das Passwort deines Kontos wurde soeben geändert. Falls du das nicht warst, setze dein Passwort sofort zurück.
+email-footer = Du erhältst diese E-Mail wegen deines Kontos bei unserem Dienst.
@@ -0,0 +1,62 @@
+## Problem titles, by HTTP status
+
+status-400 = Bad Request
+status-401 = Unauthorized
+status-403 = Forbidden
+status-404 = Not Found
+status-405 = Method Not Allowed
+status-409 = Conflict
+status-413 = Payload Too Large
+status-415 = Unsupported Media Type
+status-429 = Too Many Requests
+status-500 = Internal Server Error
+status-503 = Service Unavailable
+
+## Problem details, by error code
+
+error-invalid_credentials = Email or password is incorrect.
+error-email_taken = A user with this email already exists.
+error-invalid_current_password = Current password is incorrect.
+error-token_expired = Token has expired.
+error-invalid_token = Token is invalid.
+error-password_policy = Password does not meet the password policy.
+error-validation_failed = Some fields are invalid.
+error-rate_limited = Too many requests, please try again later.
+error-timeout = The request took too long to complete.
+error-csrf_failed = The CSRF token is missing or does not match the csrf_token cookie.
+error-session_cookie_disabled = Cookie sessions are not enabled on this server.
+error-admin_required = This operation is restricted to administrators.
+
+## Validation errors, by rule
+
+validation-min_length = Password must be at least { $min } characters long.
+validation-max_length = Password must be at most { $max } characters long.
+validation-max_bytes = Password must be at most { $max } bytes long, letters outside the basic Latin alphabet take up to 4 bytes.
+validation-require_lowercase = Password must contain a lowercase letter.
+validation-require_uppercase = Password must contain an uppercase letter.
+validation-require_digit = Password must contain a digit.
+validation-require_symbol = Password must contain a symbol.
+validation-personal_info = Password must not contain your name or email.
+validation-strength = Password is too easy to guess.
+validation-breached = Password has appeared in a data breach, please choose another one.
+validation-name_length = Name must be at least { $min } characters long.
+validation-email = Email address is invalid.
+validation-unsupported_locale = Locale is not supported.
+validation-webhook_url = URL must be an absolute { $schemes } URL.
+validation-webhook_url_private = URL must not point to a private network address.
+validation-event_types_empty = Subscribe to at least one event type.
+validation-unknown_event_type = Unknown event type { $event_type }.
+
+## Transactional emails
+
+email-welcome-subject = Welcome, { $first_name }!
+email-welcome-body =
+    Hi { $first_name },
+
+    your account has been created, you can sign in with { $email }.
+email-password_changed-subject = Your password was changed
+email-password_changed-body =
+    Hi { $first_name },
+
+    the password of your account was just changed. If this wasn't you, reset your password right away.
+email-footer = You receive this email because of your account on our service.
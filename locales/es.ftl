@@ -0,0 +1,62 @@
+## Problem titles, by HTTP status
+
+status-400 = Solicitud incorrecta
+status-401 = No autorizado
+status-403 = Prohibido
+status-404 = No encontrado
+status-405 = Método no permitido
+status-409 = Conflicto
+status-413 = Solicitud demasiado grande
+status-415 = Tipo de contenido no admitido
+status-429 = Demasiadas solicitudes
+status-500 = Error interno del servidor
+status-503 = Servicio no disponible
+
+## Problem details, by error code
+
+error-invalid_credentials = El correo electrónico o la contraseña son incorrectos.
+error-email_taken = Ya existe un usuario con este correo electrónico.
+error-invalid_current_password = La contraseña actual es incorrecta.
+error-token_expired = El token ha caducado.
+error-invalid_token = El token no es válido.
+error-password_policy = La contraseña no cumple la política de contraseñas.
+error-validation_failed = Algunos campos no son válidos.
+error-rate_limited = Demasiadas solicitudes, inténtalo de nuevo más tarde.
+error-timeout = La solicitud tardó demasiado en completarse.
+error-csrf_failed = El token CSRF falta o no coincide con la cookie csrf_token.
+error-session_cookie_disabled = Las sesiones con cookies no están habilitadas en este servidor.
+error-admin_required = Esta operación está reservada a los administradores.
+
+## Validation errors, by rule
+
+validation-min_length = La contraseña debe tener al menos { $min } caracteres.
+validation-max_length = La contraseña debe tener como máximo { $max } caracteres.
+validation-max_bytes = La contraseña debe tener como máximo { $max } bytes, las letras fuera del alfabeto latino básico ocupan hasta 4 bytes.
+validation-require_lowercase = La contraseña debe contener una letra minúscula.
+validation-require_uppercase = La contraseña debe contener una letra mayúscula.
+validation-require_digit = La contraseña debe contener un dígito.
+validation-require_symbol = La contraseña debe contener un símbolo.
+validation-personal_info = La contraseña no debe contener tu nombre ni tu correo electrónico.
+validation-strength = La contraseña es demasiado fácil de adivinar.
+validation-breached = La contraseña ha aparecido en una filtración de datos, elige otra.
+validation-name_length = El nombre debe tener al menos { $min } caracteres.
+validation-email = El correo electrónico no es válido.
+validation-unsupported_locale = El idioma no está disponible.
+validation-webhook_url = La URL debe ser una URL { $schemes } absoluta.
+validation-webhook_url_private = La URL no debe apuntar a una dirección de una red privada.
+validation-event_types_empty = Suscríbete al menos a un tipo de evento.
+validation-unknown_event_type = Tipo de evento desconocido { $event_type }.
+
+## Transactional emails
+
+email-welcome-subject = ¡Bienvenido, { $first_name }!
+email-welcome-body =
+    Hola { $first_name }:
+
+    tu cuenta ha sido creada, puedes iniciar sesión con { $email }.
+email-password_changed-subject = Tu contraseña ha cambiado
+email-password_changed-body =
+    Hola { $first_name }:
+
+    la contraseña de tu cuenta acaba de cambiar. Si no fuiste tú, restablécela de inmediato.
+email-footer = Recibes este correo por tu cuenta en nuestro servicio.
@@ -1,6 +1,13 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20240618_153555_create_users;
+mod m20261019_120000_add_users_locale;
+mod m20261019_130000_create_rate_limits;
+mod m20261019_140000_create_sessions;
+mod m20261019_150000_create_jobs;
+mod m20261019_160000_create_scheduled_runs;
+mod m20261019_170000_create_outbox;
+mod m20261019_180000_create_webhooks;
 
 pub struct Migrator;
 
@@ -9,6 +16,13 @@ impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
             Box::new(m20240618_153555_create_users::Migration),
+            Box::new(m20261019_120000_add_users_locale::Migration),
+            Box::new(m20261019_130000_create_rate_limits::Migration),
+            Box::new(m20261019_140000_create_sessions::Migration),
+            Box::new(m20261019_150000_create_jobs::Migration),
+            Box::new(m20261019_160000_create_scheduled_runs::Migration),
+            Box::new(m20261019_170000_create_outbox::Migration),
+            Box::new(m20261019_180000_create_webhooks::Migration),
         ]
     }
 }
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RateLimits::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(RateLimits::Key).string().primary_key())
+                    .col(ColumnDef::new(RateLimits::Tokens).double().not_null())
+                    .col(ColumnDef::new(RateLimits::Allowed).boolean().not_null())
+                    .col(
+                        ColumnDef::new(RateLimits::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_rate_limits_updated_at")
+                    .table(RateLimits::Table)
+                    .col(RateLimits::UpdatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RateLimits::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RateLimits {
+    Table,
+    Key,
+    Tokens,
+    Allowed,
+    UpdatedAt,
+}
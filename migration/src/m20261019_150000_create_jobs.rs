@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Jobs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Jobs::Id)
+                            .big_integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Jobs::Kind).string().not_null())
+                    .col(ColumnDef::new(Jobs::Payload).json_binary().not_null())
+                    .col(ColumnDef::new(Jobs::Status).string().not_null().default("pending"))
+                    .col(ColumnDef::new(Jobs::Priority).integer().not_null().default(0))
+                    .col(ColumnDef::new(Jobs::Attempts).integer().not_null().default(0))
+                    .col(ColumnDef::new(Jobs::MaxAttempts).integer().not_null())
+                    .col(ColumnDef::new(Jobs::UniqueKey).string().null())
+                    .col(ColumnDef::new(Jobs::LastError).text().null())
+                    .col(
+                        ColumnDef::new(Jobs::RunAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(Jobs::LockedAt).timestamp_with_time_zone().null())
+                    .col(ColumnDef::new(Jobs::LockedBy).string().null())
+                    .col(
+                        ColumnDef::new(Jobs::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(Jobs::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_jobs_claim")
+                    .table(Jobs::Table)
+                    .col(Jobs::Status)
+                    .col(Jobs::Priority)
+                    .col(Jobs::RunAt)
+                    .to_owned(),
+            )
+            .await?;
+        // sea-query can't express partial indexes, a key is unique only while its job is queued
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE UNIQUE INDEX IF NOT EXISTS idx_jobs_unique_key ON jobs (unique_key) \
+                 WHERE status IN ('pending', 'running')",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Jobs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Jobs {
+    Table,
+    Id,
+    Kind,
+    Payload,
+    Status,
+    Priority,
+    Attempts,
+    MaxAttempts,
+    UniqueKey,
+    LastError,
+    RunAt,
+    LockedAt,
+    LockedBy,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScheduledRuns::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScheduledRuns::Id)
+                            .big_integer()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ScheduledRuns::Task).string().not_null())
+                    .col(
+                        ColumnDef::new(ScheduledRuns::ScheduledAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduledRuns::Status).string().not_null())
+                    .col(ColumnDef::new(ScheduledRuns::Instance).string().not_null())
+                    .col(ColumnDef::new(ScheduledRuns::Error).text().null())
+                    .col(
+                        ColumnDef::new(ScheduledRuns::StartedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduledRuns::FinishedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        // one run per task and tick, whichever replica inserts it first
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_scheduled_runs_task_scheduled_at")
+                    .table(ScheduledRuns::Table)
+                    .col(ScheduledRuns::Task)
+                    .col(ScheduledRuns::ScheduledAt)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ScheduledRuns::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum ScheduledRuns {
+    Table,
+    Id,
+    Task,
+    ScheduledAt,
+    Status,
+    Instance,
+    Error,
+    StartedAt,
+    FinishedAt,
+}
@@ -0,0 +1,146 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240618_153555_create_users::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookEndpoints::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(WebhookEndpoints::Id).uuid().primary_key())
+                    .col(ColumnDef::new(WebhookEndpoints::TenantPid).uuid().not_null())
+                    .col(ColumnDef::new(WebhookEndpoints::Url).text().not_null())
+                    .col(ColumnDef::new(WebhookEndpoints::Secret).string().not_null())
+                    .col(ColumnDef::new(WebhookEndpoints::EventTypes).json_binary().not_null())
+                    .col(ColumnDef::new(WebhookEndpoints::Enabled).boolean().not_null().default(true))
+                    .col(
+                        ColumnDef::new(WebhookEndpoints::ConsecutiveFailures)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(WebhookEndpoints::DisabledAt).timestamp_with_time_zone().null())
+                    .col(
+                        ColumnDef::new(WebhookEndpoints::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(WebhookEndpoints::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_endpoints_tenant_pid")
+                            .from(WebhookEndpoints::Table, WebhookEndpoints::TenantPid)
+                            .to(Users::Table, Users::Pid)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_endpoints_tenant_pid")
+                    .table(WebhookEndpoints::Table)
+                    .col(WebhookEndpoints::TenantPid)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(WebhookDeliveries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WebhookDeliveries::EndpointId).uuid().not_null())
+                    .col(ColumnDef::new(WebhookDeliveries::EventId).uuid().not_null())
+                    .col(ColumnDef::new(WebhookDeliveries::EventType).string().not_null())
+                    .col(ColumnDef::new(WebhookDeliveries::Attempt).integer().not_null())
+                    .col(ColumnDef::new(WebhookDeliveries::StatusCode).integer().null())
+                    .col(ColumnDef::new(WebhookDeliveries::Success).boolean().not_null())
+                    .col(ColumnDef::new(WebhookDeliveries::Error).text().null())
+                    .col(ColumnDef::new(WebhookDeliveries::DurationMs).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(WebhookDeliveries::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_webhook_deliveries_endpoint_id")
+                            .from(WebhookDeliveries::Table, WebhookDeliveries::EndpointId)
+                            .to(WebhookEndpoints::Table, WebhookEndpoints::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_webhook_deliveries_endpoint_id_created_at")
+                    .table(WebhookDeliveries::Table)
+                    .col(WebhookDeliveries::EndpointId)
+                    .col(WebhookDeliveries::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WebhookDeliveries::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(WebhookEndpoints::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum WebhookEndpoints {
+    Table,
+    Id,
+    TenantPid,
+    Url,
+    Secret,
+    EventTypes,
+    Enabled,
+    ConsecutiveFailures,
+    DisabledAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum WebhookDeliveries {
+    Table,
+    Id,
+    EndpointId,
+    EventId,
+    EventType,
+    Attempt,
+    StatusCode,
+    Success,
+    Error,
+    DurationMs,
+    CreatedAt,
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use migration::sea_orm::{ConnectionTrait, DatabaseConnection};
+use poem::{endpoint::BoxEndpoint, EndpointExt, Middleware, Route};
+
+use super::{
+    bootstrap,
+    capabilities::common::{
+        config::{app_config::AppConfig, config_service::ConfigService},
+        database::db_router::DbRouter,
+        events::event_subscriber::EventSubscribers,
+        global_model::app_state::AppState,
+        jobs::job_handler::JobRegistry,
+    },
+    startup_error::StartupError,
+};
+
+/// Wraps the routes in one extra middleware
+pub(crate) type MiddlewareFn = Box<dyn FnOnce(BoxEndpoint<'static>) -> BoxEndpoint<'static> + Send>;
+
+/// A module plugged into the app next to the built-in capabilities.
+///
+/// Handlers reach the services through `Data<&AppState>` like the built-in controllers.
+pub trait Capability: Send + Sync {
+    /// Add routes, e.g. `route.nest("/billing", OpenApiService::new(BillingApi, "Billing", "1.0"))`
+    fn routes(&self, route: Route, _state: &AppState) -> Route {
+        route
+    }
+
+    /// Register the handlers of the jobs this capability enqueues
+    fn jobs(&self, _jobs: &mut JobRegistry, _state: &AppState) {}
+
+    /// Subscribe to domain events
+    fn subscribers(&self, _subscribers: &mut EventSubscribers, _state: &AppState) {}
+}
+
+/// Builds the app from a config struct instead of the environment.
+///
+/// `build` validates the config, connects to `database.url` unless a connection was given, runs
+/// migrations when `database.auto_migrate` is set and creates every service. With a given connection
+/// `database.url` may be empty, and the features are checked against the connection's backend.
+pub struct AppBuilder {
+    config: AppConfig,
+    db: Option<DbRouter>,
+    capabilities: Vec<Arc<dyn Capability>>,
+    middleware: Vec<MiddlewareFn>,
+}
+
+impl AppBuilder {
+    pub fn new(config: AppConfig) -> Self {
+        Self {
+            config,
+            db: None,
+            capabilities: vec![],
+            middleware: vec![],
+        }
+    }
+
+    /// Builder for a config loaded by `ConfigService::load`
+    pub fn from_config(config: &ConfigService) -> Self {
+        Self::new(config.get().clone())
+    }
+
+    /// Use an open connection as the primary, without replicas
+    pub fn database(self, db: DatabaseConnection) -> Self {
+        self.db_router(DbRouter::new(db, vec![]))
+    }
+
+    /// Use open primary and replica connections
+    pub fn db_router(mut self, db: DbRouter) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    pub fn capability(mut self, capability: impl Capability + 'static) -> Self {
+        self.capabilities.push(Arc::new(capability));
+        self
+    }
+
+    /// Wrap the routes in `middleware`, inside the built-in middleware so requests already carry
+    /// their id, locale and tracing span and errors still become problem details.
+    /// Middleware added first runs first.
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware<BoxEndpoint<'static>> + Send + 'static,
+        M::Output: 'static,
+    {
+        self.middleware.push(Box::new(move |endpoint: BoxEndpoint<'static>| endpoint.with(middleware).map_to_response().boxed()));
+        self
+    }
+
+    pub async fn build(self) -> Result<App, StartupError> {
+        let (config, db) = match self.db {
+            Some(db) => (ConfigService::for_connection(self.config, db.writer().get_database_backend())?, db),
+            None => {
+                let config = ConfigService::from_config(self.config)?;
+                let db = bootstrap::connect(&config).await?;
+                (config, db)
+            }
+        };
+        if config.database().auto_migrate {
+            bootstrap::migrate(&db).await?;
+        }
+        let state = bootstrap::make_app_state(&config, db)?;
+        // the last added wraps the others, so the first added sees requests first
+        let mut middleware = self.middleware;
+        middleware.reverse();
+        let endpoint = bootstrap::build_routes_with(state.clone(), &self.capabilities, middleware);
+        Ok(App {
+            state,
+            endpoint,
+            capabilities: self.capabilities,
+        })
+    }
+}
+
+/// A built app, serve `into_endpoint` and start background work as configured
+pub struct App {
+    state: AppState,
+    endpoint: BoxEndpoint<'static>,
+    capabilities: Vec<Arc<dyn Capability>>,
+}
+
+impl App {
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Run job workers and the event dispatcher, with the jobs and subscribers of every capability
+    pub fn start_workers(&self) {
+        let mut jobs = bootstrap::job_registry(&self.state);
+        let mut subscribers = bootstrap::event_subscribers(&self.state);
+        for capability in &self.capabilities {
+            capability.jobs(&mut jobs, &self.state);
+            capability.subscribers(&mut subscribers, &self.state);
+        }
+        bootstrap::start_workers_with(&self.state, jobs, subscribers);
+    }
+
+    pub fn start_scheduler(&self) {
+        bootstrap::start_scheduler(&self.state);
+    }
+
+    pub fn into_endpoint(self) -> BoxEndpoint<'static> {
+        self.endpoint
+    }
+}
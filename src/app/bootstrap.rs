@@ -1,75 +1,327 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use migration::{sea_orm, MigratorTrait};
-use poem::{middleware::AddDataEndpoint, EndpointExt, Route};
-use poem_openapi::OpenApiService;
-use sea_orm::DatabaseConnection;
+use migration::{
+    sea_orm::{ConnectionTrait, DbBackend},
+    MigratorTrait,
+};
+use poem::{endpoint::BoxEndpoint, get, middleware::CookieJarManager, EndpointExt, Route};
+use poem_openapi::{OpenApi, OpenApiService};
 
 use crate::app::capabilities::{
     common::{
-        config::config_service::ConfigService,
+        config::{
+            app_config::{EmailTransport, RateLimitBackend, SchedulerConfig},
+            config_service::ConfigService,
+        },
+        database::{db_health_check::DbHealthCheck, db_router::DbRouter},
+        email::{
+            email_service::EmailService, email_templates::EmailTemplates, mailer::Mailer,
+            mailer_health_check::MailerHealthCheck, send_email_job::SendEmailJob,
+        },
+        events::{event_dispatcher::EventDispatcher, event_subscriber::EventSubscribers, outbox::OutboxService},
+        health::health_service::HealthService,
+        http::{
+            body_limit_middleware::BodyLimitMiddleware, compression_middleware::CompressionMiddleware, cors::cors,
+            panic_handler::catch_panic, security_headers_middleware::SecurityHeadersMiddleware,
+            timeout_middleware::TimeoutMiddleware,
+        },
+        i18n::{i18n_service::I18nService, locale_middleware::LocaleMiddleware},
+        jobs::{
+            job_handler::JobRegistry, job_health_check::JobQueueHealthCheck, job_service::JobService, job_worker::JobWorker,
+        },
+        lifecycle::lifecycle_service::{hook_order, LifecycleService},
+        logging::request_id::RequestIdMiddleware,
+        metrics::{metrics_middleware::MetricsMiddleware, metrics_service::MetricsService},
+        problem::problem_middleware::ProblemMiddleware,
+        scheduler::{scheduled_task::SqlTask, scheduler_service::SchedulerService},
+        rate_limit::{
+            memory_store::MemoryRateLimitStore,
+            postgres_store::PostgresRateLimitStore,
+            rate_limit_middleware::RateLimitMiddleware,
+            rate_limit_service::RateLimitService,
+            rate_limit_store::RateLimitStore,
+            route_table::RouteTable,
+        },
+        telemetry::tracing_middleware::TracingMiddleware,
         global_model::app_state::{AppState, ServiceList},
+        tls::client_certificate::{ClientCertificateMiddleware, ClientCertificates},
+    },
+    iam::{
+        controllers::authentication::auth_controllers,
+        services::{iam::iam_service::IAMService, notifications::user_emails_subscriber::UserEmailsSubscriber},
     },
-    iam::{controllers::authentication::auth_controllers, services::iam::iam_service::IAMService},
+    webhooks::{
+        controllers::webhooks_controller,
+        services::webhook::{
+            webhook_delivery_job::WebhookDeliveryJob, webhook_service::WebhookService,
+            webhook_subscriber::WebhookSubscriber,
+        },
+    },
+};
+
+use super::{
+    app_builder::{App, AppBuilder, Capability, MiddlewareFn},
+    capabilities::iam::controllers::users::users_controller,
+    routes,
+    startup_error::StartupError,
 };
 
-use super::{capabilities::iam::controllers::users::users_controller, routes};
+/// Operations that run on every database
+type SqliteApiList = (
+    routes::base::Api,
+    auth_controllers::API,
+    users_controller::API,
+    routes::admin::Api,
+);
 
-pub async fn build_app() -> AddDataEndpoint<Route, AppState> {
+/// Every operation, webhooks and the scheduler status use Postgres SQL and are not mounted on SQLite
+type ApiList = (SqliteApiList, webhooks_controller::API, routes::admin::SchedulerApi);
 
-    let state = make_app_state().await;
+/// Build the app for a loaded config, see `AppBuilder` to add capabilities, middleware or a connection
+pub async fn build_app(config: &ConfigService) -> Result<BoxEndpoint<'static>, StartupError> {
+    AppBuilder::from_config(config).build().await.map(App::into_endpoint)
+}
+
+fn api_service() -> OpenApiService<ApiList, ()> {
+    api_service_of((sqlite_api_list(), webhooks_controller::API, routes::admin::SchedulerApi))
+}
 
-    let api_list = (
-        routes::base::Api::default(),
-        auth_controllers::API::default(),
-        users_controller::API::default()
-    );
-    let all_apis = OpenApiService::new(api_list, "Prod APIs", "1.0").url_prefix("/api");
-    let base_apis = OpenApiService::new(routes::base::Api::default(), "Base", "1.0");
-    let ui = all_apis.swagger_ui();
-    Route::new()
+fn sqlite_api_list() -> SqliteApiList {
+    (routes::base::Api, auth_controllers::API, users_controller::API, routes::admin::Api)
+}
+
+fn api_service_of<T: OpenApi>(api_list: T) -> OpenApiService<T, ()> {
+    OpenApiService::new(api_list, "Prod APIs", "1.0").url_prefix("/api")
+}
+
+/// Mount `apis` under `/api` with their Swagger UI
+fn nest_apis<T: OpenApi + 'static>(route: Route, apis: OpenApiService<T, ()>) -> Route {
+    let ui = apis.swagger_ui();
+    route.nest("/api", apis).nest("/swagger", ui)
+}
+
+/// OpenAPI document of everything under `/api`, as JSON
+pub fn openapi_spec() -> String {
+    api_service().spec()
+}
+
+/// Build the routes around an existing state
+pub fn build_routes(state: AppState) -> BoxEndpoint<'static> {
+    build_routes_with(state, &[], vec![])
+}
+
+/// Routes of the built-in and extra capabilities, `middleware` wraps them inside the built-in middleware
+pub(crate) fn build_routes_with(
+    state: AppState,
+    capabilities: &[Arc<dyn Capability>],
+    middleware: Vec<MiddlewareFn>,
+) -> BoxEndpoint<'static> {
+    let mut route_table = RouteTable::default();
+    route_table.add("/", routes::base::Api::meta());
+    route_table.add("/api", ApiList::meta());
+    let rate_limit = state.rate_limit.clone().with_routes(route_table);
+    let http = state.http.clone();
+    let cors = cors(&http.cors);
+
+    let base_apis = OpenApiService::new(routes::base::Api, "Base", "1.0");
+    let route = Route::new()
         .nest("/", base_apis)
-        .nest("/api", all_apis)
-        .nest("/swagger", ui)
+        .at("/metrics", get(routes::metrics::metrics));
+    let mut route = match state.db.writer().get_database_backend() {
+        DbBackend::Postgres => nest_apis(route, api_service()),
+        _ => nest_apis(route, api_service_of(sqlite_api_list())),
+    };
+    for capability in capabilities {
+        route = capability.routes(route, &state);
+    }
+    let mut endpoint = route.boxed();
+    for wrap in middleware {
+        endpoint = wrap(endpoint);
+    }
+    endpoint
+        .with(ProblemMiddleware)
+        .with(CookieJarManager::new())
+        .with(catch_panic())
+        .with_if(
+            http.request_timeout_secs > 0,
+            TimeoutMiddleware::new(Duration::from_secs(http.request_timeout_secs)),
+        )
+        .with(BodyLimitMiddleware::new(http.max_body_bytes))
+        .with(RateLimitMiddleware::new(rate_limit))
+        .with(LocaleMiddleware::new(state.i18n.clone()))
+        .with_if(cors.is_some(), cors.unwrap_or_default())
+        .with(SecurityHeadersMiddleware::new(http.security_headers.clone()))
+        .with_if(http.compression, CompressionMiddleware::new(http.compression_min_bytes))
+        .with(ClientCertificateMiddleware::new(state.client_certificates.clone()))
+        .with(MetricsMiddleware::new(state.metrics.clone()))
+        .with(TracingMiddleware)
+        .with(RequestIdMiddleware)
         .data(state)
+        .boxed()
 }
 
-async fn make_app_state() -> AppState {
-    let db = make_db_connection().await;
-    tracing::debug!("DB Connection Created");
-    let config = ConfigService::new();
-    AppState {
-        db: db.clone(),
-        services: ServiceList {
-            iam: IAMService::new(db, &config.clone()),
-        },
+/// Create all services on a connected and migrated database, the state is ready once this returns
+pub(crate) fn make_app_state(config: &ConfigService, db: DbRouter) -> Result<AppState, StartupError> {
+    let lifecycle = LifecycleService::new(Duration::from_secs(config.server().shutdown_hook_timeout_secs));
+
+    let metrics = MetricsService::new();
+    let i18n = I18nService::new(&config.i18n().default_locale);
+    let health = HealthService::new(Duration::from_secs(config.server().health_check_timeout_secs));
+    for check in DbHealthCheck::for_router(&db) {
+        health.register(check);
     }
-}
 
-async fn make_db_connection() -> DatabaseConnection {
-    let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL is not set in .env file");
-    let auto_migrate = std::env::var("AUTO_MIGRATE").expect("AUTO_MIGRATE is not set in .env file");
-
-    let mut opts = sea_orm::ConnectOptions::new(db_url);
-    opts.max_connections(10)
-        .min_connections(1)
-        .connect_timeout(Duration::from_secs(8))
-        .idle_timeout(Duration::from_secs(8))
-        .max_lifetime(Duration::from_secs(8))
-        .sqlx_logging(true);
-
-    let conn = sea_orm::Database::connect(opts)
-        .await
-        .expect("Database connection failed");
-
-    if auto_migrate == "TRUE" {
-        match migration::Migrator::up(&conn, None).await {
-            Ok(_) => tracing::debug!("Migrations successful"),
-            Err(e) => {
-                tracing::error!("Migrations failed");
-                panic!("{}", e);
-            }
-        }
+    let pools = db.clone();
+    lifecycle.on_shutdown("database", hook_order::CLOSE_POOLS, move || async move {
+        pools.close().await;
+    });
+
+    let iam = IAMService::new(db.clone(), config, &metrics, i18n.clone())
+        .map_err(|message| StartupError::Service { name: "iam", message })?;
+    let webhooks = WebhookService::new(db.clone(), config, &metrics);
+    let jobs = JobService::new(db.clone(), config.jobs());
+    // the queue is Postgres only, on SQLite nothing is enqueued
+    if db.writer().get_database_backend() == DbBackend::Postgres {
+        health.register(JobQueueHealthCheck::new(jobs.clone()));
+    }
+    let mailer = Mailer::new(config.email()).map_err(|message| StartupError::Service { name: "email", message })?;
+    if config.email().transport == EmailTransport::Smtp {
+        health.register(MailerHealthCheck::new(mailer.clone()));
     }
-    conn
+    let email = EmailService::new(EmailTemplates::new(i18n.clone()), mailer, jobs.clone(), config.email());
+    let store: Arc<dyn RateLimitStore> = match config.rate_limit().backend {
+        RateLimitBackend::Memory => Arc::new(MemoryRateLimitStore::new()),
+        RateLimitBackend::Postgres => Arc::new(PostgresRateLimitStore::new(db.clone())),
+    };
+    let rate_limit = RateLimitService::new(config.rate_limit(), store, &metrics).with_identity(iam.clone());
+
+    let state = AppState {
+        db: db.clone(),
+        lifecycle: lifecycle.clone(),
+        health,
+        metrics: metrics.clone(),
+        i18n,
+        rate_limit,
+        jobs,
+        email,
+        events: OutboxService::new(db.clone(), config.events()),
+        scheduler: make_scheduler(&db, config.scheduler()),
+        http: config.http().clone(),
+        client_certificates: ClientCertificates::default(),
+        services: ServiceList { iam, webhooks },
+    };
+    lifecycle.mark_started();
+    Ok(state)
+}
+
+/// Handlers of every job kind the app enqueues
+pub fn job_registry(state: &AppState) -> JobRegistry {
+    let mut registry = JobRegistry::default();
+    registry
+        .register(SendEmailJob {
+            emails: state.email.clone(),
+        })
+        .register(WebhookDeliveryJob {
+            webhooks: state.services.webhooks.clone(),
+        });
+    registry
+}
+
+/// In-process reactions to domain events, use `forward_to_job` for slow ones
+pub fn event_subscribers(state: &AppState) -> EventSubscribers {
+    let mut subscribers = EventSubscribers::default();
+    subscribers
+        .subscribe(UserEmailsSubscriber {
+            emails: state.email.clone(),
+        })
+        .subscribe(WebhookSubscriber {
+            webhooks: state.services.webhooks.clone(),
+            jobs: state.jobs.clone(),
+        });
+    subscribers
+}
+
+/// Run job workers and the event dispatcher in this process until shutdown
+pub fn start_workers(state: &AppState) {
+    start_workers_with(state, job_registry(state), event_subscribers(state));
+}
+
+/// Run job workers for `jobs` and the event dispatcher for `subscribers` until shutdown
+pub fn start_workers_with(state: &AppState, jobs: JobRegistry, subscribers: EventSubscribers) {
+    let worker = JobWorker::new(state.jobs.clone(), jobs, &state.metrics).spawn();
+    state
+        .lifecycle
+        .on_shutdown("job_worker", hook_order::STOP_WORKERS, move || worker.stop());
+    let dispatcher = EventDispatcher::new(state.events.clone(), subscribers, &state.metrics).spawn();
+    state
+        .lifecycle
+        .on_shutdown("event_dispatcher", hook_order::STOP_WORKERS, move || dispatcher.stop());
+}
+
+/// Recurring maintenance tasks, schedules can be overridden in `scheduler.schedules`
+fn make_scheduler(db: &DbRouter, config: &SchedulerConfig) -> SchedulerService {
+    let retention = format!("interval '{} days'", config.retention_days);
+    SchedulerService::new(db.clone(), config)
+        .with_task(
+            "purge_expired_sessions",
+            "0 */15 * * * *",
+            SqlTask::new(db.clone(), "DELETE FROM sessions WHERE expires_at < now()"),
+        )
+        .with_task(
+            "purge_rate_limits",
+            "0 0 * * * *",
+            SqlTask::new(db.clone(), "DELETE FROM rate_limits WHERE updated_at < now() - interval '1 day'"),
+        )
+        .with_task(
+            "purge_finished_jobs",
+            "0 30 3 * * *",
+            SqlTask::new(
+                db.clone(),
+                format!("DELETE FROM jobs WHERE status IN ('completed', 'dead') AND updated_at < now() - {}", retention),
+            ),
+        )
+        .with_task(
+            "purge_dispatched_events",
+            "0 15 4 * * *",
+            SqlTask::new(
+                db.clone(),
+                format!(
+                    "WITH purged AS (DELETE FROM outbox WHERE dispatched_at < now() - {} RETURNING id) \
+                     DELETE FROM processed_events WHERE event_id IN (SELECT id FROM purged)",
+                    retention
+                ),
+            ),
+        )
+        .with_task(
+            "purge_webhook_deliveries",
+            "0 30 4 * * *",
+            SqlTask::new(db.clone(), format!("DELETE FROM webhook_deliveries WHERE created_at < now() - {}", retention)),
+        )
+        .with_task(
+            "purge_scheduled_runs",
+            "0 45 3 * * *",
+            SqlTask::new(db.clone(), format!("DELETE FROM scheduled_runs WHERE started_at < now() - {}", retention)),
+        )
+}
+
+/// Run scheduled tasks in this process until shutdown
+pub fn start_scheduler(state: &AppState) {
+    let scheduler = state.scheduler.start();
+    state
+        .lifecycle
+        .on_shutdown("scheduler", hook_order::STOP_WORKERS, move || scheduler.stop());
+}
+
+/// Open the database pools described by the config
+pub(crate) async fn connect(config: &ConfigService) -> Result<DbRouter, StartupError> {
+    let db = DbRouter::connect(config.database()).await.map_err(StartupError::Database)?;
+    tracing::debug!("DB Connection Created");
+    Ok(db)
+}
+
+/// Apply pending migrations on the primary
+pub(crate) async fn migrate(db: &DbRouter) -> Result<(), StartupError> {
+    migration::Migrator::up(db.writer(), None).await.map_err(StartupError::Migration)?;
+    tracing::debug!("Migrations successful");
+    Ok(())
 }
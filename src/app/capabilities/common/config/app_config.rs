@@ -0,0 +1,867 @@
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::app::capabilities::{
+    common::i18n::i18n_service::supported_locales, iam::config::{IamConfig, SessionBackend},
+    webhooks::config::WebhooksConfig,
+};
+
+use super::secret::Secret;
+
+/// Deployment profile, selects the `config/app.<profile>.*` overlay
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Dev,
+    Test,
+    Prod,
+}
+
+impl Profile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Profile::Dev => "dev",
+            Profile::Test => "test",
+            Profile::Prod => "prod",
+        }
+    }
+}
+
+/// Typed application config, one section per capability
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub profile: Profile,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub http: HttpConfig,
+    pub logging: LoggingConfig,
+    pub telemetry: TelemetryConfig,
+    pub i18n: I18nConfig,
+    pub rate_limit: RateLimitConfig,
+    pub jobs: JobsConfig,
+    pub events: EventsConfig,
+    pub email: EmailConfig,
+    pub scheduler: SchedulerConfig,
+    pub iam: IamConfig,
+    pub webhooks: WebhooksConfig,
+}
+
+impl AppConfig {
+    /// Check every section, returning all problems found
+    pub fn validate(&self) -> Vec<String> {
+        self.validate_sections(true)
+    }
+
+    /// Check every section but `database`, for an app on an open connection
+    pub fn validate_without_database(&self) -> Vec<String> {
+        self.validate_sections(false)
+    }
+
+    fn validate_sections(&self, database: bool) -> Vec<String> {
+        let mut errors = vec![];
+        errors.extend(prefixed("server", self.server.validate()));
+        if database {
+            errors.extend(prefixed("database", self.database.validate()));
+        }
+        errors.extend(prefixed("http", self.http.validate()));
+        errors.extend(prefixed("logging", self.logging.validate()));
+        errors.extend(prefixed("telemetry", self.telemetry.validate()));
+        errors.extend(prefixed("i18n", self.i18n.validate()));
+        errors.extend(prefixed("rate_limit", self.rate_limit.validate()));
+        errors.extend(prefixed("jobs", self.jobs.validate()));
+        errors.extend(prefixed("events", self.events.validate()));
+        errors.extend(prefixed("email", self.email.validate()));
+        errors.extend(prefixed("scheduler", self.scheduler.validate()));
+        errors.extend(prefixed("iam", self.iam.validate(self.profile)));
+        errors.extend(prefixed("webhooks", self.webhooks.validate()));
+        if database && self.database.is_sqlite() {
+            errors.extend(self.sqlite_conflicts());
+        }
+        errors
+    }
+
+    /// Features built on Postgres locking that can't run on a SQLite database
+    pub fn sqlite_conflicts(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.jobs.run_in_server {
+            errors.push(String::from("jobs.run_in_server: job workers need Postgres, turn off on SQLite"));
+        }
+        if self.scheduler.enabled {
+            errors.push(String::from("scheduler.enabled: the scheduler needs Postgres, turn off on SQLite"));
+        }
+        if self.rate_limit.backend == RateLimitBackend::Postgres {
+            errors.push(String::from("rate_limit.backend: postgres is not available on SQLite"));
+        }
+        if self.iam.session.backend == SessionBackend::Postgres {
+            errors.push(String::from("iam.session.backend: postgres is not available on SQLite"));
+        }
+        errors
+    }
+}
+
+fn prefixed(section: &str, errors: Vec<String>) -> Vec<String> {
+    errors
+        .into_iter()
+        .map(|e| format!("{}.{}", section, e))
+        .collect()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// How long readiness reports failing after a shutdown signal before draining starts
+    pub shutdown_readiness_delay_secs: u64,
+    /// How long in-flight requests get to finish once draining starts
+    pub drain_timeout_secs: u64,
+    /// Upper bound for each shutdown hook
+    pub shutdown_hook_timeout_secs: u64,
+    /// Upper bound for each dependency check of the health probes
+    pub health_check_timeout_secs: u64,
+    pub tls: TlsConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: String::from("127.0.0.1"),
+            port: 3000,
+            shutdown_readiness_delay_secs: 5,
+            drain_timeout_secs: 30,
+            shutdown_hook_timeout_secs: 10,
+            health_check_timeout_secs: 2,
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.host.trim().is_empty() {
+            errors.push(String::from("host: must be set"));
+        }
+        if self.port == 0 {
+            errors.push(String::from("port: must be between 1 and 65535"));
+        }
+        if self.shutdown_hook_timeout_secs == 0 {
+            errors.push(String::from("shutdown_hook_timeout_secs: must be at least 1"));
+        }
+        if self.health_check_timeout_secs == 0 {
+            errors.push(String::from("health_check_timeout_secs: must be at least 1"));
+        }
+        if self.tls.enabled {
+            errors.extend(self.tls.validate());
+            if self.tls.redirect_http_port == Some(self.port) {
+                errors.push(String::from("tls.redirect_http_port: must differ from port"));
+            }
+        }
+        errors
+    }
+
+    /// `host:port` to bind the server to
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// Whether clients must present a certificate signed by `client_ca_path`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+    /// Verify a certificate when one is sent, plain TLS clients are still accepted
+    #[default]
+    Optional,
+    Required,
+}
+
+/// HTTPS on the main listener, HTTP/2 is negotiated through ALPN
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    /// PEM certificate chain
+    pub cert_path: String,
+    /// PEM private key
+    pub key_path: String,
+    /// PEM bundle of CAs trusted to sign client certificates, enables mutual TLS
+    pub client_ca_path: Option<String>,
+    pub client_auth: ClientAuth,
+    /// How often to check the certificate files for changes, 0 disables reloading
+    pub reload_interval_secs: u64,
+    /// Drop connections that have not finished the TLS handshake in time
+    pub handshake_timeout_secs: u64,
+    /// Serve redirects to HTTPS on this port
+    pub redirect_http_port: Option<u16>,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            enabled: false,
+            cert_path: String::new(),
+            key_path: String::new(),
+            client_ca_path: None,
+            client_auth: ClientAuth::default(),
+            reload_interval_secs: 30,
+            handshake_timeout_secs: 10,
+            redirect_http_port: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        for (key, file) in [("cert_path", &self.cert_path), ("key_path", &self.key_path)] {
+            if file.is_empty() {
+                errors.push(format!("tls.{}: must be set when tls is enabled", key));
+            } else if !Path::new(file).is_file() {
+                errors.push(format!("tls.{}: {} does not exist", key, file));
+            }
+        }
+        if let Some(file) = &self.client_ca_path {
+            if !Path::new(file).is_file() {
+                errors.push(format!("tls.client_ca_path: {} does not exist", file));
+            }
+        }
+        if self.handshake_timeout_secs == 0 {
+            errors.push(String::from("tls.handshake_timeout_secs: must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// Primary database, read replicas and their connection pool settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub url: Secret,
+    /// Run pending migrations on startup
+    pub auto_migrate: bool,
+    /// Replicas serving read-only queries, each gets its own pool with the same settings
+    pub read_replica_urls: Vec<Secret>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout_secs: u64,
+    /// How long to wait for a free connection from the pool
+    pub acquire_timeout_secs: u64,
+    /// Close connections idle for longer than this, `None` keeps them open
+    pub idle_timeout_secs: Option<u64>,
+    /// Recycle connections older than this, `None` keeps them forever
+    pub max_lifetime_secs: Option<u64>,
+    /// Ping connections before handing them out
+    pub test_before_acquire: bool,
+    /// Log every statement through sqlx
+    pub sqlx_logging: bool,
+    /// `error`, `warn`, `info`, `debug` or `trace`
+    pub sqlx_logging_level: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: Secret::default(),
+            auto_migrate: false,
+            read_replica_urls: vec![],
+            max_connections: 10,
+            min_connections: 1,
+            connect_timeout_secs: 8,
+            acquire_timeout_secs: 8,
+            idle_timeout_secs: Some(600),
+            max_lifetime_secs: Some(1800),
+            test_before_acquire: true,
+            sqlx_logging: false,
+            sqlx_logging_level: String::from("debug"),
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Whether the primary is a SQLite database, e.g. `sqlite::memory:` or `sqlite://data.db?mode=rwc`
+    pub fn is_sqlite(&self) -> bool {
+        self.url.expose().starts_with("sqlite:")
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.url.is_empty() {
+            errors.push(String::from("url: must be set (DATABASE_URL)"));
+        }
+        if self.is_sqlite() && !cfg!(feature = "sqlite") {
+            errors.push(String::from("url: SQLite needs the `sqlite` feature"));
+        }
+        if self.is_sqlite() && !self.read_replica_urls.is_empty() {
+            errors.push(String::from("read_replica_urls: not supported on SQLite"));
+        }
+        if self.read_replica_urls.iter().any(|url| url.is_empty()) {
+            errors.push(String::from("read_replica_urls: must not contain empty urls"));
+        }
+        if self.max_connections == 0 {
+            errors.push(String::from("max_connections: must be at least 1"));
+        }
+        if self.min_connections > self.max_connections {
+            errors.push(String::from("min_connections: must not exceed max_connections"));
+        }
+        if log::LevelFilter::from_str(&self.sqlx_logging_level).is_err() {
+            errors.push(format!(
+                "sqlx_logging_level: unknown level {}",
+                self.sqlx_logging_level
+            ));
+        }
+        errors
+    }
+}
+
+/// Cross-origin requests, disabled while `allowed_origins` is empty
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Exact origins such as `https://app.example.com`, `*` allows any origin
+    pub allowed_origins: Vec<String>,
+    /// Send cookies and authorization headers, not allowed together with `*`
+    pub allow_credentials: bool,
+    /// How long browsers cache a preflight response
+    pub max_age_secs: u32,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: vec![],
+            allow_credentials: false,
+            max_age_secs: 600,
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        let any_origin = self.allowed_origins.iter().any(|origin| origin == "*");
+        if any_origin && self.allow_credentials {
+            errors.push(String::from("allow_credentials: can't be used with the `*` origin"));
+        }
+        for origin in self.allowed_origins.iter().filter(|origin| *origin != "*") {
+            if !(origin.starts_with("http://") || origin.starts_with("https://")) || origin.ends_with('/') {
+                errors.push(format!("allowed_origins: {} must be a scheme and host without path", origin));
+            }
+        }
+        errors
+    }
+}
+
+/// Headers added to every response, empty values are not sent
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SecurityHeadersConfig {
+    /// `Strict-Transport-Security` max age, only sent over HTTPS, 0 to disable
+    pub hsts_max_age_secs: u64,
+    /// Not sent on `/swagger`, the UI needs inline scripts
+    pub content_security_policy: String,
+    pub frame_options: String,
+    pub referrer_policy: String,
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        SecurityHeadersConfig {
+            hsts_max_age_secs: 31_536_000,
+            content_security_policy: String::from("default-src 'none'; frame-ancestors 'none'"),
+            frame_options: String::from("DENY"),
+            referrer_policy: String::from("no-referrer"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Largest accepted request body
+    pub max_body_bytes: usize,
+    /// Requests still running after this get a 503, 0 to disable
+    pub request_timeout_secs: u64,
+    /// Compress responses with zstd, brotli or gzip, as accepted by the client
+    pub compression: bool,
+    /// Smaller responses are sent as is
+    pub compression_min_bytes: usize,
+    pub cors: CorsConfig,
+    pub security_headers: SecurityHeadersConfig,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            max_body_bytes: 1024 * 1024,
+            request_timeout_secs: 30,
+            compression: true,
+            compression_min_bytes: 1024,
+            cors: CorsConfig::default(),
+            security_headers: SecurityHeadersConfig::default(),
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.max_body_bytes == 0 {
+            errors.push(String::from("max_body_bytes: must be greater than 0"));
+        }
+        errors.extend(self.cors.validate().into_iter().map(|e| format!("cors.{}", e)));
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log shippers
+    Json,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// Filter used when `RUST_LOG` is not set, same syntax, e.g. `info,sqlx=warn`
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            format: LogFormat::default(),
+            filter: String::from("info"),
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.filter) {
+            errors.push(format!("filter: {}", e));
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// Locale used when neither the request nor the user has a supported one
+    pub default_locale: String,
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        I18nConfig {
+            default_locale: String::from("en"),
+        }
+    }
+}
+
+impl I18nConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        let supported = supported_locales();
+        if !supported.contains(&self.default_locale.as_str()) {
+            errors.push(format!("default_locale: must be one of {}", supported.join(", ")));
+        }
+        errors
+    }
+}
+
+/// Where rate limit buckets are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    /// Per instance, limits multiply with the number of instances
+    #[default]
+    Memory,
+    /// Shared by every instance through the primary database
+    Postgres,
+}
+
+/// What a rate limit bucket is keyed by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    #[default]
+    Ip,
+    /// `pid` of the bearer token, the client IP for anonymous requests
+    User,
+    /// `X-Api-Key` header, the client IP when it is missing
+    ApiKey,
+}
+
+/// Token bucket of `requests` tokens refilled over `period_secs`, applied to the requests
+/// matching any of `paths`, `operations` or `tags`, or to every request when none are set
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitRule {
+    pub name: String,
+    /// Route paths as served, a trailing `*` matches any suffix, e.g. `/api/auth/*`
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// OpenAPI operation ids
+    #[serde(default)]
+    pub operations: Vec<String>,
+    /// OpenAPI tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub key: RateLimitKey,
+    pub requests: u32,
+    pub period_secs: u64,
+}
+
+impl RateLimitRule {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.name.trim().is_empty() {
+            errors.push(String::from("name: must be set"));
+        }
+        if self.requests == 0 {
+            errors.push(format!("{}.requests: must be greater than 0", self.name));
+        }
+        if self.period_secs == 0 {
+            errors.push(format!("{}.period_secs: must be greater than 0", self.name));
+        }
+        errors
+    }
+}
+
+/// Postgres buckets idle for a day are purged, which is only safe once they would be full again
+pub const POSTGRES_MAX_PERIOD_SECS: u64 = 86_400;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub backend: RateLimitBackend,
+    /// Take the client IP from `X-Forwarded-For`, only behind a proxy that sets it
+    pub trust_forwarded_for: bool,
+    pub rules: Vec<RateLimitRule>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            enabled: true,
+            backend: RateLimitBackend::default(),
+            trust_forwarded_for: false,
+            rules: vec![RateLimitRule {
+                name: String::from("auth"),
+                paths: vec![],
+                operations: vec![String::from("login"), String::from("register"), String::from("createSession")],
+                tags: vec![],
+                key: RateLimitKey::Ip,
+                requests: 10,
+                period_secs: 60,
+            }],
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        for rule in &self.rules {
+            errors.extend(rule.validate().into_iter().map(|e| format!("rules.{}", e)));
+            if self.backend == RateLimitBackend::Postgres && rule.period_secs > POSTGRES_MAX_PERIOD_SECS {
+                errors.push(format!(
+                    "rules.{}.period_secs: must be at most {} with the postgres backend",
+                    rule.name, POSTGRES_MAX_PERIOD_SECS
+                ));
+            }
+        }
+        let mut names: Vec<_> = self.rules.iter().map(|rule| rule.name.as_str()).collect();
+        names.sort_unstable();
+        if names.windows(2).any(|pair| pair[0] == pair[1]) {
+            errors.push(String::from("rules: names must be unique"));
+        }
+        errors
+    }
+}
+
+/// Background job workers, see `common::jobs`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JobsConfig {
+    /// Run workers inside the server, turn off when only the `worker` binary should run jobs
+    pub run_in_server: bool,
+    /// Jobs run at the same time by one process
+    pub concurrency: usize,
+    /// Wait between polls of an empty queue
+    pub poll_interval_ms: u64,
+    /// Attempts of a job before it is dead, unless set when enqueued
+    pub max_attempts: i32,
+    /// First retry delay, doubled on each attempt
+    pub backoff_base_secs: u64,
+    pub backoff_max_secs: u64,
+    /// Running jobs not finished after this are considered crashed and retried
+    pub lock_timeout_secs: u64,
+    /// The `job_queue` health check fails once the oldest due job waited longer than this
+    pub max_queue_delay_secs: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        JobsConfig {
+            run_in_server: true,
+            concurrency: 4,
+            poll_interval_ms: 1000,
+            max_attempts: 5,
+            backoff_base_secs: 10,
+            backoff_max_secs: 3600,
+            lock_timeout_secs: 600,
+            max_queue_delay_secs: 300,
+        }
+    }
+}
+
+impl JobsConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.concurrency == 0 {
+            errors.push(String::from("concurrency: must be at least 1"));
+        }
+        if self.poll_interval_ms == 0 {
+            errors.push(String::from("poll_interval_ms: must be greater than 0"));
+        }
+        if self.max_attempts < 1 {
+            errors.push(String::from("max_attempts: must be at least 1"));
+        }
+        if self.backoff_base_secs > self.backoff_max_secs {
+            errors.push(String::from("backoff_base_secs: must not exceed backoff_max_secs"));
+        }
+        if self.max_queue_delay_secs == 0 {
+            errors.push(String::from("max_queue_delay_secs: must be greater than 0"));
+        }
+        errors
+    }
+}
+
+/// Delivery of domain events from the outbox, see `common::events`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    /// Wait between polls of an empty outbox
+    pub poll_interval_ms: u64,
+    /// Events delivered per poll
+    pub batch_size: u64,
+    /// Deliveries of an event before it is left in the outbox with its `last_error`
+    pub max_attempts: i32,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        EventsConfig {
+            poll_interval_ms: 500,
+            batch_size: 100,
+            max_attempts: 10,
+        }
+    }
+}
+
+impl EventsConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.poll_interval_ms == 0 {
+            errors.push(String::from("poll_interval_ms: must be greater than 0"));
+        }
+        if self.batch_size == 0 {
+            errors.push(String::from("batch_size: must be at least 1"));
+        }
+        if self.max_attempts < 1 {
+            errors.push(String::from("max_attempts: must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// How emails leave the server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTransport {
+    #[default]
+    Smtp,
+    /// Only log the rendered message, for tests and setups without a mail server
+    Log,
+}
+
+/// TLS of the SMTP connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain text, only for local servers such as mailtutan
+    #[default]
+    None,
+    StartTls,
+    Tls,
+}
+
+/// Transactional emails, see `common::email`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    pub transport: EmailTransport,
+    /// Sender mailbox, e.g. `Server <no-reply@example.com>`
+    pub from: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_security: SmtpSecurity,
+    /// Empty for servers without authentication
+    pub smtp_username: String,
+    pub smtp_password: Secret,
+    pub smtp_timeout_secs: u64,
+    /// Attempts of the `send_email` job, retried with the `jobs` backoff
+    pub max_attempts: i32,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        EmailConfig {
+            transport: EmailTransport::default(),
+            from: String::from("Server <no-reply@localhost>"),
+            smtp_host: String::from("localhost"),
+            smtp_port: 1025,
+            smtp_security: SmtpSecurity::default(),
+            smtp_username: String::new(),
+            smtp_password: Secret::default(),
+            smtp_timeout_secs: 10,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl EmailConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.from.parse::<lettre::message::Mailbox>().is_err() {
+            errors.push(String::from("from: must be a mailbox such as `Server <no-reply@example.com>`"));
+        }
+        if self.transport == EmailTransport::Smtp && self.smtp_host.is_empty() {
+            errors.push(String::from("smtp_host: required by the smtp transport"));
+        }
+        if self.smtp_timeout_secs == 0 {
+            errors.push(String::from("smtp_timeout_secs: must be greater than 0"));
+        }
+        if self.max_attempts < 1 {
+            errors.push(String::from("max_attempts: must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// Recurring tasks, see `common::scheduler`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// Run scheduled tasks in this process, replicas take turns through advisory locks
+    pub enabled: bool,
+    /// Cron expressions replacing the default schedule of a task, by task name
+    pub schedules: HashMap<String, String>,
+    /// Finished jobs, run history and other records are deleted after this many days
+    pub retention_days: u32,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            enabled: true,
+            schedules: HashMap::new(),
+            retention_days: 30,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        for (task, expression) in &self.schedules {
+            if let Err(e) = parse_cron(expression) {
+                errors.push(format!("schedules.{}: {}", task, e));
+            }
+        }
+        if self.retention_days == 0 {
+            errors.push(String::from("retention_days: must be at least 1"));
+        }
+        errors
+    }
+}
+
+/// Parse a cron expression, the seconds field is optional
+pub fn parse_cron(expression: &str) -> Result<cron::Schedule, String> {
+    let expression = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+    cron::Schedule::from_str(&expression).map_err(|e| format!("invalid cron expression {}: {}", expression, e))
+}
+
+/// Transport used to reach the OpenTelemetry collector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    #[default]
+    Grpc,
+    /// Protobuf over HTTP
+    Http,
+}
+
+/// OpenTelemetry trace export
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Export spans over OTLP, logs are written either way
+    pub enabled: bool,
+    pub service_name: String,
+    /// `http://localhost:4317` for gRPC, `http://localhost:4318/v1/traces` for HTTP
+    pub otlp_endpoint: String,
+    pub protocol: OtlpProtocol,
+    /// Share of new traces to record, requests with a `traceparent` follow the caller's decision
+    pub sampling_ratio: f64,
+    pub export_timeout_secs: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            enabled: false,
+            service_name: String::from("server"),
+            otlp_endpoint: String::from("http://localhost:4317"),
+            protocol: OtlpProtocol::default(),
+            sampling_ratio: 1.0,
+            export_timeout_secs: 10,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.service_name.trim().is_empty() {
+            errors.push(String::from("service_name: must be set"));
+        }
+        if self.enabled && self.otlp_endpoint.trim().is_empty() {
+            errors.push(String::from("otlp_endpoint: must be set when telemetry is enabled"));
+        }
+        if !(0.0..=1.0).contains(&self.sampling_ratio) {
+            errors.push(format!(
+                "sampling_ratio: must be between 0 and 1, got {}",
+                self.sampling_ratio
+            ));
+        }
+        errors
+    }
+}
@@ -1,24 +1,250 @@
-use std::env;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-#[derive(Debug, Clone, Copy)]
-pub struct ConfigService;
+use figment::{
+    providers::{Env, Format, Serialized, Toml, Yaml},
+    Figment,
+};
+use migration::sea_orm::DbBackend;
+
+use crate::app::capabilities::{iam::config::IamConfig, webhooks::config::WebhooksConfig};
+
+use super::app_config::{AppConfig, DatabaseConfig, EmailConfig, EventsConfig, HttpConfig, I18nConfig, JobsConfig, LoggingConfig, Profile, RateLimitConfig, SchedulerConfig, ServerConfig, TelemetryConfig};
+
+/// Env var selecting the profile overlay
+const PROFILE_VAR: &str = "APP_PROFILE";
+/// Env var pointing at the base config file
+const CONFIG_FILE_VAR: &str = "CONFIG_FILE";
+/// Env var pointing at the directory holding `app.*` config files
+const CONFIG_DIR_VAR: &str = "CONFIG_DIR";
+/// Prefix of env vars overriding any key, `APP_SERVER__PORT=80` sets `server.port`
+const ENV_PREFIX: &str = "APP_";
+
+/// Flat env var names kept for existing `.env` files, mapped to their config key
+const LEGACY_ENV_VARS: &[(&str, &str)] = &[
+    ("HOST", "server.host"),
+    ("PORT", "server.port"),
+    ("DATABASE_URL", "database.url"),
+    ("AUTO_MIGRATE", "database.auto_migrate"),
+    ("LOG_FORMAT", "logging.format"),
+    ("DEFAULT_LOCALE", "i18n.default_locale"),
+    ("IAM_JWT_SECRET", "iam.jwt_secret"),
+    ("BCRYPT_SALT", "iam.bcrypt_cost"),
+    ("IAM_PASSWORD_MIN_LENGTH", "iam.password_policy.min_length"),
+    ("IAM_PASSWORD_MAX_LENGTH", "iam.password_policy.max_length"),
+    ("IAM_PASSWORD_REQUIRE_LOWERCASE", "iam.password_policy.require_lowercase"),
+    ("IAM_PASSWORD_REQUIRE_UPPERCASE", "iam.password_policy.require_uppercase"),
+    ("IAM_PASSWORD_REQUIRE_DIGIT", "iam.password_policy.require_digit"),
+    ("IAM_PASSWORD_REQUIRE_SYMBOL", "iam.password_policy.require_symbol"),
+    ("IAM_PASSWORD_DISALLOW_PERSONAL_INFO", "iam.password_policy.disallow_personal_info"),
+    ("IAM_PASSWORD_MIN_STRENGTH", "iam.password_policy.min_strength"),
+    ("IAM_BREACHED_PASSWORDS_FILE", "iam.password_policy.breached_passwords_file"),
+];
+
+/// Keys of secrets set through env vars, read as raw strings since figment parses env values
+/// and a secret such as `0123` or `1e3` would come out as a number
+const SECRET_KEYS: &[&str] = &["database.url", "iam.jwt_secret", "email.smtp_password"];
+
+/// Every problem found while loading config
+#[derive(Debug)]
+pub struct ConfigError {
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration ({} errors):", self.errors.len())?;
+        for error in &self.errors {
+            writeln!(f, "  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Holds the typed application config.
+///
+/// Sources are layered, later ones overriding earlier ones:
+/// 1. defaults
+/// 2. `$CONFIG_FILE`, or `$CONFIG_DIR/app.toml` / `app.yaml` (`CONFIG_DIR` defaults to `config`)
+/// 3. `$CONFIG_DIR/app.<profile>.toml` / `.yaml` where the profile comes from `APP_PROFILE`
+/// 4. the flat env vars listed in `LEGACY_ENV_VARS`
+/// 5. `APP_` prefixed env vars, `__` separating nested keys
+#[derive(Debug, Clone)]
+pub struct ConfigService {
+    config: Arc<AppConfig>,
+}
 
 impl ConfigService {
-    pub fn new() -> ConfigService {
+    /// Load and validate config from files and the environment
+    pub fn load() -> Result<ConfigService, ConfigError> {
         dotenvy::dotenv().ok();
-        ConfigService {}
-    }
-    pub fn get_env<T: std::str::FromStr + Default>(self, key: &str) -> T {
-        let result = env::var(key);
-        match result {
-            Ok(s) => match s.parse() {
-                Ok(val) => val,
-                Err(_) => {
-                    tracing::error!("Error parsing {}", key);
-                    String::from("").parse().unwrap_or_default()
+        let profile = match std::env::var(PROFILE_VAR) {
+            Ok(profile) => parse_profile(&profile)?,
+            Err(_) => Profile::default(),
+        };
+        let config_dir = PathBuf::from(
+            std::env::var(CONFIG_DIR_VAR).unwrap_or_else(|_| String::from("config")),
+        );
+        let base_file = match std::env::var(CONFIG_FILE_VAR) {
+            Ok(file) => {
+                let file = PathBuf::from(file);
+                if !file.is_file() {
+                    return Err(ConfigError {
+                        errors: vec![format!("{} {} does not exist", CONFIG_FILE_VAR, file.display())],
+                    });
                 }
-            },
-            Err(_) => String::from("").parse().unwrap_or_default()
+                Some(file)
+            }
+            Err(_) => None,
+        };
+        Self::load_from(profile, &config_dir, base_file.as_deref())
+    }
+
+    /// Load config for a profile from a config directory and the environment
+    pub fn load_from(profile: Profile, config_dir: &Path, base_file: Option<&Path>) -> Result<ConfigService, ConfigError> {
+        let mut figment = Figment::new();
+        figment = match base_file {
+            Some(file) => merge_file(figment, file),
+            None => merge_file(merge_file(figment, &config_dir.join("app.toml")), &config_dir.join("app.yaml")),
+        };
+        let overlay = format!("app.{}", profile.as_str());
+        figment = merge_file(figment, &config_dir.join(format!("{}.toml", overlay)));
+        figment = merge_file(figment, &config_dir.join(format!("{}.yaml", overlay)));
+
+        let legacy = Env::raw()
+            .only(&LEGACY_ENV_VARS.iter().map(|(var, _)| *var).collect::<Vec<_>>())
+            .map(|var| {
+                let key = LEGACY_ENV_VARS
+                    .iter()
+                    .find(|(legacy, _)| var == *legacy)
+                    .map(|(_, key)| *key)
+                    .unwrap_or_default();
+                key.into()
+            });
+        figment = raw_secrets(figment.merge(legacy), |key| {
+            LEGACY_ENV_VARS.iter().find(|(_, legacy)| *legacy == key).map(|(var, _)| var.to_string())
+        });
+        figment = raw_secrets(figment.merge(Env::prefixed(ENV_PREFIX).ignore(&["profile"]).split("__")), |key| {
+            Some(format!("{}{}", ENV_PREFIX, key.replace('.', "__")))
+        });
+
+        let mut config: AppConfig = figment.extract().map_err(|e| ConfigError {
+            errors: e.into_iter().map(|e| e.to_string()).collect(),
+        })?;
+        config.profile = profile;
+        Self::from_config(config)
+    }
+
+    /// Use an already built config, validating it
+    pub fn from_config(config: AppConfig) -> Result<ConfigService, ConfigError> {
+        let errors = config.validate();
+        if !errors.is_empty() {
+            return Err(ConfigError { errors });
+        }
+        Ok(ConfigService {
+            config: Arc::new(config),
+        })
+    }
+
+    /// Validate `config` for an app on an open `backend` connection: `database` is not used to
+    /// connect, so only the features the backend can't run are checked
+    pub fn for_connection(config: AppConfig, backend: DbBackend) -> Result<ConfigService, ConfigError> {
+        let mut errors = config.validate_without_database();
+        if backend == DbBackend::Sqlite {
+            errors.extend(config.sqlite_conflicts());
+        }
+        if !errors.is_empty() {
+            return Err(ConfigError { errors });
         }
+        Ok(ConfigService {
+            config: Arc::new(config),
+        })
+    }
+
+    pub fn get(&self) -> &AppConfig {
+        &self.config
+    }
+
+    pub fn server(&self) -> &ServerConfig {
+        &self.config.server
+    }
+
+    pub fn database(&self) -> &DatabaseConfig {
+        &self.config.database
+    }
+
+    pub fn i18n(&self) -> &I18nConfig {
+        &self.config.i18n
+    }
+
+    pub fn http(&self) -> &HttpConfig {
+        &self.config.http
+    }
+
+    pub fn rate_limit(&self) -> &RateLimitConfig {
+        &self.config.rate_limit
+    }
+
+    pub fn jobs(&self) -> &JobsConfig {
+        &self.config.jobs
+    }
+
+    pub fn email(&self) -> &EmailConfig {
+        &self.config.email
+    }
+
+    pub fn events(&self) -> &EventsConfig {
+        &self.config.events
+    }
+
+    pub fn scheduler(&self) -> &SchedulerConfig {
+        &self.config.scheduler
+    }
+
+    pub fn logging(&self) -> &LoggingConfig {
+        &self.config.logging
+    }
+
+    pub fn telemetry(&self) -> &TelemetryConfig {
+        &self.config.telemetry
+    }
+
+    pub fn iam(&self) -> &IamConfig {
+        &self.config.iam
+    }
+
+    pub fn webhooks(&self) -> &WebhooksConfig {
+        &self.config.webhooks
+    }
+}
+
+fn parse_profile(profile: &str) -> Result<Profile, ConfigError> {
+    match profile.to_lowercase().as_str() {
+        "dev" => Ok(Profile::Dev),
+        "test" => Ok(Profile::Test),
+        "prod" => Ok(Profile::Prod),
+        _ => Err(ConfigError {
+            errors: vec![format!("{}: unknown profile {}, expected dev, test or prod", PROFILE_VAR, profile)],
+        }),
+    }
+}
+
+/// Override the secret keys with the unparsed value of the env var `var_of` names, when set
+fn raw_secrets(figment: Figment, var_of: impl Fn(&str) -> Option<String>) -> Figment {
+    SECRET_KEYS.iter().fold(figment, |figment, key| match var_of(key).and_then(|var| Env::var(&var)) {
+        Some(value) => figment.merge(Serialized::default(key, value)),
+        None => figment,
+    })
+}
+
+fn merge_file(figment: Figment, file: &Path) -> Figment {
+    match file.extension().and_then(|e| e.to_str()) {
+        Some("yaml") | Some("yml") => figment.merge(Yaml::file(file)),
+        _ => figment.merge(Toml::file(file)),
     }
-}
\ No newline at end of file
+}
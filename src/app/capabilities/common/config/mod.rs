@@ -1 +1,6 @@
-pub mod config_service;
\ No newline at end of file
+pub mod config_service;
+pub mod app_config;
+pub mod secret;
+
+#[cfg(test)]
+mod config_service_test;
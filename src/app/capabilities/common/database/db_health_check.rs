@@ -0,0 +1,71 @@
+use migration::sea_orm::DatabaseConnection;
+
+use crate::app::capabilities::common::health::health_check::{CheckFuture, HealthCheck};
+
+use super::db_router::DbRouter;
+
+/// Pings one connection pool
+pub struct DbHealthCheck {
+    name: String,
+    db: DatabaseConnection,
+    required: bool,
+    /// The router and index of a replica, told whether it is up after each check
+    replica: Option<(DbRouter, usize)>,
+}
+
+impl DbHealthCheck {
+    /// The primary is required. Replicas are reported but optional: the router skips a replica
+    /// whose last check failed, and reads from the primary once every replica is down.
+    pub fn for_router(db: &DbRouter) -> Vec<DbHealthCheck> {
+        let mut checks = vec![DbHealthCheck {
+            name: String::from("database"),
+            db: db.writer().clone(),
+            required: true,
+            replica: None,
+        }];
+        for (i, replica) in db.replicas().iter().enumerate() {
+            checks.push(DbHealthCheck {
+                name: format!("database-replica-{}", i),
+                db: replica.clone(),
+                required: false,
+                replica: Some((db.clone(), i)),
+            });
+        }
+        checks
+    }
+}
+
+impl HealthCheck for DbHealthCheck {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn required(&self) -> bool {
+        self.required
+    }
+
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            // a check dropped on timeout leaves the replica marked down
+            let mut status = self.replica.as_ref().map(|replica| ReplicaStatus { replica, up: false });
+            let outcome = self.db.ping().await.map_err(|e| e.to_string());
+            if let Some(status) = &mut status {
+                status.up = outcome.is_ok();
+            }
+            outcome
+        })
+    }
+}
+
+/// Tells the router whether a replica is up once its check ends or is dropped
+struct ReplicaStatus<'a> {
+    replica: &'a (DbRouter, usize),
+    up: bool,
+}
+
+impl Drop for ReplicaStatus<'_> {
+    fn drop(&mut self) {
+        let (router, index) = self.replica;
+        router.set_replica_up(*index, self.up);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use migration::sea_orm;
+use poem_openapi::Object;
+use sea_orm::{ConnectOptions, DatabaseConnection, DbErr};
+
+use crate::app::capabilities::common::{
+    config::app_config::DatabaseConfig, telemetry::query_spans::record_query,
+};
+
+/// Usage of a single connection pool
+#[derive(Debug, Object, Clone, PartialEq, Eq)]
+pub struct PoolStats {
+    /// `primary` or `replica-<n>`
+    pub name: String,
+    /// Open connections, idle and in use
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+}
+
+/// Routes queries between the primary database and its read replicas.
+///
+/// Writes, and reads that must see them, go to `writer`. Read-only queries that can
+/// tolerate replication lag go to `reader`, which round robins over the replicas whose
+/// last health check passed and falls back to the primary when none are up or configured.
+#[derive(Clone)]
+pub struct DbRouter {
+    primary: DatabaseConnection,
+    replicas: Arc<Vec<DatabaseConnection>>,
+    replicas_up: Arc<Vec<AtomicBool>>,
+    next_replica: Arc<AtomicUsize>,
+}
+
+impl DbRouter {
+    pub fn new(primary: DatabaseConnection, replicas: Vec<DatabaseConnection>) -> Self {
+        Self {
+            primary,
+            replicas_up: Arc::new(replicas.iter().map(|_| AtomicBool::new(true)).collect()),
+            replicas: Arc::new(replicas),
+            next_replica: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Open the primary and replica pools described by the config, every query is traced
+    pub async fn connect(config: &DatabaseConfig) -> Result<DbRouter, DbErr> {
+        let mut primary = sea_orm::Database::connect(connect_options(config, config.url.expose())).await?;
+        primary.set_metric_callback(|info| record_query("primary", info));
+        let mut replicas = vec![];
+        for (i, url) in config.read_replica_urls.iter().enumerate() {
+            let mut replica = sea_orm::Database::connect(connect_options(config, url.expose())).await?;
+            let name = format!("replica-{}", i);
+            replica.set_metric_callback(move |info| record_query(&name, info));
+            replicas.push(replica);
+        }
+        Ok(Self::new(primary, replicas))
+    }
+
+    /// Connection for writes and read-your-writes queries
+    pub fn writer(&self) -> &DatabaseConnection {
+        &self.primary
+    }
+
+    /// Connection for read-only queries, the primary when every replica is down
+    pub fn reader(&self) -> &DatabaseConnection {
+        if self.replicas.is_empty() {
+            return &self.primary;
+        }
+        let next = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        (0..self.replicas.len())
+            .map(|offset| (next + offset) % self.replicas.len())
+            .find(|&i| self.replicas_up[i].load(Ordering::Relaxed))
+            .map_or(&self.primary, |i| &self.replicas[i])
+    }
+
+    /// Record the outcome of the last health check of a replica, reads skip it while it is down
+    pub fn set_replica_up(&self, index: usize, up: bool) {
+        if let Some(replica_up) = self.replicas_up.get(index) {
+            if replica_up.swap(up, Ordering::Relaxed) != up {
+                tracing::warn!("Database replica-{} is {}", index, if up { "back up" } else { "down, reads skip it" });
+            }
+        }
+    }
+
+    /// Replica connections, in config order
+    pub fn replicas(&self) -> &[DatabaseConnection] {
+        &self.replicas
+    }
+
+    /// Close every pool, waiting for checked out connections to be returned
+    pub async fn close(&self) {
+        for (name, db) in std::iter::once((String::from("primary"), &self.primary)).chain(
+            self.replicas
+                .iter()
+                .enumerate()
+                .map(|(i, replica)| (format!("replica-{}", i), replica)),
+        ) {
+            if let Err(e) = db.clone().close().await {
+                tracing::error!("Failed to close {} pool: {}", name, e);
+            }
+        }
+    }
+
+    /// Usage of every pool, primary first
+    pub fn stats(&self) -> Vec<PoolStats> {
+        let mut stats = vec![];
+        if let Some(s) = pool_stats(String::from("primary"), &self.primary) {
+            stats.push(s);
+        }
+        for (i, replica) in self.replicas.iter().enumerate() {
+            if let Some(s) = pool_stats(format!("replica-{}", i), replica) {
+                stats.push(s);
+            }
+        }
+        stats
+    }
+}
+
+fn connect_options(config: &DatabaseConfig, url: &str) -> ConnectOptions {
+    let mut opts = ConnectOptions::new(url);
+    opts.max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .test_before_acquire(config.test_before_acquire)
+        .sqlx_logging(config.sqlx_logging)
+        .sqlx_logging_level(log::LevelFilter::from_str(&config.sqlx_logging_level).unwrap_or(log::LevelFilter::Debug));
+    if is_memory_sqlite(url) {
+        // every connection opens its own in-memory database, keep exactly one open for the pool's lifetime
+        opts.max_connections(1).min_connections(1);
+        return opts;
+    }
+    if let Some(idle_timeout) = config.idle_timeout_secs {
+        opts.idle_timeout(Duration::from_secs(idle_timeout));
+    }
+    if let Some(max_lifetime) = config.max_lifetime_secs {
+        opts.max_lifetime(Duration::from_secs(max_lifetime));
+    }
+    opts
+}
+
+fn is_memory_sqlite(url: &str) -> bool {
+    url.starts_with("sqlite:") && (url.contains(":memory:") || url.contains("mode=memory"))
+}
+
+fn pool_stats(name: String, db: &DatabaseConnection) -> Option<PoolStats> {
+    match db {
+        DatabaseConnection::SqlxPostgresPoolConnection(_) => {
+            let pool = db.get_postgres_connection_pool();
+            let size = pool.size();
+            let idle = pool.num_idle() as u32;
+            Some(PoolStats {
+                name,
+                size,
+                idle,
+                in_use: size.saturating_sub(idle),
+                max_connections: pool.options().get_max_connections(),
+            })
+        }
+        #[cfg(feature = "sqlite")]
+        DatabaseConnection::SqlxSqlitePoolConnection(_) => {
+            let pool = db.get_sqlite_connection_pool();
+            let size = pool.size();
+            let idle = pool.num_idle() as u32;
+            Some(PoolStats {
+                name,
+                size,
+                idle,
+                in_use: size.saturating_sub(idle),
+                max_connections: pool.options().get_max_connections(),
+            })
+        }
+        _ => None,
+    }
+}
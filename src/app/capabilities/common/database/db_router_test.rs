@@ -0,0 +1,58 @@
+use migration::sea_orm::DatabaseConnection;
+
+use crate::app::capabilities::common::health::health_check::HealthCheck;
+
+use super::{db_health_check::DbHealthCheck, db_router::*};
+
+#[test]
+fn should_read_from_primary_without_replicas() {
+    let router = DbRouter::new(DatabaseConnection::Disconnected, vec![]);
+    assert!(std::ptr::eq(router.reader(), router.writer()));
+    assert_eq!(router.stats(), vec![]);
+}
+
+#[test]
+fn should_round_robin_reads_over_replicas() {
+    let router = DbRouter::new(
+        DatabaseConnection::Disconnected,
+        vec![DatabaseConnection::Disconnected, DatabaseConnection::Disconnected],
+    );
+    let first = router.reader() as *const DatabaseConnection;
+    let second = router.reader() as *const DatabaseConnection;
+    let third = router.reader() as *const DatabaseConnection;
+
+    assert!(!std::ptr::eq(first, router.writer()));
+    assert!(!std::ptr::eq(second, router.writer()));
+    assert!(!std::ptr::eq(first, second));
+    assert!(std::ptr::eq(first, third));
+}
+
+#[test]
+fn should_skip_replicas_that_are_down() {
+    let router = DbRouter::new(
+        DatabaseConnection::Disconnected,
+        vec![DatabaseConnection::Disconnected, DatabaseConnection::Disconnected],
+    );
+    let second = &router.replicas()[1] as *const DatabaseConnection;
+    router.set_replica_up(0, false);
+    for _ in 0..3 {
+        assert!(std::ptr::eq(router.reader(), second));
+    }
+
+    router.set_replica_up(1, false);
+    assert!(std::ptr::eq(router.reader(), router.writer()));
+
+    router.set_replica_up(0, true);
+    assert!(std::ptr::eq(router.reader(), &router.replicas()[0]));
+}
+
+#[tokio::test]
+async fn should_mark_replicas_failing_their_health_check_down() {
+    let router = DbRouter::new(DatabaseConnection::Disconnected, vec![DatabaseConnection::Disconnected]);
+    let checks = DbHealthCheck::for_router(&router);
+    assert_eq!(checks.iter().map(|c| (c.name(), c.required())).collect::<Vec<_>>(), vec![("database", true), ("database-replica-0", false)]);
+    assert!(!std::ptr::eq(router.reader(), router.writer()));
+
+    assert!(checks[1].check().await.is_err());
+    assert!(std::ptr::eq(router.reader(), router.writer()));
+}
@@ -0,0 +1,12 @@
+pub mod db_health_check;
+pub mod db_router;
+pub mod unit_of_work;
+
+#[cfg(test)]
+mod db_router_test;
+#[cfg(all(test, feature = "sqlite"))]
+mod sqlite_test;
+#[cfg(test)]
+pub mod test_postgres;
+#[cfg(test)]
+mod unit_of_work_test;
@@ -0,0 +1,85 @@
+use migration::{sea_orm::ConnectionTrait, MigratorTrait};
+
+use super::{
+    db_router::DbRouter,
+    unit_of_work::{is_unique_violation, UnitOfWork},
+};
+
+use crate::app::capabilities::{
+    common::config::{app_config::DatabaseConfig, secret::Secret},
+    iam::{
+        enums::auth_error::AuthError,
+        services::users::user_repository::{NewUser, SeaOrmUserRepository, UserRepository},
+    },
+};
+
+async fn migrated() -> DbRouter {
+    let config = DatabaseConfig {
+        url: Secret::new(String::from("sqlite::memory:")),
+        ..DatabaseConfig::default()
+    };
+    let db = DbRouter::connect(&config).await.unwrap();
+    migration::Migrator::up(db.writer(), None).await.unwrap();
+    db
+}
+
+fn new_user(email: &str) -> NewUser {
+    NewUser {
+        email: email.to_string(),
+        first_name: String::from("Jane"),
+        last_name: String::from("Doe"),
+        password: String::from("hash"),
+        locale: String::from("de"),
+    }
+}
+
+#[tokio::test]
+async fn should_migrate_and_store_users_in_memory() {
+    let db = migrated().await;
+    let users = SeaOrmUserRepository::new(db.clone());
+
+    let uow = UnitOfWork::begin(&db).await.unwrap();
+    let created = users.create(&uow, new_user("jane@example.com")).await.unwrap();
+    uow.commit().await.unwrap();
+    assert_eq!(created.password, None);
+    let found = users.find_by_pid(created.pid).await.unwrap().unwrap();
+    assert_eq!(found.email, "jane@example.com");
+    assert_eq!(found.locale, "de");
+
+    let events = db
+        .writer()
+        .query_one(migration::sea_orm::Statement::from_string(
+            db.writer().get_database_backend(),
+            "SELECT count(*) AS count FROM outbox WHERE event_type = 'user.registered'",
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(events.try_get::<i64>("", "count").unwrap(), 1);
+}
+
+#[tokio::test]
+async fn should_roll_back_users_of_an_uncommitted_unit_of_work() {
+    let db = migrated().await;
+    let users = SeaOrmUserRepository::new(db.clone());
+    let uow = users.begin().await.unwrap();
+    users.create(&uow, new_user("jane@example.com")).await.unwrap();
+    drop(uow);
+
+    assert_eq!(users.find_by_email(String::from("jane@example.com")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn should_map_duplicate_emails_to_conflicts() {
+    let db = migrated().await;
+    let users = SeaOrmUserRepository::new(db.clone());
+    let uow = UnitOfWork::begin(&db).await.unwrap();
+    users.create(&uow, new_user("jane@example.com")).await.unwrap();
+    uow.commit().await.unwrap();
+
+    let uow = UnitOfWork::begin(&db).await.unwrap();
+    assert!(users.find_by_email_for_write(&uow, String::from("jane@example.com")).await.unwrap().is_some());
+    let error = users.create(&uow, new_user("jane@example.com")).await.unwrap_err();
+    assert!(is_unique_violation(&error));
+    assert_eq!(AuthError::from(error), AuthError::Conflict);
+}
@@ -0,0 +1,43 @@
+use migration::sea_orm;
+use sea_orm::{DatabaseTransaction, DbErr, SqlErr, TransactionTrait};
+
+use super::db_router::DbRouter;
+
+/// A transaction on the primary shared by the services of one operation.
+///
+/// Services take a `&impl ConnectionTrait`, pass them `conn()` so their writes commit or roll back
+/// together. Dropping the unit of work without `commit` rolls it back.
+pub struct UnitOfWork {
+    /// `None` when detached from any database
+    txn: Option<DatabaseTransaction>,
+}
+
+impl UnitOfWork {
+    pub async fn begin(db: &DbRouter) -> Result<Self, DbErr> {
+        Ok(Self {
+            txn: Some(db.writer().begin().await?),
+        })
+    }
+
+    /// Unit of work without a transaction, for repository mocks that never call `conn()`
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        Self { txn: None }
+    }
+
+    pub fn conn(&self) -> &DatabaseTransaction {
+        self.txn.as_ref().expect("a detached unit of work has no connection")
+    }
+
+    pub async fn commit(self) -> Result<(), DbErr> {
+        match self.txn {
+            Some(txn) => txn.commit().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether `e` is a unique index violation, e.g. a concurrent insert of the same email
+pub fn is_unique_violation(e: &DbErr) -> bool {
+    matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_)))
+}
@@ -0,0 +1,46 @@
+use migration::sea_orm::{ConnectionTrait, DbErr};
+
+use crate::app::capabilities::iam::enums::auth_error::AuthError;
+
+use super::{
+    db_router::DbRouter,
+    test_postgres::test_postgres,
+    unit_of_work::{is_unique_violation, UnitOfWork},
+};
+
+/// Insert the same email twice in one unit of work, returning the error of the second insert
+async fn insert_duplicate(db: &DbRouter) -> DbErr {
+    let uow = UnitOfWork::begin(db).await.unwrap();
+    let conn = uow.conn();
+    conn.execute_unprepared("CREATE TABLE emails (email TEXT NOT NULL UNIQUE)").await.unwrap();
+    conn.execute_unprepared("INSERT INTO emails (email) VALUES ('jane@example.com')").await.unwrap();
+    conn.execute_unprepared("INSERT INTO emails (email) VALUES ('jane@example.com')").await.unwrap_err()
+}
+
+#[test]
+fn should_not_treat_other_errors_as_unique_violations() {
+    assert!(!is_unique_violation(&DbErr::RecordNotInserted));
+    assert!(!is_unique_violation(&DbErr::Custom("duplicate key".to_string())));
+}
+
+#[tokio::test]
+async fn should_map_duplicate_inserts_to_conflicts_on_postgres() {
+    let Some(db) = test_postgres().await else { return };
+    let error = insert_duplicate(&db).await;
+    assert!(is_unique_violation(&error), "{:?}", error);
+    assert_eq!(AuthError::from(error), AuthError::Conflict);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn should_map_duplicate_inserts_to_conflicts_on_sqlite() {
+    use crate::app::capabilities::common::config::{app_config::DatabaseConfig, secret::Secret};
+
+    let config = DatabaseConfig {
+        url: Secret::new(String::from("sqlite::memory:")),
+        ..DatabaseConfig::default()
+    };
+    let error = insert_duplicate(&DbRouter::connect(&config).await.unwrap()).await;
+    assert!(is_unique_violation(&error), "{:?}", error);
+    assert_eq!(AuthError::from(error), AuthError::Conflict);
+}
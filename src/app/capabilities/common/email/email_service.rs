@@ -0,0 +1,58 @@
+use migration::sea_orm::{ConnectionTrait, DbErr};
+use unic_langid::LanguageIdentifier;
+
+use crate::app::capabilities::common::{
+    config::app_config::EmailConfig,
+    jobs::job_service::{EnqueueOptions, JobService},
+};
+
+use super::{
+    email_templates::{EmailError, EmailTemplates, RenderedEmail},
+    mailer::Mailer,
+    send_email_job::{SendEmail, SendEmailJob},
+};
+
+/// Transactional emails, queued as `send_email` jobs
+#[derive(Clone)]
+pub struct EmailService {
+    templates: EmailTemplates,
+    mailer: Mailer,
+    jobs: JobService,
+    max_attempts: i32,
+}
+
+impl EmailService {
+    pub fn new(templates: EmailTemplates, mailer: Mailer, jobs: JobService, config: &EmailConfig) -> Self {
+        Self {
+            templates,
+            mailer,
+            jobs,
+            max_attempts: config.max_attempts,
+        }
+    }
+
+    /// Queue `email` on `conn`, pass a transaction to send it only if the transaction commits
+    pub async fn enqueue_in<C: ConnectionTrait>(&self, conn: &C, email: &SendEmail) -> Result<(), DbErr> {
+        let options = EnqueueOptions {
+            max_attempts: Some(self.max_attempts),
+            ..EnqueueOptions::default()
+        };
+        self.jobs.enqueue_in::<SendEmailJob, _>(conn, email, options).await.map(|_| ())
+    }
+
+    /// Render and send now, in the recipient's locale or the default one when it isn't supported
+    pub async fn send(&self, email: &SendEmail) -> Result<(), String> {
+        let i18n = self.templates.i18n();
+        let locale = i18n.find(&email.locale).unwrap_or_else(|| i18n.default_locale().clone());
+        let rendered = self
+            .templates
+            .render(&email.email, &locale, &email.args)
+            .map_err(|e| e.to_string())?;
+        self.mailer.send(&email.to, &rendered).await
+    }
+
+    /// `email` rendered with sample arguments
+    pub fn preview(&self, email: &str, locale: &LanguageIdentifier) -> Result<RenderedEmail, EmailError> {
+        self.templates.preview(email, locale)
+    }
+}
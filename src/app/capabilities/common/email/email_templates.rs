@@ -0,0 +1,141 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use minijinja::{context, Environment, Value};
+use poem_openapi::Object;
+use unic_langid::LanguageIdentifier;
+
+use crate::app::capabilities::common::i18n::i18n_service::I18nService;
+
+/// Template files shipped with the server, `<email>.html` and `<email>.txt` extend the layouts.
+/// A `<email>.<locale>.html` file replaces the default one for a locale.
+const SOURCES: &[(&str, &str)] = &[
+    ("layout.html", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email/layout.html"))),
+    ("layout.txt", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email/layout.txt"))),
+    ("welcome.html", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email/welcome.html"))),
+    ("welcome.txt", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email/welcome.txt"))),
+    (
+        "password_changed.html",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email/password_changed.html")),
+    ),
+    (
+        "password_changed.txt",
+        include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email/password_changed.txt")),
+    ),
+];
+
+/// Emails by name with the sample arguments used by previews
+pub const EMAILS: &[(&str, &[(&str, &str)])] = &[
+    ("welcome", &[("first_name", "Jane"), ("email", "jane@example.com")]),
+    ("password_changed", &[("first_name", "Jane")]),
+];
+
+#[derive(Debug)]
+pub enum EmailError {
+    /// No catalogue messages or template for the email
+    UnknownEmail(String),
+    Render(minijinja::Error),
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmailError::UnknownEmail(name) => write!(f, "unknown email {}", name),
+            EmailError::Render(e) => write!(f, "failed to render email: {:#}", e),
+        }
+    }
+}
+
+impl From<minijinja::Error> for EmailError {
+    fn from(e: minijinja::Error) -> Self {
+        EmailError::Render(e)
+    }
+}
+
+/// Both parts of an email in one locale
+#[derive(Debug, Clone, PartialEq, Eq, Object)]
+pub struct RenderedEmail {
+    pub email: String,
+    pub locale: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Renders transactional emails: subject and body come from the `email-<name>-*` messages of the
+/// catalogues, the templates lay them out as HTML and plain text
+#[derive(Clone)]
+pub struct EmailTemplates {
+    env: Arc<Environment<'static>>,
+    i18n: I18nService,
+}
+
+impl EmailTemplates {
+    pub fn new(i18n: I18nService) -> Self {
+        Self::with_sources(i18n, SOURCES)
+    }
+
+    /// Templates from `sources`, file name to source
+    pub fn with_sources(i18n: I18nService, sources: &[(&'static str, &'static str)]) -> Self {
+        let mut env = Environment::new();
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        for (name, source) in sources {
+            env.add_template(name, source)
+                .unwrap_or_else(|e| panic!("Invalid email template {}: {:#}", name, e));
+        }
+        Self { env: Arc::new(env), i18n }
+    }
+
+    pub fn i18n(&self) -> &I18nService {
+        &self.i18n
+    }
+
+    /// Render `email` in `locale`, `args` are passed to the messages and the templates
+    pub fn render(&self, email: &str, locale: &LanguageIdentifier, args: &HashMap<String, String>) -> Result<RenderedEmail, EmailError> {
+        let localized = self
+            .i18n
+            .email(locale, email, args)
+            .ok_or_else(|| EmailError::UnknownEmail(email.to_string()))?;
+        let paragraphs: Vec<&str> = localized.body.split("\n\n").map(str::trim).filter(|p| !p.is_empty()).collect();
+        let ctx = context! {
+            locale => locale.to_string(),
+            subject => &localized.subject,
+            body => &localized.body,
+            paragraphs => paragraphs,
+            footer => self.i18n.message(locale, "email-footer", None),
+            ..Value::from_serialize(args)
+        };
+        let html = self.template(email, locale, "html")?.render(&ctx)?;
+        let text = self.template(email, locale, "txt")?.render(&ctx)?;
+        Ok(RenderedEmail {
+            email: email.to_string(),
+            locale: locale.to_string(),
+            subject: localized.subject,
+            html,
+            text,
+        })
+    }
+
+    /// Render `email` with its sample arguments
+    pub fn preview(&self, email: &str, locale: &LanguageIdentifier) -> Result<RenderedEmail, EmailError> {
+        let (_, sample) = EMAILS
+            .iter()
+            .find(|(name, _)| *name == email)
+            .ok_or_else(|| EmailError::UnknownEmail(email.to_string()))?;
+        let args = sample.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        self.render(email, locale, &args)
+    }
+
+    /// Most specific template of `email` for `locale`: `welcome.de-AT.html`, `welcome.de.html`, `welcome.html`
+    fn template(&self, email: &str, locale: &LanguageIdentifier, extension: &str) -> Result<minijinja::Template<'_, '_>, EmailError> {
+        let candidates = [
+            format!("{}.{}.{}", email, locale, extension),
+            format!("{}.{}.{}", email, locale.language, extension),
+            format!("{}.{}", email, extension),
+        ];
+        candidates
+            .iter()
+            .find_map(|name| self.env.get_template(name).ok())
+            .ok_or_else(|| EmailError::UnknownEmail(email.to_string()))
+    }
+}
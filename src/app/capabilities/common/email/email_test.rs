@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::oneshot,
+};
+
+use crate::app::capabilities::common::{
+    config::app_config::{EmailConfig, EmailTransport},
+    health::health_check::HealthCheck,
+    i18n::i18n_service::{supported_locales, I18nService},
+};
+
+use super::{
+    email_templates::{EmailError, EmailTemplates, EMAILS},
+    mailer::Mailer,
+    mailer_health_check::MailerHealthCheck,
+};
+
+fn args(first_name: &str) -> HashMap<String, String> {
+    HashMap::from([
+        (String::from("first_name"), first_name.to_string()),
+        (String::from("email"), String::from("jane@example.com")),
+    ])
+}
+
+#[test]
+fn should_render_html_and_text_parts_in_the_locale() {
+    let templates = EmailTemplates::new(I18nService::new("en"));
+    let de = "de".parse().unwrap();
+
+    let email = templates.render("welcome", &de, &args("<b>Jane</b>")).unwrap();
+    assert_eq!(email.subject, "Willkommen, <b>Jane</b>!");
+    assert!(email.html.starts_with("<!DOCTYPE html>"));
+    assert!(email.html.contains(r#"<html lang="de">"#));
+    assert!(email.html.contains("Hallo &lt;b&gt;Jane&lt;&#x2f;b&gt;,</p>"));
+    assert!(email.html.contains("Du erhältst diese E-Mail"));
+    assert!(email.text.starts_with("Hallo <b>Jane</b>,\n\n"));
+    assert!(email.text.ends_with("--\nDu erhältst diese E-Mail wegen deines Kontos bei unserem Dienst."));
+
+    let error = templates.render("invoice", &de, &args("Jane")).unwrap_err();
+    assert!(matches!(error, EmailError::UnknownEmail(name) if name == "invoice"));
+}
+
+#[test]
+fn should_prefer_templates_of_the_locale() {
+    let sources = [
+        ("layout.txt", "{% block content %}{% endblock %}"),
+        ("welcome.txt", "{% extends \"layout.txt\" %}{% block content %}default {{ first_name }}{% endblock %}"),
+        ("welcome.de.txt", "{% extends \"layout.txt\" %}{% block content %}deutsch {{ first_name }}{% endblock %}"),
+        ("welcome.html", "<p>{{ subject }}</p>"),
+    ];
+    let templates = EmailTemplates::with_sources(I18nService::new("en"), &sources);
+    let render = |locale: &str| templates.render("welcome", &locale.parse().unwrap(), &args("Jane")).unwrap();
+
+    assert_eq!(render("en").text, "default Jane");
+    assert_eq!(render("de").text, "deutsch Jane");
+    assert_eq!(render("de-AT").text, "deutsch Jane");
+    assert_eq!(render("de").html, "<p>Willkommen, Jane!</p>");
+}
+
+#[test]
+fn should_preview_every_email_in_every_locale() {
+    let templates = EmailTemplates::new(I18nService::new("en"));
+    for (email, _) in EMAILS {
+        for locale in supported_locales() {
+            let preview = templates.preview(email, &locale.parse().unwrap()).unwrap();
+            assert!(!preview.subject.is_empty(), "{} in {}", email, locale);
+            assert!(preview.text.contains("Jane"), "{} in {}", email, locale);
+        }
+    }
+    assert!(templates.preview("invoice", &"en".parse().unwrap()).is_err());
+}
+
+/// Accept one message like an SMTP server without authentication, sending back its data
+async fn smtp_server() -> (u16, oneshot::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (sender, receiver) = oneshot::channel();
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 localhost ESMTP\r\n").await.unwrap();
+        let mut data = None::<String>;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if let Some(message) = data.as_mut() {
+                if line == "." {
+                    sender.send(data.take().unwrap()).ok();
+                    writer.write_all(b"250 queued\r\n").await.unwrap();
+                    return;
+                }
+                message.push_str(&line);
+                message.push('\n');
+                continue;
+            }
+            let reply: &[u8] = match line.split(' ').next().unwrap_or_default().to_uppercase().as_str() {
+                "EHLO" | "HELO" => b"250 localhost\r\n",
+                "DATA" => {
+                    data = Some(String::new());
+                    b"354 end with .\r\n"
+                }
+                _ => b"250 ok\r\n",
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+    });
+    (port, receiver)
+}
+
+#[tokio::test]
+async fn should_send_multipart_emails_over_smtp() {
+    let (port, received) = smtp_server().await;
+    let config = EmailConfig {
+        smtp_host: String::from("127.0.0.1"),
+        smtp_port: port,
+        ..EmailConfig::default()
+    };
+    let templates = EmailTemplates::new(I18nService::new("en"));
+    let email = templates.render("welcome", &"en".parse().unwrap(), &args("Jane")).unwrap();
+
+    Mailer::new(&config).unwrap().send("jane@example.com", &email).await.unwrap();
+    let message = received.await.unwrap();
+    assert!(message.contains("From: Server <no-reply@localhost>"));
+    assert!(message.contains("To: jane@example.com"));
+    assert!(message.contains("Subject: Welcome, Jane!"));
+    assert!(message.contains("Content-Type: multipart/alternative"));
+    assert!(message.contains("Content-Type: text/plain; charset=utf-8"));
+    assert!(message.contains("Content-Type: text/html; charset=utf-8"));
+}
+
+#[tokio::test]
+async fn should_check_the_smtp_connection() {
+    let (port, _) = smtp_server().await;
+    let config = EmailConfig {
+        smtp_host: String::from("127.0.0.1"),
+        smtp_port: port,
+        ..EmailConfig::default()
+    };
+    let check = MailerHealthCheck::new(Mailer::new(&config).unwrap());
+    assert_eq!(check.name(), "smtp");
+    assert!(!check.required());
+    assert_eq!(check.check().await, Ok(()));
+
+    // nothing listens on port 1
+    let config = EmailConfig { smtp_port: 1, ..config };
+    assert!(MailerHealthCheck::new(Mailer::new(&config).unwrap()).check().await.is_err());
+
+    let config = EmailConfig {
+        transport: EmailTransport::Log,
+        ..config
+    };
+    assert_eq!(MailerHealthCheck::new(Mailer::new(&config).unwrap()).check().await, Ok(()));
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use lettre::{
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::app::capabilities::common::config::app_config::{EmailConfig, EmailTransport, SmtpSecurity};
+
+use super::email_templates::RenderedEmail;
+
+/// Sends rendered emails over SMTP, or only logs them with the `log` transport
+#[derive(Clone)]
+pub struct Mailer {
+    smtp: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    from: Mailbox,
+}
+
+impl Mailer {
+    pub fn new(config: &EmailConfig) -> Result<Self, String> {
+        let from = config.from.parse().map_err(|e| format!("Invalid sender {}: {}", config.from, e))?;
+        let smtp = match config.transport {
+            EmailTransport::Log => None,
+            EmailTransport::Smtp => {
+                let builder = match config.smtp_security {
+                    SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host),
+                    SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                        .map_err(|e| e.to_string())?,
+                    SmtpSecurity::Tls => {
+                        AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host).map_err(|e| e.to_string())?
+                    }
+                };
+                let mut builder = builder
+                    .port(config.smtp_port)
+                    .timeout(Some(Duration::from_secs(config.smtp_timeout_secs)));
+                if !config.smtp_username.is_empty() {
+                    builder = builder.credentials(Credentials::new(
+                        config.smtp_username.clone(),
+                        config.smtp_password.expose().to_string(),
+                    ));
+                }
+                Some(builder.build())
+            }
+        };
+        Ok(Self { smtp, from })
+    }
+
+    /// Connect and greet the SMTP server, there is nothing to check with the `log` transport
+    pub async fn test_connection(&self) -> Result<(), String> {
+        match &self.smtp {
+            Some(smtp) => match smtp.test_connection().await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(String::from("SMTP server closed the connection")),
+                Err(e) => Err(e.to_string()),
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Send `email` to `to` as a multipart message with the text and HTML parts
+    pub async fn send(&self, to: &str, email: &RenderedEmail) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse().map_err(|e| format!("Invalid recipient: {}", e))?)
+            .subject(&email.subject)
+            .multipart(MultiPart::alternative_plain_html(email.text.clone(), email.html.clone()))
+            .map_err(|e| e.to_string())?;
+        match &self.smtp {
+            Some(smtp) => smtp.send(message).await.map(|_| ()).map_err(|e| e.to_string()),
+            None => {
+                tracing::info!(email = %email.email, locale = %email.locale, subject = %email.subject, "Email not sent, log transport:\n{}", email.text);
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use crate::app::capabilities::common::health::health_check::{CheckFuture, HealthCheck};
+
+use super::mailer::Mailer;
+
+/// Connects to the SMTP server of the `smtp` transport. Optional, emails are queued and retried
+/// while it is down.
+pub struct MailerHealthCheck {
+    mailer: Mailer,
+}
+
+impl MailerHealthCheck {
+    pub fn new(mailer: Mailer) -> Self {
+        Self { mailer }
+    }
+}
+
+impl HealthCheck for MailerHealthCheck {
+    fn name(&self) -> &str {
+        "smtp"
+    }
+
+    fn required(&self) -> bool {
+        false
+    }
+
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(self.mailer.test_connection())
+    }
+}
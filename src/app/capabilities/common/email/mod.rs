@@ -0,0 +1,8 @@
+pub mod email_service;
+pub mod email_templates;
+pub mod mailer;
+pub mod mailer_health_check;
+pub mod send_email_job;
+
+#[cfg(test)]
+mod email_test;
@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::capabilities::common::jobs::job_handler::{JobFuture, JobHandler};
+
+use super::email_service::EmailService;
+
+/// An email to render and send, rendering happens in the job so template fixes apply to queued emails
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendEmail {
+    pub to: String,
+    /// Name of the email, e.g. `welcome`
+    pub email: String,
+    pub locale: String,
+    pub args: HashMap<String, String>,
+}
+
+pub struct SendEmailJob {
+    pub emails: EmailService,
+}
+
+impl JobHandler for SendEmailJob {
+    const KIND: &'static str = "send_email";
+    type Payload = SendEmail;
+
+    fn handle(&self, payload: SendEmail) -> JobFuture<'_> {
+        Box::pin(async move { self.emails.send(&payload).await })
+    }
+}
@@ -0,0 +1,40 @@
+use migration::sea_orm::prelude::Uuid;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Something that happened in a capability that others may react to.
+///
+/// Events are recorded with `outbox::record` in the transaction of the change they describe.
+pub trait DomainEvent: Serialize + DeserializeOwned + Send + Sync {
+    /// Stored in the `event_type` column, never rename it while events of the type are undelivered
+    const EVENT_TYPE: &'static str;
+}
+
+/// An event as stored in the outbox and handed to subscribers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    pub id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    /// RFC 3339
+    pub occurred_at: String,
+}
+
+impl EventEnvelope {
+    pub fn new<E: DomainEvent>(event: &E) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            event_type: E::EVENT_TYPE.to_string(),
+            payload: serde_json::to_value(event)?,
+            occurred_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        })
+    }
+
+    pub fn is<E: DomainEvent>(&self) -> bool {
+        self.event_type == E::EVENT_TYPE
+    }
+
+    /// The typed event, `None` when the envelope holds another type
+    pub fn decode<E: DomainEvent>(&self) -> Option<Result<E, serde_json::Error>> {
+        self.is::<E>().then(|| serde_json::from_value(self.payload.clone()))
+    }
+}
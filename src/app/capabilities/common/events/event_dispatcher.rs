@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use migration::sea_orm::{DbErr, TransactionTrait};
+use prometheus::IntCounterVec;
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::app::capabilities::common::metrics::metrics_service::MetricsService;
+
+use super::{
+    domain_event::EventEnvelope,
+    event_subscriber::{EventSubscriber, EventSubscribers},
+    outbox::{self, OutboxService},
+};
+
+/// Polls the `outbox` table and delivers events to subscribers, at least once.
+///
+/// An event leaves the outbox once every interested subscriber handled it. Failed deliveries are
+/// retried with backoff, subscribers that already handled the event are skipped.
+pub struct EventDispatcher {
+    outbox: OutboxService,
+    subscribers: EventSubscribers,
+    delivered: IntCounterVec,
+}
+
+/// Stops a spawned dispatcher
+pub struct DispatcherHandle {
+    stop: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl DispatcherHandle {
+    /// Stop polling and wait for the current batch to finish
+    pub async fn stop(self) {
+        let _ = self.stop.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("Event dispatcher failed: {}", e);
+        }
+    }
+}
+
+impl EventDispatcher {
+    pub fn new(outbox: OutboxService, subscribers: EventSubscribers, metrics: &MetricsService) -> Self {
+        Self {
+            outbox,
+            subscribers,
+            delivered: metrics.counter_vec(
+                "events_delivered_total",
+                "Delivery attempts of outbox events",
+                &["event_type", "outcome"],
+            ),
+        }
+    }
+
+    pub fn spawn(self) -> DispatcherHandle {
+        let (stop, stopped) = watch::channel(false);
+        let task = tokio::spawn(self.run(stopped));
+        DispatcherHandle { stop, task }
+    }
+
+    async fn run(self, mut stopped: watch::Receiver<bool>) {
+        let poll_interval = Duration::from_millis(self.outbox.config().poll_interval_ms);
+        let batch_size = self.outbox.config().batch_size as usize;
+        tracing::info!(subscribers = ?self.subscribers.names(), "Event dispatcher started");
+
+        while !*stopped.borrow() {
+            let full_batch = match self.dispatch_batch().await {
+                Ok(count) => count >= batch_size,
+                Err(e) => {
+                    tracing::error!("Failed to dispatch events: {}", e);
+                    false
+                }
+            };
+            if full_batch {
+                continue;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(poll_interval) => {},
+                _ = stopped.changed() => {},
+            }
+        }
+        tracing::info!("Event dispatcher stopped");
+    }
+
+    /// Deliver the next due events, returns how many were attempted
+    pub async fn dispatch_batch(&self) -> Result<usize, DbErr> {
+        let txn = self.outbox.db().writer().begin().await?;
+        let events = outbox::lock_pending(&txn, self.outbox.config()).await?;
+        for event in &events {
+            let span = tracing::info_span!("event", id = %event.id, event_type = %event.event_type);
+            let outcome = match self.deliver(event).await {
+                Ok(_) => {
+                    outbox::mark_dispatched(&txn, event.id).await?;
+                    "delivered"
+                }
+                Err(error) => {
+                    span.in_scope(|| tracing::warn!(error, "Event delivery failed, retrying"));
+                    outbox::mark_failed(&txn, event.id, &error).await?;
+                    "failed"
+                }
+            };
+            self.delivered.with_label_values(&[&event.event_type, outcome]).inc();
+        }
+        txn.commit().await?;
+        Ok(events.len())
+    }
+
+    /// Hand `event` to every interested subscriber, failures are collected so one subscriber
+    /// doesn't hold back the others
+    async fn deliver(&self, event: &EventEnvelope) -> Result<(), String> {
+        let mut errors = vec![];
+        for subscriber in self.subscribers.for_event(&event.event_type) {
+            if let Err(e) = self.deliver_to(subscriber.as_ref(), event).await {
+                errors.push(format!("{}: {}", subscriber.name(), e));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join("; ")),
+        }
+    }
+
+    async fn deliver_to(&self, subscriber: &dyn EventSubscriber, event: &EventEnvelope) -> Result<(), String> {
+        let txn = self.outbox.db().writer().begin().await.map_err(|e| e.to_string())?;
+        let first = outbox::claim_delivery(&txn, subscriber.name(), event.id)
+            .await
+            .map_err(|e| e.to_string())?;
+        if !first {
+            return txn.rollback().await.map_err(|e| e.to_string());
+        }
+        if let Err(e) = subscriber.handle(&txn, event).await {
+            if let Err(rollback) = txn.rollback().await {
+                tracing::error!("Failed to roll back event delivery: {}", rollback);
+            }
+            return Err(e);
+        }
+        txn.commit().await.map_err(|e| e.to_string())
+    }
+}
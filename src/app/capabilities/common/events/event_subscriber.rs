@@ -0,0 +1,98 @@
+use std::{future::Future, marker::PhantomData, pin::Pin, sync::Arc};
+
+use migration::sea_orm::DatabaseTransaction;
+
+use crate::app::capabilities::common::jobs::{
+    job_handler::JobHandler,
+    job_service::{EnqueueOptions, JobService},
+};
+
+use super::domain_event::EventEnvelope;
+
+pub type EventFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Reacts to domain events in-process.
+///
+/// Each event is handed to a subscriber at most once per successful delivery: the dispatcher
+/// records it in `processed_events` within `txn`, so writes made through `txn` commit together
+/// with that record. An `Err` rolls both back and the event is delivered again later.
+pub trait EventSubscriber: Send + Sync + 'static {
+    /// Recorded in `processed_events`, never rename it while events are undelivered
+    fn name(&self) -> &str;
+
+    /// Event types delivered to the subscriber, every type when empty
+    fn event_types(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn handle<'a>(&'a self, txn: &'a DatabaseTransaction, event: &'a EventEnvelope) -> EventFuture<'a>;
+}
+
+/// Subscribers by name, filled in at bootstrap
+#[derive(Default, Clone)]
+pub struct EventSubscribers {
+    subscribers: Vec<Arc<dyn EventSubscriber>>,
+}
+
+impl EventSubscribers {
+    pub fn subscribe<S: EventSubscriber>(&mut self, subscriber: S) -> &mut Self {
+        if self.subscribers.iter().any(|s| s.name() == subscriber.name()) {
+            tracing::warn!("Event subscriber {} registered twice, keeping the last one", subscriber.name());
+            self.subscribers.retain(|s| s.name() != subscriber.name());
+        }
+        self.subscribers.push(Arc::new(subscriber));
+        self
+    }
+
+    /// Queue a job of `H` for every event of `event_types`, the job is created once per event
+    pub fn forward_to_job<H>(&mut self, jobs: JobService, event_types: &[&'static str]) -> &mut Self
+    where
+        H: JobHandler<Payload = EventEnvelope>,
+    {
+        self.subscribe(JobForwarder::<H> {
+            name: format!("job:{}", H::KIND),
+            jobs,
+            event_types: event_types.to_vec(),
+            handler: PhantomData,
+        })
+    }
+
+    /// Subscribers interested in `event_type`
+    pub fn for_event<'a>(&'a self, event_type: &'a str) -> impl Iterator<Item = &'a Arc<dyn EventSubscriber>> + 'a {
+        self.subscribers
+            .iter()
+            .filter(move |s| s.event_types().is_empty() || s.event_types().contains(&event_type))
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.subscribers.iter().map(|s| s.name()).collect()
+    }
+}
+
+/// Hands events to the job queue, for reactions that are slow or call other services
+struct JobForwarder<H> {
+    name: String,
+    jobs: JobService,
+    event_types: Vec<&'static str>,
+    handler: PhantomData<fn() -> H>,
+}
+
+impl<H: JobHandler<Payload = EventEnvelope>> EventSubscriber for JobForwarder<H> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn event_types(&self) -> &[&'static str] {
+        &self.event_types
+    }
+
+    fn handle<'a>(&'a self, txn: &'a DatabaseTransaction, event: &'a EventEnvelope) -> EventFuture<'a> {
+        Box::pin(async move {
+            self.jobs
+                .enqueue_in::<H, _>(txn, event, EnqueueOptions::default())
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
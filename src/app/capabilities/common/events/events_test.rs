@@ -0,0 +1,64 @@
+use migration::sea_orm::DatabaseTransaction;
+
+use crate::app::capabilities::iam::events::UserRegistered;
+
+use super::{
+    domain_event::{DomainEvent, EventEnvelope},
+    event_subscriber::{EventFuture, EventSubscriber, EventSubscribers},
+};
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct OrderPlaced {
+    total: u32,
+}
+
+impl DomainEvent for OrderPlaced {
+    const EVENT_TYPE: &'static str = "order.placed";
+}
+
+struct Named(&'static str, &'static [&'static str]);
+
+impl EventSubscriber for Named {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    fn event_types(&self) -> &[&'static str] {
+        self.1
+    }
+
+    fn handle<'a>(&'a self, _txn: &'a DatabaseTransaction, _event: &'a EventEnvelope) -> EventFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[test]
+fn should_decode_only_the_recorded_type() {
+    let event = OrderPlaced { total: 42 };
+    let envelope = EventEnvelope::new(&event).unwrap();
+    assert_eq!(envelope.event_type, "order.placed");
+    assert_eq!(envelope.payload, serde_json::json!({"total": 42}));
+    assert!(envelope.is::<OrderPlaced>());
+    assert_eq!(envelope.decode::<OrderPlaced>().unwrap().unwrap(), event);
+    assert!(envelope.decode::<UserRegistered>().is_none());
+}
+
+#[test]
+fn should_route_events_to_interested_subscribers() {
+    let mut subscribers = EventSubscribers::default();
+    subscribers
+        .subscribe(Named("audit", &[]))
+        .subscribe(Named("welcome_email", &["user.registered"]))
+        .subscribe(Named("invoice", &["order.placed"]));
+
+    let names = |subscribers: &EventSubscribers, event_type| {
+        subscribers.for_event(event_type).map(|s| s.name().to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(names(&subscribers, "user.registered"), vec!["audit", "welcome_email"]);
+    assert_eq!(names(&subscribers, "order.placed"), vec!["audit", "invoice"]);
+    assert_eq!(names(&subscribers, "order.cancelled"), vec!["audit"]);
+
+    subscribers.subscribe(Named("invoice", &["order.cancelled"]));
+    assert_eq!(names(&subscribers, "order.cancelled"), vec!["audit", "invoice"]);
+    assert_eq!(subscribers.names(), vec!["audit", "welcome_email", "invoice"]);
+}
@@ -0,0 +1,7 @@
+pub mod domain_event;
+pub mod event_dispatcher;
+pub mod event_subscriber;
+pub mod outbox;
+
+#[cfg(test)]
+mod events_test;
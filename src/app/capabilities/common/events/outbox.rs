@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use migration::sea_orm;
+use sea_orm::{prelude::Uuid, ConnectionTrait, DbBackend, DbErr, Statement};
+
+use crate::app::capabilities::common::{config::app_config::EventsConfig, database::db_router::DbRouter};
+
+use super::domain_event::{DomainEvent, EventEnvelope};
+
+const RECORD: &str = r#"
+INSERT INTO outbox (id, event_type, payload, occurred_at) VALUES ($1, $2, $3::jsonb, $4::timestamptz)
+"#;
+
+/// Events are recorded on SQLite too, delivering them needs Postgres
+const RECORD_SQLITE: &str = r#"
+INSERT INTO outbox (id, event_type, payload, occurred_at) VALUES (?, ?, ?, ?)
+"#;
+
+/// Rows stay locked until the transaction ends, concurrent dispatchers skip them
+const LOCK_PENDING: &str = r#"
+SELECT id, event_type, payload::text AS payload, EXTRACT(EPOCH FROM occurred_at)::float8 AS occurred_at
+FROM outbox
+WHERE dispatched_at IS NULL AND next_attempt_at <= now() AND attempts < $1
+ORDER BY occurred_at, id
+FOR UPDATE SKIP LOCKED
+LIMIT $2
+"#;
+
+const MARK_DISPATCHED: &str = r#"
+UPDATE outbox SET dispatched_at = now(), attempts = attempts + 1, last_error = NULL WHERE id = $1
+"#;
+
+const MARK_FAILED: &str = r#"
+UPDATE outbox SET
+    attempts = attempts + 1, last_error = $2,
+    next_attempt_at = now() + make_interval(secs => least(power(2, attempts), $3))
+WHERE id = $1
+"#;
+
+/// Returns a row only for the first delivery of an event to a consumer
+const CLAIM_DELIVERY: &str = r#"
+INSERT INTO processed_events (consumer, event_id) VALUES ($1, $2)
+ON CONFLICT DO NOTHING
+RETURNING event_id
+"#;
+
+/// Longest wait before an event that failed delivery is retried
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Store `event` in the outbox. Pass the transaction of the change the event describes, so the
+/// event is delivered if and only if the change is committed.
+pub async fn record<C: ConnectionTrait, E: DomainEvent>(conn: &C, event: &E) -> Result<EventEnvelope, DbErr> {
+    let envelope = EventEnvelope::new(event).map_err(|e| DbErr::Custom(e.to_string()))?;
+    let backend = conn.get_database_backend();
+    let sql = match backend {
+        DbBackend::Sqlite => RECORD_SQLITE,
+        _ => RECORD,
+    };
+    let statement = Statement::from_sql_and_values(
+        backend,
+        sql,
+        [
+            envelope.id.into(),
+            envelope.event_type.clone().into(),
+            envelope.payload.to_string().into(),
+            envelope.occurred_at.clone().into(),
+        ],
+    );
+    conn.execute(statement).await?;
+    Ok(envelope)
+}
+
+/// Lock up to `limit` undelivered events, delivery attempts are recorded in the same transaction
+pub async fn lock_pending<C: ConnectionTrait>(conn: &C, config: &EventsConfig) -> Result<Vec<EventEnvelope>, DbErr> {
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        LOCK_PENDING,
+        [config.max_attempts.into(), (config.batch_size as i64).into()],
+    );
+    conn.query_all(statement)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let payload: String = row.try_get("", "payload")?;
+            let occurred_at: f64 = row.try_get("", "occurred_at")?;
+            Ok(EventEnvelope {
+                id: row.try_get("", "id")?,
+                event_type: row.try_get("", "event_type")?,
+                payload: serde_json::from_str(&payload).map_err(|e| DbErr::Custom(e.to_string()))?,
+                occurred_at: chrono::DateTime::from_timestamp_millis((occurred_at * 1000.0) as i64)
+                    .unwrap_or_default()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            })
+        })
+        .collect()
+}
+
+pub async fn mark_dispatched<C: ConnectionTrait>(conn: &C, id: Uuid) -> Result<(), DbErr> {
+    let statement = Statement::from_sql_and_values(DbBackend::Postgres, MARK_DISPATCHED, [id.into()]);
+    conn.execute(statement).await.map(|_| ())
+}
+
+/// Keep the event for a retry with exponential backoff, capped at an hour
+pub async fn mark_failed<C: ConnectionTrait>(conn: &C, id: Uuid, error: &str) -> Result<(), DbErr> {
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        MARK_FAILED,
+        [id.into(), error.into(), MAX_RETRY_DELAY.as_secs_f64().into()],
+    );
+    conn.execute(statement).await.map(|_| ())
+}
+
+/// Record the delivery of `event_id` to `consumer`, false when it was already delivered.
+///
+/// Run it in the transaction of the consumer's own writes, a rollback makes the event deliverable again.
+pub async fn claim_delivery<C: ConnectionTrait>(conn: &C, consumer: &str, event_id: Uuid) -> Result<bool, DbErr> {
+    let statement = Statement::from_sql_and_values(DbBackend::Postgres, CLAIM_DELIVERY, [consumer.into(), event_id.into()]);
+    Ok(conn.query_one(statement).await?.is_some())
+}
+
+/// The `outbox` table, shared by the server and the `worker` binary
+#[derive(Clone)]
+pub struct OutboxService {
+    db: DbRouter,
+    config: EventsConfig,
+}
+
+impl OutboxService {
+    pub fn new(db: DbRouter, config: &EventsConfig) -> Self {
+        Self {
+            db,
+            config: config.clone(),
+        }
+    }
+
+    pub fn db(&self) -> &DbRouter {
+        &self.db
+    }
+
+    pub fn config(&self) -> &EventsConfig {
+        &self.config
+    }
+}
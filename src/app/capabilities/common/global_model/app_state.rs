@@ -1,14 +1,44 @@
-use sea_orm::DatabaseConnection;
-
 use crate::app::capabilities::*;
+use common::{
+    config::app_config::HttpConfig,
+    email::email_service::EmailService,
+    events::outbox::OutboxService,
+    jobs::job_service::JobService,
+    scheduler::scheduler_service::SchedulerService,
+    database::db_router::DbRouter, health::health_service::HealthService, i18n::i18n_service::I18nService,
+    lifecycle::lifecycle_service::LifecycleService, metrics::metrics_service::MetricsService,
+    rate_limit::rate_limit_service::RateLimitService,
+    tls::client_certificate::ClientCertificates,
+};
 #[derive(Clone)]
 pub struct AppState {
-    pub db: DatabaseConnection,
+    pub db: DbRouter,
+    pub lifecycle: LifecycleService,
+    /// Dependency checks behind `/readyz` and `/startupz`
+    pub health: HealthService,
+    pub metrics: MetricsService,
+    /// Message catalogues, for messages rendered outside of a request such as emails
+    pub i18n: I18nService,
+    /// Rules applied by the rate limit middleware, routes are added when the app is built
+    pub rate_limit: RateLimitService,
+    /// Background job queue, see `bootstrap::start_workers`
+    pub jobs: JobService,
+    /// Transactional emails, sent by the `send_email` job
+    pub email: EmailService,
+    /// Domain events awaiting delivery, dispatched next to the job workers
+    pub events: OutboxService,
+    /// Recurring tasks, see `bootstrap::start_scheduler`
+    pub scheduler: SchedulerService,
+    /// Middleware settings applied when the routes are built
+    pub http: HttpConfig,
+    /// Verified client certificates of open TLS connections, filled in by the TLS listener
+    pub client_certificates: ClientCertificates,
     pub services: ServiceList,
 }
 
 #[derive(Clone)]
 pub struct ServiceList {
     pub iam: iam::services::iam::iam_service::IAMService,
+    pub webhooks: webhooks::services::webhook::webhook_service::WebhookService,
 }
 
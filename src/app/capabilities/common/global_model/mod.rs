@@ -1,3 +1,3 @@
 pub mod session_user;
 pub mod app_state;
-pub mod api_error;
+pub mod validation_error;
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::app::capabilities::common::i18n::i18n_service::MessageArg;
+
+/// A single failed validation rule on a request field
+#[derive(Object, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Request field the rule applies to
+    pub field: String,
+    /// Machine readable rule name
+    pub rule: String,
+    /// Message in the request locale
+    pub message: String,
+    /// Values of the rule, e.g. `min`, used to localize the message
+    #[oai(skip)]
+    #[serde(default)]
+    pub args: HashMap<String, MessageArg>,
+}
+
+impl ValidationError {
+    pub fn new(field: &str, rule: &str, message: String) -> ValidationError {
+        ValidationError {
+            field: field.to_string(),
+            rule: rule.to_string(),
+            message,
+            args: HashMap::new(),
+        }
+    }
+
+    /// Text argument, passed to the message as is
+    pub fn with_arg(mut self, name: &str, value: impl ToString) -> ValidationError {
+        self.args.insert(name.to_string(), MessageArg::Text(value.to_string()));
+        self
+    }
+
+    /// Numeric argument, selects plural forms of the message
+    pub fn with_number_arg(mut self, name: &str, value: i64) -> ValidationError {
+        self.args.insert(name.to_string(), MessageArg::Number(value));
+        self
+    }
+}
@@ -0,0 +1,45 @@
+use std::{future::Future, pin::Pin};
+
+use poem_openapi::{Enum, Object};
+
+pub type CheckFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// A dependency probed by `/readyz` and `/startupz`.
+///
+/// Capabilities register their checks with `HealthService::register` while the app state is built.
+pub trait HealthCheck: Send + Sync {
+    /// Name shown in the health report, unique per check
+    fn name(&self) -> &str;
+
+    /// Whether a failure of this check takes the app out of rotation
+    fn required(&self) -> bool {
+        true
+    }
+
+    /// Probe the dependency, the error is shown in the health report
+    fn check(&self) -> CheckFuture<'_>;
+}
+
+#[derive(Debug, Enum, Clone, Copy, PartialEq, Eq)]
+#[oai(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+/// Outcome of a single check
+#[derive(Debug, Object, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: HealthStatus,
+    pub required: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Outcome of every check, `Down` when any required check failed
+#[derive(Debug, Object, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checks: Vec<CheckResult>,
+}
@@ -0,0 +1,79 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use tokio::task::JoinSet;
+
+use super::health_check::{CheckResult, HealthCheck, HealthReport, HealthStatus};
+
+/// Registry of the dependency checks behind the readiness and startup probes
+#[derive(Clone)]
+pub struct HealthService {
+    checks: Arc<RwLock<Vec<Arc<dyn HealthCheck>>>>,
+    check_timeout: Duration,
+}
+
+impl HealthService {
+    pub fn new(check_timeout: Duration) -> Self {
+        Self {
+            checks: Arc::new(RwLock::new(vec![])),
+            check_timeout,
+        }
+    }
+
+    pub fn register(&self, check: impl HealthCheck + 'static) {
+        self.checks.write().unwrap().push(Arc::new(check));
+    }
+
+    /// Run every check concurrently, each bounded by the check timeout
+    pub async fn run(&self) -> HealthReport {
+        let checks = self.checks.read().unwrap().clone();
+        let timeout = self.check_timeout;
+        // a check that panics keeps this placeholder
+        let mut results: Vec<CheckResult> = checks
+            .iter()
+            .map(|check| CheckResult {
+                name: check.name().to_string(),
+                status: HealthStatus::Down,
+                required: check.required(),
+                latency_ms: 0,
+                error: Some(String::from("check panicked")),
+            })
+            .collect();
+
+        let mut running = JoinSet::new();
+        for (i, check) in checks.into_iter().enumerate() {
+            running.spawn(async move {
+                let started = Instant::now();
+                let outcome = match tokio::time::timeout(timeout, check.check()).await {
+                    Ok(outcome) => outcome,
+                    Err(_) => Err(format!("timed out after {}ms", timeout.as_millis())),
+                };
+                if let Err(e) = &outcome {
+                    tracing::warn!("Health check {} failed: {}", check.name(), e);
+                }
+                (i, started.elapsed().as_millis() as u64, outcome)
+            });
+        }
+        while let Some(finished) = running.join_next().await {
+            match finished {
+                Ok((i, latency_ms, outcome)) => {
+                    let result = &mut results[i];
+                    result.latency_ms = latency_ms;
+                    result.status = if outcome.is_ok() { HealthStatus::Up } else { HealthStatus::Down };
+                    result.error = outcome.err();
+                }
+                Err(e) => tracing::error!("Health check panicked: {}", e),
+            }
+        }
+
+        let healthy = results
+            .iter()
+            .all(|check| !check.required || check.status == HealthStatus::Up);
+        HealthReport {
+            status: if healthy { HealthStatus::Up } else { HealthStatus::Down },
+            checks: results,
+        }
+    }
+}
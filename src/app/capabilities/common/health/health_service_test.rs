@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use super::{health_check::*, health_service::*};
+
+struct FakeCheck {
+    name: &'static str,
+    required: bool,
+    outcome: Option<Result<(), String>>,
+}
+
+impl HealthCheck for FakeCheck {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn required(&self) -> bool {
+        self.required
+    }
+
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            match &self.outcome {
+                Some(outcome) => outcome.clone(),
+                None => std::future::pending().await,
+            }
+        })
+    }
+}
+
+fn check(name: &'static str, required: bool, outcome: Option<Result<(), String>>) -> FakeCheck {
+    FakeCheck { name, required, outcome }
+}
+
+#[tokio::test]
+async fn should_be_up_when_required_checks_pass() {
+    let health = HealthService::new(Duration::from_secs(1));
+    health.register(check("database", true, Some(Ok(()))));
+    health.register(check("cache", false, Some(Err(String::from("refused")))));
+
+    let report = health.run().await;
+    assert_eq!(report.status, HealthStatus::Up);
+    let names: Vec<&str> = report.checks.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["database", "cache"]);
+    assert_eq!(report.checks[1].status, HealthStatus::Down);
+    assert_eq!(report.checks[1].error, Some(String::from("refused")));
+}
+
+#[tokio::test]
+async fn should_be_down_when_a_required_check_fails() {
+    let health = HealthService::new(Duration::from_secs(1));
+    health.register(check("cache", false, Some(Ok(()))));
+    health.register(check("database", true, Some(Err(String::from("connection refused")))));
+
+    let report = health.run().await;
+    assert_eq!(report.status, HealthStatus::Down);
+    assert_eq!(report.checks[0].status, HealthStatus::Up);
+}
+
+#[tokio::test]
+async fn should_fail_checks_that_time_out() {
+    let health = HealthService::new(Duration::from_millis(20));
+    health.register(check("queue", true, None));
+
+    let report = health.run().await;
+    assert_eq!(report.status, HealthStatus::Down);
+    assert_eq!(report.checks[0].error, Some(String::from("timed out after 20ms")));
+    assert!(report.checks[0].latency_ms >= 20);
+}
@@ -0,0 +1,5 @@
+pub mod health_check;
+pub mod health_service;
+
+#[cfg(test)]
+mod health_service_test;
@@ -0,0 +1,91 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use futures_util::StreamExt;
+use poem::{
+    http::{header, StatusCode},
+    Body, Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use poem_openapi::types::ToJSON;
+
+use crate::app::capabilities::common::problem::problem_details::{ProblemDetails, PROBLEM_JSON};
+
+/// Rejects request bodies larger than `max_bytes` with 413, whether announced by
+/// `Content-Length` or discovered while streaming a chunked body
+pub struct BodyLimitMiddleware {
+    max_bytes: usize,
+}
+
+impl BodyLimitMiddleware {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for BodyLimitMiddleware {
+    type Output = BodyLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        BodyLimitEndpoint {
+            inner: ep,
+            max_bytes: self.max_bytes,
+        }
+    }
+}
+
+pub struct BodyLimitEndpoint<E> {
+    inner: E,
+    max_bytes: usize,
+}
+
+impl<E: Endpoint> Endpoint for BodyLimitEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let content_length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok());
+        if content_length.is_some_and(|length| length > self.max_bytes) {
+            return Ok(too_large());
+        }
+
+        let exceeded = Arc::new(AtomicBool::new(false));
+        if content_length.is_none() {
+            let max_bytes = self.max_bytes;
+            let flag = exceeded.clone();
+            let mut read = 0;
+            let stream = req.take_body().into_bytes_stream().map(move |chunk| {
+                let chunk = chunk?;
+                read += chunk.len();
+                if read > max_bytes {
+                    flag.store(true, Ordering::Relaxed);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "request body too large"));
+                }
+                Ok(chunk)
+            });
+            req.set_body(Body::from_bytes_stream(stream));
+        }
+
+        let result = self.inner.call(req).await;
+        // extractors report a failed read as a bad request
+        if exceeded.load(Ordering::Relaxed) {
+            return Ok(too_large());
+        }
+        result.map(IntoResponse::into_response)
+    }
+}
+
+fn too_large() -> Response {
+    let problem = ProblemDetails::new(StatusCode::PAYLOAD_TOO_LARGE);
+    Response::builder()
+        .status(StatusCode::PAYLOAD_TOO_LARGE)
+        .header(header::CONTENT_TYPE, PROBLEM_JSON)
+        .body(problem.to_json_string())
+}
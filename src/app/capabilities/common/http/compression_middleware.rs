@@ -0,0 +1,139 @@
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZstdEncoder};
+use bytes::Bytes;
+use http_body::Body as _;
+use http_body_util::combinators::BoxBody;
+use poem::{
+    http::{header, HeaderValue, StatusCode},
+    Body, Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use tokio::io::BufReader;
+
+/// Response encodings, in order of preference when the client weighs them equally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Zstd,
+    Br,
+    Gzip,
+}
+
+impl Encoding {
+    const ALL: [Encoding; 3] = [Encoding::Zstd, Encoding::Br, Encoding::Gzip];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Zstd => "zstd",
+            Encoding::Br => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// Encoding with the highest `q` in an `Accept-Encoding` header
+pub fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+    let weights: Vec<(&str, f32)> = accept_encoding
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let name = parts.next()?.trim();
+            let q = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            Some((name, q))
+        })
+        .collect();
+    let weight = |encoding: Encoding| {
+        weights
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(encoding.as_str()))
+            .or_else(|| weights.iter().find(|(name, _)| *name == "*"))
+            .map_or(0.0, |(_, q)| *q)
+    };
+    Encoding::ALL
+        .into_iter()
+        .map(|encoding| (encoding, weight(encoding)))
+        .filter(|(_, q)| *q > 0.0)
+        // `max_by` keeps the last of equal elements, so walk the preference order backwards
+        .rev()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(encoding, _)| encoding)
+}
+
+/// Compresses responses with zstd, brotli or gzip, skipping small, empty and already
+/// compressed bodies
+pub struct CompressionMiddleware {
+    min_bytes: usize,
+}
+
+impl CompressionMiddleware {
+    pub fn new(min_bytes: usize) -> Self {
+        Self { min_bytes }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for CompressionMiddleware {
+    type Output = CompressionEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        CompressionEndpoint {
+            inner: ep,
+            min_bytes: self.min_bytes,
+        }
+    }
+}
+
+pub struct CompressionEndpoint<E> {
+    inner: E,
+    min_bytes: usize,
+}
+
+impl<E: Endpoint> Endpoint for CompressionEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let encoding = req
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|value| value.to_str().ok())
+            .and_then(negotiate);
+        let mut resp = self.inner.call(req).await?.into_response();
+        let Some(encoding) = encoding else {
+            return Ok(resp);
+        };
+        resp.headers_mut().append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        if !is_compressible(&resp) {
+            return Ok(resp);
+        }
+
+        let body: BoxBody<Bytes, std::io::Error> = resp.take_body().into();
+        let size = body.size_hint();
+        // streamed bodies have no exact size and are always compressed
+        if size.exact().is_some_and(|size| size < self.min_bytes as u64) {
+            resp.set_body(Body::from(body));
+            return Ok(resp);
+        }
+        let reader = BufReader::new(Body::from(body).into_async_read());
+        let body = match encoding {
+            Encoding::Zstd => Body::from_async_read(ZstdEncoder::new(reader)),
+            Encoding::Br => Body::from_async_read(BrotliEncoder::new(reader)),
+            Encoding::Gzip => Body::from_async_read(GzipEncoder::new(reader)),
+        };
+        resp.set_body(body);
+        resp.headers_mut().remove(header::CONTENT_LENGTH);
+        resp.headers_mut()
+            .insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding.as_str()));
+        Ok(resp)
+    }
+}
+
+fn is_compressible(resp: &Response) -> bool {
+    if matches!(resp.status(), StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED)
+        || resp.headers().contains_key(header::CONTENT_ENCODING)
+    {
+        return false;
+    }
+    // event streams must be flushed as they go, media types are compressed already
+    let content_type = resp.content_type().unwrap_or_default();
+    !["text/event-stream", "image/", "audio/", "video/", "application/zip", "application/gzip"]
+        .iter()
+        .any(|prefix| content_type.starts_with(prefix))
+}
@@ -0,0 +1,30 @@
+use poem::middleware::Cors;
+
+use crate::app::capabilities::common::config::app_config::CorsConfig;
+
+/// Headers set by the app that browser scripts may read
+const EXPOSED_HEADERS: [&str; 7] = [
+    "x-request-id",
+    "content-language",
+    "retry-after",
+    "ratelimit-limit",
+    "ratelimit-remaining",
+    "ratelimit-reset",
+    "ratelimit-policy",
+];
+
+/// Cors middleware for the configured origins, `None` while no origin is allowed
+pub fn cors(config: &CorsConfig) -> Option<Cors> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+    let mut cors = Cors::new()
+        .allow_credentials(config.allow_credentials)
+        .max_age(config.max_age_secs as i32)
+        .expose_headers(EXPOSED_HEADERS);
+    // without explicit origins poem allows any origin
+    if !config.allowed_origins.iter().any(|origin| origin == "*") {
+        cors = cors.allow_origins(config.allowed_origins.iter().map(String::as_str));
+    }
+    Some(cors)
+}
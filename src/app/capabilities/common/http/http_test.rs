@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use async_compression::tokio::bufread::GzipDecoder;
+use poem::{
+    handler,
+    http::{header, Method, StatusCode},
+    post, Body, Endpoint, EndpointExt, Request, Route,
+};
+use tokio::io::{AsyncReadExt, BufReader};
+
+use crate::app::capabilities::common::{
+    config::app_config::{CorsConfig, SecurityHeadersConfig},
+    problem::problem_details::PROBLEM_JSON,
+};
+
+use super::{
+    body_limit_middleware::BodyLimitMiddleware,
+    compression_middleware::{negotiate, CompressionMiddleware, Encoding},
+    cors::cors,
+    panic_handler::catch_panic,
+    security_headers_middleware::SecurityHeadersMiddleware,
+    timeout_middleware::TimeoutMiddleware,
+};
+
+#[handler]
+fn large() -> String {
+    "compressible ".repeat(200)
+}
+
+#[handler]
+fn small() -> &'static str {
+    "ok"
+}
+
+#[handler]
+async fn echo(body: String) -> String {
+    body
+}
+
+#[handler]
+async fn slow() -> &'static str {
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    "late"
+}
+
+#[handler]
+fn boom() -> &'static str {
+    panic!("boom")
+}
+
+#[test]
+fn should_negotiate_the_preferred_encoding() {
+    assert_eq!(negotiate("gzip, br, zstd"), Some(Encoding::Zstd));
+    assert_eq!(negotiate("gzip;q=1.0, br;q=0.5"), Some(Encoding::Gzip));
+    assert_eq!(negotiate("*;q=0.1, gzip;q=0"), Some(Encoding::Zstd));
+    assert_eq!(negotiate("identity"), None);
+    assert_eq!(negotiate("zstd;q=0, br;q=0, gzip;q=0"), None);
+}
+
+#[tokio::test]
+async fn should_compress_large_responses_only() {
+    let app = Route::new()
+        .at("/large", large)
+        .at("/small", small)
+        .with(CompressionMiddleware::new(1024));
+    let get = |path: &str| Request::builder().uri_str(path).header(header::ACCEPT_ENCODING, "gzip").finish();
+
+    let resp = app.get_response(get("/large")).await;
+    assert_eq!(resp.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(resp.headers()[header::VARY], "accept-encoding");
+    let mut body = String::new();
+    GzipDecoder::new(BufReader::new(resp.into_body().into_async_read()))
+        .read_to_string(&mut body)
+        .await
+        .unwrap();
+    assert_eq!(body, "compressible ".repeat(200));
+
+    let resp = app.get_response(get("/small")).await;
+    assert!(!resp.headers().contains_key(header::CONTENT_ENCODING));
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn should_add_security_headers() {
+    let app = Route::new()
+        .at("/small", small)
+        .at("/swagger", small)
+        .with(SecurityHeadersMiddleware::new(SecurityHeadersConfig::default()));
+
+    let resp = app.get_response(Request::builder().uri_str("/small").finish()).await;
+    assert_eq!(resp.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    assert_eq!(resp.headers()[header::X_FRAME_OPTIONS], "DENY");
+    assert_eq!(resp.headers()[header::REFERRER_POLICY], "no-referrer");
+    assert!(resp.headers().contains_key(header::CONTENT_SECURITY_POLICY));
+    // plain HTTP, browsers ignore HSTS there
+    assert!(!resp.headers().contains_key(header::STRICT_TRANSPORT_SECURITY));
+
+    let resp = app.get_response(Request::builder().uri_str("/missing").finish()).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(resp.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+
+    let resp = app.get_response(Request::builder().uri_str("/swagger").finish()).await;
+    assert!(!resp.headers().contains_key(header::CONTENT_SECURITY_POLICY));
+}
+
+#[tokio::test]
+async fn should_reject_bodies_over_the_limit() {
+    let app = Route::new().at("/echo", post(echo)).with(BodyLimitMiddleware::new(8));
+    let send = |body: Body, length: Option<usize>| {
+        let mut req = Request::builder().method(Method::POST).uri_str("/echo");
+        if let Some(length) = length {
+            req = req.header(header::CONTENT_LENGTH, length);
+        }
+        req.body(body)
+    };
+
+    let resp = app.get_response(send(Body::from("short"), Some(5))).await;
+    assert_eq!(resp.into_body().into_string().await.unwrap(), "short");
+
+    let resp = app.get_response(send(Body::from("much too long"), Some(13))).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+
+    // chunked, the size is only known while reading
+    let stream = Body::from_async_read(&b"much too long"[..]);
+    let resp = app.get_response(send(stream, None)).await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn should_answer_problems_for_timeouts_and_panics() {
+    let app = Route::new()
+        .at("/slow", slow)
+        .at("/boom", boom)
+        .with(catch_panic())
+        .with(TimeoutMiddleware::new(Duration::from_millis(10)));
+
+    let resp = app.get_response(Request::builder().uri_str("/slow").finish()).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+    let body: serde_json::Value = serde_json::from_str(&resp.into_body().into_string().await.unwrap()).unwrap();
+    assert_eq!(body["code"], "timeout");
+
+    let resp = app.get_response(Request::builder().uri_str("/boom").finish()).await;
+    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+}
+
+#[tokio::test]
+async fn should_allow_configured_origins() {
+    assert!(cors(&CorsConfig::default()).is_none());
+
+    let config = CorsConfig {
+        allowed_origins: vec![String::from("https://app.example.com")],
+        ..CorsConfig::default()
+    };
+    let app = Route::new().at("/small", small).with(cors(&config).unwrap());
+    let preflight = |origin: &str| {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri_str("/small")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .finish()
+    };
+
+    let resp = app.get_response(preflight("https://app.example.com")).await;
+    assert_eq!(resp.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+
+    let resp = app.get_response(preflight("https://evil.example.com")).await;
+    assert!(!resp.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
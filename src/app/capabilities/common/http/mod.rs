@@ -0,0 +1,9 @@
+pub mod body_limit_middleware;
+pub mod compression_middleware;
+pub mod cors;
+pub mod panic_handler;
+pub mod security_headers_middleware;
+pub mod timeout_middleware;
+
+#[cfg(test)]
+mod http_test;
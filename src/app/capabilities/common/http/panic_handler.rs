@@ -0,0 +1,29 @@
+use std::any::Any;
+
+use poem::{
+    http::{header, StatusCode},
+    middleware::CatchPanic,
+    Response,
+};
+use poem_openapi::types::ToJSON;
+
+use crate::app::capabilities::common::problem::problem_details::{ProblemDetails, PROBLEM_JSON};
+
+/// Catches panics in handlers and answers with a problem+json 500 instead of dropping the
+/// connection
+pub fn catch_panic() -> CatchPanic<fn(Box<dyn Any + Send>) -> Response> {
+    CatchPanic::new().with_handler(panic_response)
+}
+
+fn panic_response(panic: Box<dyn Any + Send>) -> Response {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    tracing::error!("Request handler panicked: {}", message);
+    Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .header(header::CONTENT_TYPE, PROBLEM_JSON)
+        .body(ProblemDetails::internal().to_json_string())
+}
@@ -0,0 +1,72 @@
+use poem::{
+    http::{header, uri::Scheme, HeaderName, HeaderValue},
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+
+use crate::app::capabilities::common::config::app_config::SecurityHeadersConfig;
+
+/// Adds HSTS, CSP, `X-Frame-Options`, `Referrer-Policy` and `X-Content-Type-Options` to every
+/// response, error responses included
+pub struct SecurityHeadersMiddleware {
+    config: SecurityHeadersConfig,
+}
+
+impl SecurityHeadersMiddleware {
+    pub fn new(config: SecurityHeadersConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for SecurityHeadersMiddleware {
+    type Output = SecurityHeadersEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        let header = |value: &str| HeaderValue::from_str(value).ok().filter(|value| !value.is_empty());
+        SecurityHeadersEndpoint {
+            inner: ep,
+            hsts: (self.config.hsts_max_age_secs > 0)
+                .then(|| header(&format!("max-age={}; includeSubDomains", self.config.hsts_max_age_secs)))
+                .flatten(),
+            content_security_policy: header(&self.config.content_security_policy),
+            frame_options: header(&self.config.frame_options),
+            referrer_policy: header(&self.config.referrer_policy),
+        }
+    }
+}
+
+pub struct SecurityHeadersEndpoint<E> {
+    inner: E,
+    hsts: Option<HeaderValue>,
+    content_security_policy: Option<HeaderValue>,
+    frame_options: Option<HeaderValue>,
+    referrer_policy: Option<HeaderValue>,
+}
+
+impl<E: Endpoint> Endpoint for SecurityHeadersEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let https = *req.scheme() == Scheme::HTTPS;
+        // the swagger UI runs inline scripts and styles
+        let swagger = req.uri().path().starts_with("/swagger");
+        let mut resp = match self.inner.call(req).await {
+            Ok(resp) => resp.into_response(),
+            Err(e) => e.into_response(),
+        };
+
+        let headers = resp.headers_mut();
+        headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+        let optional: [(HeaderName, &Option<HeaderValue>, bool); 4] = [
+            (header::STRICT_TRANSPORT_SECURITY, &self.hsts, https),
+            (header::CONTENT_SECURITY_POLICY, &self.content_security_policy, !swagger),
+            (header::X_FRAME_OPTIONS, &self.frame_options, true),
+            (header::REFERRER_POLICY, &self.referrer_policy, true),
+        ];
+        for (name, value, enabled) in optional {
+            if let (Some(value), true) = (value, enabled) {
+                headers.insert(name, value.clone());
+            }
+        }
+        Ok(resp)
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use poem::{
+    http::{header, StatusCode},
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use poem_openapi::types::ToJSON;
+
+use crate::app::capabilities::common::problem::problem_details::{ProblemDetails, PROBLEM_JSON};
+
+/// Answers 503 when a request takes longer than `timeout`, the handler future is dropped
+pub struct TimeoutMiddleware {
+    timeout: Duration,
+}
+
+impl TimeoutMiddleware {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for TimeoutMiddleware {
+    type Output = TimeoutEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        TimeoutEndpoint {
+            inner: ep,
+            timeout: self.timeout,
+        }
+    }
+}
+
+pub struct TimeoutEndpoint<E> {
+    inner: E,
+    timeout: Duration,
+}
+
+impl<E: Endpoint> Endpoint for TimeoutEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        match tokio::time::timeout(self.timeout, self.inner.call(req)).await {
+            Ok(result) => result.map(IntoResponse::into_response),
+            Err(_) => {
+                tracing::warn!("Request timed out after {:?}", self.timeout);
+                let problem = ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE).with_code("timeout");
+                Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .header(header::CONTENT_TYPE, PROBLEM_JSON)
+                    .body(problem.to_json_string()))
+            }
+        }
+    }
+}
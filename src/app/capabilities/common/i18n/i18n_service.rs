@@ -0,0 +1,138 @@
+use std::{collections::HashMap, sync::Arc};
+
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use fluent_langneg::{accepted_languages, negotiate_languages, NegotiationStrategy};
+use serde::{Deserialize, Serialize};
+use unic_langid::LanguageIdentifier;
+
+/// Message catalogues shipped with the server, by locale
+const CATALOGUES: &[(&str, &str)] = &[
+    ("en", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/locales/en.ftl"))),
+    ("de", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/locales/de.ftl"))),
+    ("es", include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/locales/es.ftl"))),
+];
+
+/// Locales with a message catalogue
+pub fn supported_locales() -> Vec<&'static str> {
+    CATALOGUES.iter().map(|(locale, _)| *locale).collect()
+}
+
+/// Value of a message argument, numbers select plural forms and are formatted for the locale
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageArg {
+    Number(i64),
+    Text(String),
+}
+
+impl MessageArg {
+    fn to_fluent(&self) -> FluentValue<'_> {
+        match self {
+            MessageArg::Number(number) => FluentValue::from(*number),
+            MessageArg::Text(text) => FluentValue::from(text.as_str()),
+        }
+    }
+}
+
+/// Subject and text of a transactional email in one locale
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedEmail {
+    pub subject: String,
+    pub body: String,
+}
+
+/// Fluent message catalogues and locale negotiation
+#[derive(Clone)]
+pub struct I18nService {
+    bundles: Arc<HashMap<LanguageIdentifier, FluentBundle<FluentResource>>>,
+    locales: Arc<Vec<LanguageIdentifier>>,
+    default_locale: LanguageIdentifier,
+}
+
+impl I18nService {
+    /// Load the shipped catalogues, `default_locale` must be one of [`supported_locales`]
+    pub fn new(default_locale: &str) -> Self {
+        let mut bundles = HashMap::new();
+        let mut locales = vec![];
+        for (locale, source) in CATALOGUES {
+            let locale: LanguageIdentifier = locale.parse().expect("Invalid catalogue locale");
+            let resource = match FluentResource::try_new(source.to_string()) {
+                Ok(resource) => resource,
+                Err((_, errors)) => panic!("Invalid {} catalogue: {:?}", locale, errors),
+            };
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.clone()]);
+            // no bidi isolation marks, messages end up in JSON and plain text emails
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|errors| panic!("Duplicate {} messages: {:?}", locale, errors));
+            locales.push(locale.clone());
+            bundles.insert(locale, bundle);
+        }
+        let default_locale = default_locale.parse().expect("Invalid default locale");
+        Self {
+            bundles: Arc::new(bundles),
+            locales: Arc::new(locales),
+            default_locale,
+        }
+    }
+
+    pub fn default_locale(&self) -> &LanguageIdentifier {
+        &self.default_locale
+    }
+
+    /// Supported locale matching `locale` exactly or by language, e.g. `de-AT` gives `de`
+    pub fn find(&self, locale: &str) -> Option<LanguageIdentifier> {
+        let requested: LanguageIdentifier = locale.parse().ok()?;
+        negotiate_languages(&[requested], &self.locales, None, NegotiationStrategy::Lookup)
+            .first()
+            .map(|locale| (*locale).clone())
+    }
+
+    /// Best supported locale for an `Accept-Language` header, the default one otherwise
+    pub fn negotiate(&self, accept_language: Option<&str>) -> LanguageIdentifier {
+        let requested = accepted_languages::parse(accept_language.unwrap_or_default());
+        negotiate_languages(&requested, &self.locales, Some(&self.default_locale), NegotiationStrategy::Lookup)
+            .first()
+            .map(|locale| (*locale).clone())
+            .unwrap_or_else(|| self.default_locale.clone())
+    }
+
+    /// Message `key` in `locale`, falling back to the default locale
+    pub fn message(&self, locale: &LanguageIdentifier, key: &str, args: Option<&FluentArgs>) -> Option<String> {
+        self.format(locale, key, args)
+            .or_else(|| self.format(&self.default_locale, key, args))
+    }
+
+    /// Message with typed arguments
+    pub fn message_with(&self, locale: &LanguageIdentifier, key: &str, args: &HashMap<String, MessageArg>) -> Option<String> {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(name.as_str(), value.to_fluent());
+        }
+        self.message(locale, key, Some(&fluent_args))
+    }
+
+    /// Subject and body of the transactional email `name`, e.g. `welcome`, every argument is text
+    pub fn email(&self, locale: &LanguageIdentifier, name: &str, args: &HashMap<String, String>) -> Option<LocalizedEmail> {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(name.as_str(), FluentValue::from(value.as_str()));
+        }
+        Some(LocalizedEmail {
+            subject: self.message(locale, &format!("email-{}-subject", name), Some(&fluent_args))?,
+            body: self.message(locale, &format!("email-{}-body", name), Some(&fluent_args))?,
+        })
+    }
+
+    fn format(&self, locale: &LanguageIdentifier, key: &str, args: Option<&FluentArgs>) -> Option<String> {
+        let bundle = self.bundles.get(locale)?;
+        let pattern = bundle.get_message(key)?.value()?;
+        let mut errors = vec![];
+        let value = bundle.format_pattern(pattern, args, &mut errors);
+        if !errors.is_empty() {
+            tracing::warn!("Message {} in {}: {:?}", key, locale, errors);
+        }
+        Some(value.into_owned())
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use poem::{
+    http::{header, Method, StatusCode},
+    Endpoint, EndpointExt, Request,
+};
+use poem_openapi::{payload::Json, OpenApi, OpenApiService};
+use serde_json::Value;
+
+use crate::app::capabilities::{
+    common::{
+        global_model::validation_error::ValidationError,
+        problem::{problem_details::ProblemDetails, problem_middleware::ProblemMiddleware},
+    },
+    iam::{entities::users::Validator, enums::auth_error::AuthError},
+};
+
+use super::{
+    i18n_service::{I18nService, MessageArg},
+    locale_middleware::LocaleMiddleware,
+};
+
+struct Api;
+
+#[OpenApi]
+impl Api {
+    #[oai(path = "/register", method = "post")]
+    async fn register(&self) -> Json<ProblemDetails> {
+        let errors = Validator {
+            first_name: String::from("J"),
+            last_name: String::from("Doe"),
+            email: String::from("jane@example.com"),
+        }
+        .errors();
+        Json(ProblemDetails::from(AuthError::Validation(errors)))
+    }
+}
+
+#[test]
+fn should_negotiate_supported_locales() {
+    let i18n = I18nService::new("en");
+
+    assert_eq!(i18n.negotiate(Some("de-AT,de;q=0.9,en;q=0.5")).to_string(), "de");
+    assert_eq!(i18n.negotiate(Some("fr-FR, es;q=0.8")).to_string(), "es");
+    assert_eq!(i18n.negotiate(Some("ja")).to_string(), "en");
+    assert_eq!(i18n.negotiate(None).to_string(), "en");
+    assert_eq!(i18n.find("es-MX").map(|l| l.to_string()), Some(String::from("es")));
+    assert_eq!(i18n.find("ja"), None);
+}
+
+#[test]
+fn should_render_messages_and_emails_with_arguments() {
+    let i18n = I18nService::new("en");
+    let de = i18n.find("de").unwrap();
+    let args = HashMap::from([(String::from("min"), MessageArg::Number(12))]);
+
+    assert_eq!(
+        i18n.message_with(&de, "validation-min_length", &args).unwrap(),
+        "Das Passwort muss mindestens 12 Zeichen lang sein."
+    );
+    // unknown keys leave the fallback to the caller
+    assert_eq!(i18n.message_with(&de, "missing", &args), None);
+
+    let args = HashMap::from([
+        (String::from("first_name"), String::from("Jane")),
+        (String::from("email"), String::from("jane@example.com")),
+    ]);
+    let email = i18n.email(&de, "welcome", &args).unwrap();
+    assert_eq!(email.subject, "Willkommen, Jane!");
+    assert!(email.body.contains("jane@example.com"));
+}
+
+#[test]
+fn should_keep_text_arguments_that_look_like_numbers() {
+    let i18n = I18nService::new("en");
+    let en = i18n.find("en").unwrap();
+
+    for event_type in ["1e3", "007", "Inf", "NaN"] {
+        let error = ValidationError::new("event_types", "unknown_event_type", String::new()).with_arg("event_type", event_type);
+        assert_eq!(
+            i18n.message_with(&en, "validation-unknown_event_type", &error.args).unwrap(),
+            format!("Unknown event type {}.", event_type)
+        );
+    }
+
+    let args = HashMap::from([
+        (String::from("first_name"), String::from("Nan")),
+        (String::from("email"), String::from("nan@example.com")),
+    ]);
+    assert_eq!(i18n.email(&en, "welcome", &args).unwrap().subject, "Welcome, Nan!");
+}
+
+#[tokio::test]
+async fn should_localize_problems_from_accept_language() {
+    let app = OpenApiService::new(Api, "test", "1.0")
+        .with(ProblemMiddleware)
+        .with(LocaleMiddleware::new(I18nService::new("en")));
+
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri_str("/register")
+        .header(header::ACCEPT_LANGUAGE, "de-DE,de;q=0.9")
+        .finish();
+    let resp = app.get_response(req).await;
+    assert_eq!(resp.headers()[header::CONTENT_LANGUAGE], "de");
+    let body: Value = resp.into_body().into_json().await.unwrap();
+    assert_eq!(body["title"], "Ungültige Anfrage");
+    assert_eq!(body["detail"], "Einige Felder sind ungültig.");
+    assert_eq!(body["errors"][0]["field"], "first_name");
+    assert_eq!(body["errors"][0]["rule"], "name_length");
+    assert_eq!(body["errors"][0]["message"], "Der Name muss mindestens 2 Zeichen lang sein.");
+
+    let req = Request::builder()
+        .uri_str("/missing")
+        .header(header::ACCEPT_LANGUAGE, "es")
+        .finish();
+    let resp = app.get_response(req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let body: Value = resp.into_body().into_json().await.unwrap();
+    assert_eq!(body["title"], "No encontrado");
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use poem::{
+    http::{header, HeaderValue},
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use unic_langid::LanguageIdentifier;
+
+use super::i18n_service::{I18nService, MessageArg};
+
+tokio::task_local! {
+    static CURRENT: (I18nService, LanguageIdentifier);
+}
+
+/// Locale negotiated from `Accept-Language` for the current request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale(pub LanguageIdentifier);
+
+/// Locale of the request handled by the current task, if any
+pub fn current_locale() -> Option<LanguageIdentifier> {
+    CURRENT.try_with(|(_, locale)| locale.clone()).ok()
+}
+
+/// Message `key` in the locale of the current request, `None` outside of a request
+pub fn translate(key: &str, args: &HashMap<String, MessageArg>) -> Option<String> {
+    CURRENT
+        .try_with(|(i18n, locale)| i18n.message_with(locale, key, args))
+        .ok()
+        .flatten()
+}
+
+/// Negotiates the response locale, available as request data and through [`translate`],
+/// and reports it in `Content-Language`
+pub struct LocaleMiddleware {
+    i18n: I18nService,
+}
+
+impl LocaleMiddleware {
+    pub fn new(i18n: I18nService) -> Self {
+        Self { i18n }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for LocaleMiddleware {
+    type Output = LocaleEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        LocaleEndpoint {
+            inner: ep,
+            i18n: self.i18n.clone(),
+        }
+    }
+}
+
+pub struct LocaleEndpoint<E> {
+    inner: E,
+    i18n: I18nService,
+}
+
+impl<E: Endpoint> Endpoint for LocaleEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let accept_language = req
+            .headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok());
+        let locale = self.i18n.negotiate(accept_language);
+        req.extensions_mut().insert(Locale(locale.clone()));
+
+        let result = CURRENT
+            .scope((self.i18n.clone(), locale.clone()), self.inner.call(req))
+            .await;
+        let mut resp = match result {
+            Ok(resp) => resp.into_response(),
+            Err(e) => e.into_response(),
+        };
+        if let Ok(value) = HeaderValue::from_str(&locale.to_string()) {
+            resp.headers_mut().insert(header::CONTENT_LANGUAGE, value);
+        }
+        Ok(resp)
+    }
+}
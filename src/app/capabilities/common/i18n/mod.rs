@@ -0,0 +1,5 @@
+pub mod i18n_service;
+pub mod locale_middleware;
+
+#[cfg(test)]
+mod i18n_service_test;
@@ -0,0 +1,58 @@
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub type JobFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Runs jobs of one kind. An `Err` is retried with backoff until the job runs out of attempts.
+///
+/// Handlers may run more than once for the same job, when a worker dies mid-job, so they
+/// should be idempotent.
+pub trait JobHandler: Send + Sync + 'static {
+    /// Stored in the `kind` column, never rename it while jobs of the kind are queued
+    const KIND: &'static str;
+    type Payload: Serialize + DeserializeOwned + Send + 'static;
+
+    fn handle(&self, payload: Self::Payload) -> JobFuture<'_>;
+}
+
+type ErasedHandler = Box<dyn Fn(serde_json::Value) -> JobFuture<'static> + Send + Sync>;
+
+/// Handlers by job kind, filled in at bootstrap
+#[derive(Default)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, ErasedHandler>,
+}
+
+impl JobRegistry {
+    pub fn register<H: JobHandler>(&mut self, handler: H) -> &mut Self {
+        let handler = Arc::new(handler);
+        let run: ErasedHandler = Box::new(move |payload| {
+            let handler = handler.clone();
+            Box::pin(async move {
+                let payload = serde_json::from_value::<H::Payload>(payload)
+                    .map_err(|e| format!("invalid payload: {}", e))?;
+                handler.handle(payload).await
+            })
+        });
+        if self.handlers.insert(H::KIND, run).is_some() {
+            tracing::warn!("Job handler for {} registered twice, keeping the last one", H::KIND);
+        }
+        self
+    }
+
+    pub fn kinds(&self) -> Vec<&'static str> {
+        self.handlers.keys().copied().collect()
+    }
+
+    /// Run the handler of `kind`, failing for unknown kinds
+    pub fn run(&self, kind: &str, payload: serde_json::Value) -> JobFuture<'static> {
+        match self.handlers.get(kind) {
+            Some(run) => run(payload),
+            None => {
+                let error = format!("no handler for job kind {}", kind);
+                Box::pin(async move { Err(error) })
+            }
+        }
+    }
+}
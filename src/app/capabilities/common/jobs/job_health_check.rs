@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use crate::app::capabilities::common::health::health_check::{CheckFuture, HealthCheck};
+
+use super::job_service::JobService;
+
+/// Fails when the oldest due job waited longer than `jobs.max_queue_delay_secs`, i.e. no worker
+/// keeps up with the queue. Optional, requests are served without workers.
+pub struct JobQueueHealthCheck {
+    jobs: JobService,
+}
+
+impl JobQueueHealthCheck {
+    pub fn new(jobs: JobService) -> Self {
+        Self { jobs }
+    }
+}
+
+impl HealthCheck for JobQueueHealthCheck {
+    fn name(&self) -> &str {
+        "job_queue"
+    }
+
+    fn required(&self) -> bool {
+        false
+    }
+
+    fn check(&self) -> CheckFuture<'_> {
+        Box::pin(async move {
+            let max_delay = Duration::from_secs(self.jobs.config().max_queue_delay_secs);
+            match self.jobs.oldest_pending_age().await.map_err(|e| e.to_string())? {
+                Some(age) if age > max_delay => Err(format!(
+                    "oldest due job waited {}s, more than {}s",
+                    age.as_secs(),
+                    max_delay.as_secs()
+                )),
+                _ => Ok(()),
+            }
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use migration::sea_orm;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, Statement, Value};
+
+use crate::app::capabilities::common::{config::app_config::JobsConfig, database::db_router::DbRouter};
+
+use super::job_handler::JobHandler;
+
+/// Skipped when a pending or running job has the same `unique_key`
+const ENQUEUE: &str = r#"
+INSERT INTO jobs (kind, payload, priority, max_attempts, unique_key, run_at)
+VALUES ($1, $2::jsonb, $3, $4, $5, now() + make_interval(secs => $6))
+ON CONFLICT (unique_key) WHERE status IN ('pending', 'running') DO NOTHING
+RETURNING id
+"#;
+
+/// Concurrent workers skip rows locked by each other instead of waiting on them
+const CLAIM: &str = r#"
+UPDATE jobs SET status = 'running', attempts = attempts + 1, locked_at = now(), locked_by = $1, updated_at = now()
+WHERE id = (
+    SELECT id FROM jobs
+    WHERE status = 'pending' AND run_at <= now()
+    ORDER BY priority DESC, run_at, id
+    FOR UPDATE SKIP LOCKED
+    LIMIT 1
+)
+RETURNING id, kind, payload::text AS payload, attempts, max_attempts
+"#;
+
+const COMPLETE: &str = r#"
+UPDATE jobs SET status = 'completed', locked_at = NULL, locked_by = NULL, updated_at = now() WHERE id = $1
+"#;
+
+/// `$3` is the retry delay, ignored once the job is dead
+const FAIL: &str = r#"
+UPDATE jobs SET
+    status = CASE WHEN attempts >= max_attempts THEN 'dead' ELSE 'pending' END,
+    run_at = now() + make_interval(secs => $3),
+    last_error = $2, locked_at = NULL, locked_by = NULL, updated_at = now()
+WHERE id = $1
+RETURNING status
+"#;
+
+/// Jobs of crashed workers go back to the queue, the attempt counts: jobs out of attempts are dead
+const RELEASE_STALE: &str = r#"
+UPDATE jobs SET status = CASE WHEN attempts >= max_attempts THEN 'dead' ELSE 'pending' END,
+    locked_at = NULL, locked_by = NULL, updated_at = now()
+WHERE status = 'running' AND locked_at < now() - make_interval(secs => $1)
+"#;
+
+/// Seconds since the oldest due pending job could have run, NULL when none is due
+const OLDEST_PENDING_AGE: &str = r#"
+SELECT EXTRACT(EPOCH FROM now() - min(run_at))::float8 AS age FROM jobs WHERE status = 'pending' AND run_at <= now()
+"#;
+
+/// How a job is queued
+#[derive(Debug, Clone, Default)]
+pub struct EnqueueOptions {
+    /// Higher runs first
+    pub priority: i32,
+    /// Defaults to `jobs.max_attempts`
+    pub max_attempts: Option<i32>,
+    pub delay: Duration,
+    /// Deduplicates jobs, a second job with the key is dropped while the first is queued
+    pub unique_key: Option<String>,
+}
+
+/// A job taken by a worker
+#[derive(Debug, Clone)]
+pub struct ClaimedJob {
+    pub id: i64,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub max_attempts: i32,
+}
+
+/// Jobs in the `jobs` table, shared by the server and the `worker` binary
+#[derive(Clone)]
+pub struct JobService {
+    db: DbRouter,
+    config: JobsConfig,
+}
+
+impl JobService {
+    pub fn new(db: DbRouter, config: &JobsConfig) -> Self {
+        Self {
+            db,
+            config: config.clone(),
+        }
+    }
+
+    pub fn config(&self) -> &JobsConfig {
+        &self.config
+    }
+
+    /// Queue a job for `H`, returns `None` when deduplicated by `unique_key`
+    pub async fn enqueue<H: JobHandler>(&self, payload: &H::Payload, options: EnqueueOptions) -> Result<Option<i64>, DbErr> {
+        self.enqueue_in::<H, _>(self.db.writer(), payload, options).await
+    }
+
+    /// Queue a job on `conn`, pass a transaction to create the job only if the transaction commits
+    pub async fn enqueue_in<H: JobHandler, C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        payload: &H::Payload,
+        options: EnqueueOptions,
+    ) -> Result<Option<i64>, DbErr> {
+        let payload = serde_json::to_string(payload).map_err(|e| DbErr::Custom(e.to_string()))?;
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            ENQUEUE,
+            [
+                H::KIND.into(),
+                payload.into(),
+                options.priority.into(),
+                options.max_attempts.unwrap_or(self.config.max_attempts).into(),
+                options.unique_key.into(),
+                options.delay.as_secs_f64().into(),
+            ],
+        );
+        let row = conn.query_one(statement).await?;
+        row.map(|row| row.try_get("", "id")).transpose()
+    }
+
+    /// Take the next due job, by priority then due date
+    pub async fn claim(&self, worker: &str) -> Result<Option<ClaimedJob>, DbErr> {
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, CLAIM, [worker.into()]);
+        let Some(row) = self.db.writer().query_one(statement).await? else {
+            return Ok(None);
+        };
+        let payload: String = row.try_get("", "payload")?;
+        Ok(Some(ClaimedJob {
+            id: row.try_get("", "id")?,
+            kind: row.try_get("", "kind")?,
+            payload: serde_json::from_str(&payload).map_err(|e| DbErr::Custom(e.to_string()))?,
+            attempts: row.try_get("", "attempts")?,
+            max_attempts: row.try_get("", "max_attempts")?,
+        }))
+    }
+
+    pub async fn complete(&self, id: i64) -> Result<(), DbErr> {
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, COMPLETE, [id.into()]);
+        self.db.writer().execute(statement).await.map(|_| ())
+    }
+
+    /// Schedule a retry, or move the job to the dead-letter state once out of attempts.
+    /// Returns whether the job is dead.
+    pub async fn fail(&self, job: &ClaimedJob, error: &str) -> Result<bool, DbErr> {
+        let delay = retry_delay(job.attempts, &self.config);
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            FAIL,
+            [job.id.into(), error.into(), delay.as_secs_f64().into()],
+        );
+        let row = self.db.writer().query_one(statement).await?;
+        let status: Option<String> = row.map(|row| row.try_get("", "status")).transpose()?;
+        Ok(status.as_deref() == Some("dead"))
+    }
+
+    /// Requeue running jobs locked for longer than `jobs.lock_timeout_secs`, or move them to dead
+    /// when they are out of attempts
+    pub async fn release_stale(&self) -> Result<u64, DbErr> {
+        let timeout = Value::from(self.config.lock_timeout_secs as f64);
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, RELEASE_STALE, [timeout]);
+        Ok(self.db.writer().execute(statement).await?.rows_affected())
+    }
+
+    /// How long the oldest due pending job has been waiting for a worker
+    pub async fn oldest_pending_age(&self) -> Result<Option<Duration>, DbErr> {
+        let statement = Statement::from_string(DbBackend::Postgres, OLDEST_PENDING_AGE);
+        let Some(row) = self.db.writer().query_one(statement).await? else {
+            return Ok(None);
+        };
+        let age: Option<f64> = row.try_get("", "age")?;
+        Ok(age.map(|age| Duration::from_secs_f64(age.max(0.0))))
+    }
+}
+
+/// Exponential backoff after the `attempts`th failed attempt, capped at `backoff_max_secs`
+pub fn retry_delay(attempts: i32, config: &JobsConfig) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 32) as u32;
+    let secs = config.backoff_base_secs.saturating_mul(2u64.saturating_pow(exponent));
+    Duration::from_secs(secs.min(config.backoff_max_secs))
+}
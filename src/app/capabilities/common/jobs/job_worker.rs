@@ -0,0 +1,171 @@
+use std::{panic::AssertUnwindSafe, sync::Arc, time::Duration};
+
+use futures_util::FutureExt;
+use prometheus::IntCounterVec;
+use tokio::{
+    sync::{watch, Semaphore},
+    task::JoinHandle,
+};
+
+use crate::app::capabilities::common::metrics::metrics_service::MetricsService;
+
+use super::{
+    job_handler::JobRegistry,
+    job_service::{ClaimedJob, JobService},
+};
+
+/// Wait between two requeues of jobs left running by crashed workers
+const RELEASE_STALE_EVERY: Duration = Duration::from_secs(60);
+
+/// Polls the `jobs` table and runs up to `jobs.concurrency` jobs at a time
+pub struct JobWorker {
+    jobs: JobService,
+    registry: Arc<JobRegistry>,
+    processed: IntCounterVec,
+    /// Recorded in `locked_by`
+    name: String,
+    release_stale_every: Duration,
+}
+
+/// Stops a spawned worker
+pub struct WorkerHandle {
+    stop: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl WorkerHandle {
+    /// Stop claiming jobs and wait for the running ones to finish
+    pub async fn stop(self) {
+        let _ = self.stop.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("Job worker failed: {}", e);
+        }
+    }
+}
+
+impl JobWorker {
+    pub fn new(jobs: JobService, registry: JobRegistry, metrics: &MetricsService) -> Self {
+        Self {
+            jobs,
+            registry: Arc::new(registry),
+            processed: metrics.counter_vec("jobs_processed_total", "Finished job attempts", &["kind", "outcome"]),
+            name: format!("{}-{}", std::process::id(), uuid::Uuid::new_v4().simple()),
+            release_stale_every: RELEASE_STALE_EVERY,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_release_stale_every(mut self, every: Duration) -> Self {
+        self.release_stale_every = every;
+        self
+    }
+
+    pub fn spawn(self) -> WorkerHandle {
+        let (stop, stopped) = watch::channel(false);
+        let task = tokio::spawn(self.run(stopped));
+        WorkerHandle { stop, task }
+    }
+
+    async fn run(self, mut stopped: watch::Receiver<bool>) {
+        let config = self.jobs.config().clone();
+        let permits = Arc::new(Semaphore::new(config.concurrency));
+        let poll_interval = Duration::from_millis(config.poll_interval_ms);
+        let worker = Arc::new(self);
+        // on its own task, so a busy queue or all permits taken don't hold it back
+        let releaser = tokio::spawn(worker.clone().release_stale(stopped.clone()));
+        tracing::info!(worker = %worker.name, kinds = ?worker.registry.kinds(), "Job worker started");
+
+        while !*stopped.borrow() {
+            let permit = tokio::select! {
+                permit = permits.clone().acquire_owned() => permit.expect("semaphore is never closed"),
+                _ = stopped.changed() => break,
+            };
+            let job = match worker.jobs.claim(&worker.name).await {
+                Ok(job) => job,
+                Err(e) => {
+                    tracing::error!("Failed to claim job: {}", e);
+                    None
+                }
+            };
+            let Some(job) = job else {
+                drop(permit);
+                tokio::select! {
+                    _ = tokio::time::sleep(poll_interval) => {},
+                    _ = stopped.changed() => {},
+                }
+                continue;
+            };
+            let worker = worker.clone();
+            tokio::spawn(async move {
+                worker.execute(job).await;
+                drop(permit);
+            });
+        }
+
+        // every permit back means no job is running anymore
+        let _ = permits.acquire_many(config.concurrency as u32).await;
+        if let Err(e) = releaser.await {
+            tracing::error!("Stale job release failed: {}", e);
+        }
+        tracing::info!(worker = %worker.name, "Job worker stopped");
+    }
+
+    /// Requeue the jobs of crashed workers every `RELEASE_STALE_EVERY` until stopped
+    async fn release_stale(self: Arc<Self>, mut stopped: watch::Receiver<bool>) {
+        let mut interval = tokio::time::interval(self.release_stale_every);
+        while !*stopped.borrow() {
+            tokio::select! {
+                _ = interval.tick() => {},
+                _ = stopped.changed() => break,
+            }
+            match self.jobs.release_stale().await {
+                Ok(0) => {}
+                Ok(count) => tracing::warn!("Requeued {} jobs of crashed workers", count),
+                Err(e) => tracing::error!("Failed to requeue stale jobs: {}", e),
+            }
+        }
+    }
+
+    async fn execute(&self, job: ClaimedJob) {
+        let span = tracing::info_span!("job", id = job.id, kind = %job.kind, attempt = job.attempts);
+        span.in_scope(|| tracing::debug!("Job started"));
+        // a panicking handler fails its job instead of leaving it running until it is stale
+        let result = match AssertUnwindSafe(self.registry.run(&job.kind, job.payload.clone())).catch_unwind().await {
+            Ok(result) => result,
+            Err(panic) => Err(panic_message(panic.as_ref())),
+        };
+        let outcome = match result {
+            Ok(_) => match self.jobs.complete(job.id).await {
+                Ok(_) => "completed",
+                Err(e) => {
+                    span.in_scope(|| tracing::error!("Failed to complete job: {}", e));
+                    "error"
+                }
+            },
+            Err(error) => match self.jobs.fail(&job, &error).await {
+                Ok(true) => {
+                    span.in_scope(|| tracing::error!(error, "Job failed for the last time, moved to dead"));
+                    "dead"
+                }
+                Ok(false) => {
+                    span.in_scope(|| tracing::warn!(error, "Job failed, retrying"));
+                    "retried"
+                }
+                Err(e) => {
+                    span.in_scope(|| tracing::error!("Failed to record job failure: {}", e));
+                    "error"
+                }
+            },
+        };
+        self.processed.with_label_values(&[&job.kind, outcome]).inc();
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown cause");
+    format!("job panicked: {}", message)
+}
@@ -0,0 +1,334 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use migration::sea_orm::{ConnectionTrait, DbBackend, Statement, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::app::capabilities::common::{
+    config::app_config::JobsConfig,
+    database::{db_router::DbRouter, test_postgres::test_postgres},
+    health::health_check::HealthCheck,
+    metrics::metrics_service::MetricsService,
+};
+
+use super::{
+    job_handler::{JobFuture, JobHandler, JobRegistry},
+    job_health_check::JobQueueHealthCheck,
+    job_service::{retry_delay, EnqueueOptions, JobService},
+    job_worker::JobWorker,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Greeting {
+    name: String,
+}
+
+struct GreetingJob {
+    greeted: Arc<Mutex<Vec<String>>>,
+}
+
+impl JobHandler for GreetingJob {
+    const KIND: &'static str = "greeting";
+    type Payload = Greeting;
+
+    fn handle(&self, payload: Greeting) -> JobFuture<'_> {
+        Box::pin(async move {
+            if payload.name.is_empty() {
+                return Err(String::from("nobody to greet"));
+            }
+            self.greeted.lock().unwrap().push(payload.name);
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test]
+async fn should_run_the_handler_of_a_kind() {
+    let greeted = Arc::new(Mutex::new(vec![]));
+    let mut registry = JobRegistry::default();
+    registry.register(GreetingJob { greeted: greeted.clone() });
+    assert_eq!(registry.kinds(), vec!["greeting"]);
+
+    assert!(registry.run("greeting", serde_json::json!({"name": "Jane"})).await.is_ok());
+    assert_eq!(*greeted.lock().unwrap(), vec![String::from("Jane")]);
+
+    let error = registry.run("greeting", serde_json::json!({"name": ""})).await.unwrap_err();
+    assert_eq!(error, "nobody to greet");
+    let error = registry.run("greeting", serde_json::json!({"nom": "Jane"})).await.unwrap_err();
+    assert!(error.starts_with("invalid payload"));
+    let error = registry.run("export", serde_json::json!({})).await.unwrap_err();
+    assert_eq!(error, "no handler for job kind export");
+}
+
+#[test]
+fn should_back_off_exponentially() {
+    let config = JobsConfig {
+        backoff_base_secs: 10,
+        backoff_max_secs: 300,
+        ..JobsConfig::default()
+    };
+    assert_eq!(retry_delay(1, &config), Duration::from_secs(10));
+    assert_eq!(retry_delay(2, &config), Duration::from_secs(20));
+    assert_eq!(retry_delay(4, &config), Duration::from_secs(80));
+    assert_eq!(retry_delay(6, &config), Duration::from_secs(300));
+    assert_eq!(retry_delay(1000, &config), Duration::from_secs(300));
+}
+
+/// Waits until notified, to keep a worker busy
+struct BlockingJob {
+    release: Arc<Notify>,
+}
+
+impl JobHandler for BlockingJob {
+    const KIND: &'static str = "blocking";
+    type Payload = ();
+
+    fn handle(&self, _payload: ()) -> JobFuture<'_> {
+        Box::pin(async move {
+            self.release.notified().await;
+            Ok(())
+        })
+    }
+}
+
+struct PanickingJob;
+
+impl JobHandler for PanickingJob {
+    const KIND: &'static str = "panicking";
+    type Payload = ();
+
+    fn handle(&self, _payload: ()) -> JobFuture<'_> {
+        Box::pin(async move { panic!("out of coffee") })
+    }
+}
+
+fn greeting(name: &str) -> Greeting {
+    Greeting { name: name.to_string() }
+}
+
+async fn status(db: &DbRouter, id: i64) -> (String, i32, Option<String>) {
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT status, attempts, locked_by FROM jobs WHERE id = $1",
+        [id.into()],
+    );
+    let row = db.writer().query_one(statement).await.unwrap().unwrap();
+    (
+        row.try_get("", "status").unwrap(),
+        row.try_get("", "attempts").unwrap(),
+        row.try_get("", "locked_by").unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn should_skip_jobs_locked_by_other_workers() {
+    let Some(db) = test_postgres().await else { return };
+    let jobs = JobService::new(db.clone(), &JobsConfig::default());
+    let first = jobs.enqueue::<GreetingJob>(&greeting("first"), EnqueueOptions::default()).await.unwrap().unwrap();
+    let urgent = EnqueueOptions {
+        priority: 10,
+        ..EnqueueOptions::default()
+    };
+    let second = jobs.enqueue::<GreetingJob>(&greeting("second"), urgent).await.unwrap().unwrap();
+
+    // another worker is in the middle of claiming the urgent job
+    let txn = db.writer().begin().await.unwrap();
+    let lock = Statement::from_sql_and_values(DbBackend::Postgres, "SELECT id FROM jobs WHERE id = $1 FOR UPDATE", [second.into()]);
+    txn.query_one(lock).await.unwrap();
+
+    let claimed = jobs.claim("worker-a").await.unwrap().unwrap();
+    assert_eq!(claimed.id, first);
+    assert_eq!(claimed.payload, serde_json::json!({"name": "first"}));
+    assert!(jobs.claim("worker-a").await.unwrap().is_none());
+    txn.rollback().await.unwrap();
+
+    assert_eq!(jobs.claim("worker-b").await.unwrap().unwrap().id, second);
+    assert_eq!(status(&db, second).await, (String::from("running"), 1, Some(String::from("worker-b"))));
+}
+
+#[tokio::test]
+async fn should_drop_duplicates_while_a_job_with_the_key_is_queued() {
+    let Some(db) = test_postgres().await else { return };
+    let jobs = JobService::new(db, &JobsConfig::default());
+    let unique = || EnqueueOptions {
+        unique_key: Some(String::from("greet:jane")),
+        ..EnqueueOptions::default()
+    };
+
+    let id = jobs.enqueue::<GreetingJob>(&greeting("Jane"), unique()).await.unwrap();
+    assert!(id.is_some());
+    assert_eq!(jobs.enqueue::<GreetingJob>(&greeting("Jane"), unique()).await.unwrap(), None);
+    let claimed = jobs.claim("worker").await.unwrap().unwrap();
+    assert_eq!(jobs.enqueue::<GreetingJob>(&greeting("Jane"), unique()).await.unwrap(), None);
+
+    jobs.complete(claimed.id).await.unwrap();
+    assert!(jobs.enqueue::<GreetingJob>(&greeting("Jane"), unique()).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn should_retry_then_move_jobs_out_of_attempts_to_dead() {
+    let Some(db) = test_postgres().await else { return };
+    let config = JobsConfig {
+        backoff_base_secs: 0,
+        ..JobsConfig::default()
+    };
+    let jobs = JobService::new(db.clone(), &config);
+    let options = EnqueueOptions {
+        max_attempts: Some(2),
+        ..EnqueueOptions::default()
+    };
+    let id = jobs.enqueue::<GreetingJob>(&greeting(""), options).await.unwrap().unwrap();
+
+    let job = jobs.claim("worker").await.unwrap().unwrap();
+    assert!(!jobs.fail(&job, "nobody to greet").await.unwrap());
+    assert_eq!(status(&db, id).await, (String::from("pending"), 1, None));
+
+    let job = jobs.claim("worker").await.unwrap().unwrap();
+    assert_eq!(job.attempts, 2);
+    assert!(jobs.fail(&job, "nobody to greet").await.unwrap());
+    assert_eq!(status(&db, id).await, (String::from("dead"), 2, None));
+    assert!(jobs.claim("worker").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn should_requeue_jobs_locked_for_longer_than_the_timeout() {
+    let Some(db) = test_postgres().await else { return };
+    let jobs = JobService::new(db.clone(), &JobsConfig::default());
+    let id = jobs.enqueue::<GreetingJob>(&greeting("Jane"), EnqueueOptions::default()).await.unwrap().unwrap();
+    jobs.claim("crashed").await.unwrap().unwrap();
+    assert_eq!(jobs.release_stale().await.unwrap(), 0);
+
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "UPDATE jobs SET locked_at = now() - interval '1 hour' WHERE id = $1",
+        [id.into()],
+    );
+    db.writer().execute(statement).await.unwrap();
+    assert_eq!(jobs.release_stale().await.unwrap(), 1);
+    assert_eq!(status(&db, id).await, (String::from("pending"), 1, None));
+    assert_eq!(jobs.claim("worker").await.unwrap().unwrap().attempts, 2);
+}
+
+#[tokio::test]
+async fn should_move_stale_jobs_out_of_attempts_to_dead() {
+    let Some(db) = test_postgres().await else { return };
+    let jobs = JobService::new(db.clone(), &JobsConfig::default());
+    let options = EnqueueOptions {
+        max_attempts: Some(1),
+        ..EnqueueOptions::default()
+    };
+    let id = jobs.enqueue::<GreetingJob>(&greeting("Jane"), options).await.unwrap().unwrap();
+    jobs.claim("crashed").await.unwrap().unwrap();
+
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "UPDATE jobs SET locked_at = now() - interval '1 hour' WHERE id = $1",
+        [id.into()],
+    );
+    db.writer().execute(statement).await.unwrap();
+    assert_eq!(jobs.release_stale().await.unwrap(), 1);
+    assert_eq!(status(&db, id).await, (String::from("dead"), 1, None));
+    assert!(jobs.claim("worker").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn should_fail_jobs_whose_handler_panics() {
+    let Some(db) = test_postgres().await else { return };
+    let config = JobsConfig {
+        poll_interval_ms: 10,
+        backoff_base_secs: 0,
+        ..JobsConfig::default()
+    };
+    let jobs = JobService::new(db.clone(), &config);
+    let options = EnqueueOptions {
+        max_attempts: Some(2),
+        ..EnqueueOptions::default()
+    };
+    let id = jobs.enqueue::<PanickingJob>(&(), options).await.unwrap().unwrap();
+
+    let mut registry = JobRegistry::default();
+    registry.register(PanickingJob);
+    let worker = JobWorker::new(jobs.clone(), registry, &MetricsService::new()).spawn();
+    let dead = async {
+        while status(&db, id).await.0 != "dead" {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    let result = tokio::time::timeout(Duration::from_secs(5), dead).await;
+    worker.stop().await;
+    assert!(result.is_ok(), "the panicking job was not failed");
+    assert_eq!(status(&db, id).await, (String::from("dead"), 2, None));
+
+    let statement = Statement::from_sql_and_values(DbBackend::Postgres, "SELECT last_error FROM jobs WHERE id = $1", [id.into()]);
+    let row = db.writer().query_one(statement).await.unwrap().unwrap();
+    let last_error: String = row.try_get("", "last_error").unwrap();
+    assert_eq!(last_error, "job panicked: out of coffee");
+}
+
+#[tokio::test]
+async fn should_release_stale_jobs_while_every_permit_is_taken() {
+    let Some(db) = test_postgres().await else { return };
+    let config = JobsConfig {
+        concurrency: 1,
+        poll_interval_ms: 10,
+        ..JobsConfig::default()
+    };
+    let jobs = JobService::new(db.clone(), &config);
+    let blocking = jobs.enqueue::<BlockingJob>(&(), EnqueueOptions::default()).await.unwrap().unwrap();
+
+    let release = Arc::new(Notify::new());
+    let greeted = Arc::new(Mutex::new(vec![]));
+    let mut registry = JobRegistry::default();
+    registry
+        .register(BlockingJob { release: release.clone() })
+        .register(GreetingJob { greeted: greeted.clone() });
+    let worker = JobWorker::new(jobs.clone(), registry, &MetricsService::new())
+        .with_release_stale_every(Duration::from_millis(20))
+        .spawn();
+    while status(&db, blocking).await.0 != "running" {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // a job of a crashed worker, once the only permit is taken
+    let stale = jobs.enqueue::<GreetingJob>(&greeting("Jane"), EnqueueOptions::default()).await.unwrap().unwrap();
+    jobs.claim("crashed").await.unwrap().unwrap();
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "UPDATE jobs SET locked_at = now() - interval '1 hour' WHERE id = $1",
+        [stale.into()],
+    );
+    db.writer().execute(statement).await.unwrap();
+
+    let released = async {
+        while status(&db, stale).await.2.as_deref() == Some("crashed") {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    };
+    let result = tokio::time::timeout(Duration::from_secs(5), released).await;
+    release.notify_one();
+    worker.stop().await;
+    assert!(result.is_ok(), "the stale job was not released while the worker was busy");
+}
+
+#[tokio::test]
+async fn should_report_a_queue_no_worker_keeps_up_with() {
+    let Some(db) = test_postgres().await else { return };
+    let jobs = JobService::new(db.clone(), &JobsConfig::default());
+    let check = JobQueueHealthCheck::new(jobs.clone());
+    assert!(!check.required());
+    assert!(check.check().await.is_ok());
+
+    let id = jobs.enqueue::<GreetingJob>(&greeting("Jane"), EnqueueOptions::default()).await.unwrap().unwrap();
+    assert!(check.check().await.is_ok());
+    let statement = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "UPDATE jobs SET run_at = now() - interval '10 minutes' WHERE id = $1",
+        [id.into()],
+    );
+    db.writer().execute(statement).await.unwrap();
+    let error = check.check().await.unwrap_err();
+    assert!(error.starts_with("oldest due job waited 600s"), "{}", error);
+}
@@ -0,0 +1,7 @@
+pub mod job_handler;
+pub mod job_health_check;
+pub mod job_service;
+pub mod job_worker;
+
+#[cfg(test)]
+mod jobs_test;
@@ -0,0 +1,138 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+type HookFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type HookFn = Box<dyn FnOnce() -> HookFuture + Send>;
+
+/// Run order of shutdown hooks, lower runs first
+pub mod hook_order {
+    /// Stop taking new background work
+    pub const STOP_WORKERS: i32 = 100;
+    /// Flush buffered work such as queued jobs or telemetry
+    pub const FLUSH: i32 = 200;
+    /// Close connection pools, nothing may touch the database afterwards
+    pub const CLOSE_POOLS: i32 = 900;
+}
+
+struct ShutdownHook {
+    name: String,
+    order: i32,
+    run: HookFn,
+}
+
+/// Tracks readiness and the ordered hooks run when the server shuts down.
+///
+/// On SIGTERM/SIGINT readiness flips to failing first, so load balancers stop sending
+/// traffic, then the server drains in-flight requests and finally the hooks run.
+#[derive(Clone)]
+pub struct LifecycleService {
+    started: Arc<AtomicBool>,
+    ready: Arc<AtomicBool>,
+    hooks: Arc<Mutex<Vec<ShutdownHook>>>,
+    hook_timeout: Duration,
+}
+
+impl LifecycleService {
+    pub fn new(hook_timeout: Duration) -> Self {
+        Self {
+            started: Arc::new(AtomicBool::new(false)),
+            ready: Arc::new(AtomicBool::new(false)),
+            hooks: Arc::new(Mutex::new(vec![])),
+            hook_timeout,
+        }
+    }
+
+    /// Whether startup finished, stays true through shutdown
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    /// Mark startup as finished and the app as ready for traffic
+    pub fn mark_started(&self) {
+        self.started.store(true, Ordering::SeqCst);
+        self.set_ready(true);
+    }
+
+    /// Whether the app should receive traffic
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    pub fn set_ready(&self, ready: bool) {
+        self.ready.store(ready, Ordering::SeqCst);
+    }
+
+    /// Register a hook to run on shutdown, see `hook_order` for the order values in use
+    pub fn on_shutdown<F, Fut>(&self, name: &str, order: i32, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut hooks = self.hooks.lock().unwrap();
+        hooks.push(ShutdownHook {
+            name: name.to_string(),
+            order,
+            run: Box::new(move || Box::pin(hook())),
+        });
+    }
+
+    /// Wait for a shutdown signal, then mark the app as not ready and give load
+    /// balancers `readiness_delay` to notice before the server stops accepting connections
+    pub async fn wait_for_shutdown(self, readiness_delay: Duration) {
+        shutdown_signal().await;
+        tracing::info!("Shutdown signal received, failing readiness");
+        self.set_ready(false);
+        tokio::time::sleep(readiness_delay).await;
+        tracing::info!("Draining connections");
+    }
+
+    /// Run every registered hook once, in order, each bounded by the hook timeout
+    pub async fn run_shutdown_hooks(&self) {
+        let mut hooks: Vec<ShutdownHook> = std::mem::take(&mut *self.hooks.lock().unwrap());
+        // stable sort keeps registration order for equal order values
+        hooks.sort_by_key(|hook| hook.order);
+        for hook in hooks {
+            tracing::info!("Running shutdown hook {}", hook.name);
+            if tokio::time::timeout(self.hook_timeout, (hook.run)()).await.is_err() {
+                tracing::error!("Shutdown hook {} timed out", hook.name);
+            }
+        }
+    }
+}
+
+/// Resolves on SIGINT, or SIGTERM on unix
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,45 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::lifecycle_service::*;
+
+#[tokio::test]
+async fn should_run_hooks_in_order_once() {
+    let lifecycle = LifecycleService::new(Duration::from_secs(1));
+    let calls = Arc::new(Mutex::new(vec![]));
+
+    for (name, order) in [("pool", hook_order::CLOSE_POOLS), ("workers", hook_order::STOP_WORKERS), ("flush", hook_order::FLUSH)] {
+        let calls = calls.clone();
+        lifecycle.on_shutdown(name, order, move || async move {
+            calls.lock().unwrap().push(name);
+        });
+    }
+
+    lifecycle.run_shutdown_hooks().await;
+    lifecycle.run_shutdown_hooks().await;
+    assert_eq!(*calls.lock().unwrap(), vec!["workers", "flush", "pool"]);
+}
+
+#[tokio::test]
+async fn should_not_wait_forever_on_a_hook() {
+    let lifecycle = LifecycleService::new(Duration::from_millis(10));
+    let finished = Arc::new(Mutex::new(false));
+    lifecycle.on_shutdown("stuck", 0, std::future::pending::<()>);
+    let flag = finished.clone();
+    lifecycle.on_shutdown("next", 1, move || async move {
+        *flag.lock().unwrap() = true;
+    });
+
+    lifecycle.run_shutdown_hooks().await;
+    assert!(*finished.lock().unwrap());
+}
+
+#[test]
+fn should_track_readiness() {
+    let lifecycle = LifecycleService::new(Duration::from_secs(1));
+    assert!(!lifecycle.is_ready());
+    lifecycle.set_ready(true);
+    assert!(lifecycle.clone().is_ready());
+}
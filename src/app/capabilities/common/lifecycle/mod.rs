@@ -0,0 +1,4 @@
+pub mod lifecycle_service;
+
+#[cfg(test)]
+mod lifecycle_service_test;
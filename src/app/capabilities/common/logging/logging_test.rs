@@ -0,0 +1,59 @@
+use poem::{http::StatusCode, Endpoint, EndpointExt, Request};
+use poem_openapi::{payload::Json, OpenApi, OpenApiService};
+use serde_json::json;
+
+use crate::app::capabilities::common::problem::problem_details::ProblemDetails;
+
+use super::{
+    redact::redact_json,
+    request_id::{RequestIdMiddleware, REQUEST_ID_HEADER},
+};
+
+struct Api;
+
+#[OpenApi]
+impl Api {
+    #[oai(path = "/fail", method = "get")]
+    async fn fail(&self) -> Json<ProblemDetails> {
+        Json(ProblemDetails::new(StatusCode::BAD_REQUEST))
+    }
+}
+
+#[tokio::test]
+async fn should_propagate_or_generate_request_ids() {
+    let app = OpenApiService::new(Api, "test", "1.0").with(RequestIdMiddleware);
+
+    let resp = app
+        .get_response(Request::builder().uri_str("/fail").header(REQUEST_ID_HEADER, "abc-123").finish())
+        .await;
+    assert_eq!(resp.headers()[REQUEST_ID_HEADER], "abc-123");
+    let body: serde_json::Value = resp.into_body().into_json().await.unwrap();
+    assert_eq!(body["request_id"], "abc-123");
+
+    // ids that can't be logged safely are replaced
+    let resp = app
+        .get_response(Request::builder().uri_str("/missing").header(REQUEST_ID_HEADER, "a b").finish())
+        .await;
+    let generated = resp.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(generated).is_ok());
+}
+
+#[test]
+fn should_redact_sensitive_fields() {
+    let payload = json!({
+        "email": "jane@example.com",
+        "first_name": "Jane",
+        "new_password": "hunter22",
+        "sessions": [{ "access_token": "abc", "device": "phone" }],
+    });
+
+    assert_eq!(
+        redact_json(&payload),
+        json!({
+            "email": "[REDACTED]",
+            "first_name": "Jane",
+            "new_password": "[REDACTED]",
+            "sessions": [{ "access_token": "[REDACTED]", "device": "phone" }],
+        })
+    );
+}
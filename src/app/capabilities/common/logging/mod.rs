@@ -0,0 +1,5 @@
+pub mod redact;
+pub mod request_id;
+
+#[cfg(test)]
+mod logging_test;
@@ -0,0 +1,49 @@
+use std::fmt;
+
+use poem_openapi::types::ToJSON;
+use serde_json::Value;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Keys that are always hidden, compared case-insensitively
+const SENSITIVE_KEYS: &[&str] = &["authorization", "cookie", "email", "jwt"];
+
+/// Key fragments that hide any key containing them, e.g. `new_password` or `access_token`
+const SENSITIVE_FRAGMENTS: &[&str] = &["password", "secret", "token"];
+
+fn is_sensitive(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEYS.contains(&key.as_str()) || SENSITIVE_FRAGMENTS.iter().any(|f| key.contains(f))
+}
+
+/// Copy of `value` with the values of sensitive keys replaced, at any depth
+pub fn redact_json(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if is_sensitive(key) {
+                        Value::String(String::from(REDACTED))
+                    } else {
+                        redact_json(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_json).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Displays an API payload as redacted JSON, for use in log fields
+pub struct Redacted<'a, T>(pub &'a T);
+
+impl<T: ToJSON> fmt::Display for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.to_json() {
+            Some(value) => write!(f, "{}", redact_json(&value)),
+            None => f.write_str("null"),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+
+use poem::{
+    http::{HeaderName, HeaderValue},
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest incoming id that is kept, anything longer is replaced
+const MAX_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: CurrentRequest;
+}
+
+#[derive(Clone)]
+struct CurrentRequest {
+    id: RequestId,
+    path: String,
+}
+
+/// Id of the request being handled, taken from `X-Request-Id` or generated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Keep the caller's id when it is safe to log and echo, generate one otherwise
+    pub fn from_header(value: Option<&HeaderValue>) -> RequestId {
+        match value.and_then(|value| value.to_str().ok()) {
+            Some(id) if is_valid(id) => RequestId(id.to_string()),
+            _ => RequestId(uuid::Uuid::new_v4().to_string()),
+        }
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_LENGTH && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Id of the request handled by the current task, if any
+pub fn current_request_id() -> Option<String> {
+    CURRENT.try_with(|current| current.id.0.clone()).ok()
+}
+
+/// Path of the request handled by the current task, if any
+pub fn current_request_path() -> Option<String> {
+    CURRENT.try_with(|current| current.path.clone()).ok()
+}
+
+/// Assigns every request an id, available as request data and through [`current_request_id`],
+/// and echoes it in the `X-Request-Id` response header
+pub struct RequestIdMiddleware;
+
+impl<E: Endpoint> Middleware<E> for RequestIdMiddleware {
+    type Output = RequestIdEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RequestIdEndpoint { inner: ep }
+    }
+}
+
+pub struct RequestIdEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for RequestIdEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let id = RequestId::from_header(req.headers().get(REQUEST_ID_HEADER));
+        req.extensions_mut().insert(id.clone());
+
+        let current = CurrentRequest {
+            id: id.clone(),
+            path: req.uri().path().to_string(),
+        };
+        let result = CURRENT.scope(current, self.inner.call(req)).await;
+        // errors are turned into responses here so they carry the header too
+        let mut resp = match result {
+            Ok(resp) => resp.into_response(),
+            Err(e) => e.into_response(),
+        };
+        if let Ok(value) = HeaderValue::from_str(&id.0) {
+            resp.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        Ok(resp)
+    }
+}
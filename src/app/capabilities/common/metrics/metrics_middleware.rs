@@ -0,0 +1,49 @@
+use std::time::Instant;
+
+use poem::{Endpoint, Middleware, Request, Response, Result};
+use poem_openapi::OperationId;
+
+use super::metrics_service::MetricsService;
+
+/// Label of requests not served by an OpenAPI operation, such as swagger or unknown paths
+const NO_OPERATION: &str = "none";
+
+/// Records count and latency of every request, labelled by OpenAPI operation id and status
+pub struct MetricsMiddleware {
+    metrics: MetricsService,
+}
+
+impl MetricsMiddleware {
+    pub fn new(metrics: MetricsService) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for MetricsMiddleware {
+    type Output = MetricsEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        MetricsEndpoint {
+            inner: ep,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+pub struct MetricsEndpoint<E> {
+    inner: E,
+    metrics: MetricsService,
+}
+
+impl<E: Endpoint> Endpoint for MetricsEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let started = Instant::now();
+        let resp = self.inner.get_response(req).await;
+        let operation = resp.data::<OperationId>().map(|id| id.0).unwrap_or(NO_OPERATION);
+        self.metrics
+            .observe_request(operation, resp.status().as_u16(), started.elapsed().as_secs_f64());
+        Ok(resp)
+    }
+}
@@ -0,0 +1,124 @@
+use prometheus::{
+    core::Collector, Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+use crate::app::capabilities::common::database::db_router::PoolStats;
+
+/// Prometheus registry of the app, rendered by `/metrics`.
+///
+/// Capabilities create their metrics through the `*_vec` helpers, or `register` for
+/// anything else, while the app state is built and keep the handles they get back.
+#[derive(Clone)]
+pub struct MetricsService {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+}
+
+impl MetricsService {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+        let http_requests = counter_vec(
+            &registry,
+            "http_requests_total",
+            "HTTP requests by OpenAPI operation id and status",
+            &["operation", "status"],
+        );
+        let http_request_duration = histogram_vec(
+            &registry,
+            "http_request_duration_seconds",
+            "HTTP request latency by OpenAPI operation id and status",
+            &["operation", "status"],
+        );
+        let db_pool_connections = gauge_vec(
+            &registry,
+            "db_pool_connections",
+            "Connections of each database pool by state",
+            &["pool", "state"],
+        );
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_pool_connections,
+        }
+    }
+
+    /// Register a collector built outside the helpers, panics on duplicate metric names
+    pub fn register<C: Collector + Clone + 'static>(&self, collector: C) -> C {
+        self.registry
+            .register(Box::new(collector.clone()))
+            .expect("metric registered twice");
+        collector
+    }
+
+    pub fn counter_vec(&self, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+        counter_vec(&self.registry, name, help, labels)
+    }
+
+    pub fn histogram_vec(&self, name: &str, help: &str, labels: &[&str]) -> HistogramVec {
+        histogram_vec(&self.registry, name, help, labels)
+    }
+
+    pub fn gauge_vec(&self, name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+        gauge_vec(&self.registry, name, help, labels)
+    }
+
+    /// Count a finished HTTP request
+    pub fn observe_request(&self, operation: &str, status: u16, seconds: f64) {
+        let status = status.to_string();
+        let labels = [operation, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration.with_label_values(&labels).observe(seconds);
+    }
+
+    /// Update the pool gauges, called on every scrape
+    pub fn observe_pools(&self, pools: &[PoolStats]) {
+        for pool in pools {
+            for (state, value) in [
+                ("idle", pool.idle),
+                ("in_use", pool.in_use),
+                ("max", pool.max_connections),
+            ] {
+                self.db_pool_connections
+                    .with_label_values(&[pool.name.as_str(), state])
+                    .set(value as i64);
+            }
+        }
+    }
+
+    /// Every metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = vec![];
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("{}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+impl Default for MetricsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn counter_vec(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    let counter = IntCounterVec::new(Opts::new(name, help), labels).expect("invalid counter");
+    registry.register(Box::new(counter.clone())).expect("metric registered twice");
+    counter
+}
+
+fn histogram_vec(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> HistogramVec {
+    let histogram = HistogramVec::new(HistogramOpts::new(name, help), labels).expect("invalid histogram");
+    registry.register(Box::new(histogram.clone())).expect("metric registered twice");
+    histogram
+}
+
+fn gauge_vec(registry: &Registry, name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+    let gauge = IntGaugeVec::new(Opts::new(name, help), labels).expect("invalid gauge");
+    registry.register(Box::new(gauge.clone())).expect("metric registered twice");
+    gauge
+}
@@ -0,0 +1,53 @@
+use poem::{http::StatusCode, Endpoint, EndpointExt, Request};
+use poem_openapi::{payload::PlainText, OpenApi, OpenApiService};
+
+use crate::app::capabilities::common::database::db_router::PoolStats;
+
+use super::{metrics_middleware::MetricsMiddleware, metrics_service::MetricsService};
+
+struct Api;
+
+#[OpenApi]
+impl Api {
+    #[oai(path = "/hello", method = "get", operation_id = "hello")]
+    async fn hello(&self) -> PlainText<&'static str> {
+        PlainText("hello")
+    }
+}
+
+#[tokio::test]
+async fn should_label_requests_by_operation_and_status() {
+    let metrics = MetricsService::new();
+    let app = OpenApiService::new(Api, "test", "1.0").with(MetricsMiddleware::new(metrics.clone()));
+
+    for uri in ["/hello", "/hello", "/missing"] {
+        app.call(Request::builder().uri_str(uri).finish()).await.ok();
+    }
+
+    let output = metrics.render();
+    assert!(output.contains("http_requests_total{operation=\"hello\",status=\"200\"} 2"));
+    assert!(output.contains(&format!(
+        "http_requests_total{{operation=\"none\",status=\"{}\"}} 1",
+        StatusCode::NOT_FOUND.as_u16()
+    )));
+    assert!(output.contains("http_request_duration_seconds_count{operation=\"hello\",status=\"200\"} 2"));
+}
+
+#[test]
+fn should_expose_pool_gauges_and_custom_metrics() {
+    let metrics = MetricsService::new();
+    metrics.observe_pools(&[PoolStats {
+        name: String::from("primary"),
+        size: 4,
+        idle: 3,
+        in_use: 1,
+        max_connections: 10,
+    }]);
+    let jobs = metrics.counter_vec("jobs_total", "Jobs run", &["queue"]);
+    jobs.with_label_values(&["default"]).inc();
+
+    let output = metrics.render();
+    assert!(output.contains("db_pool_connections{pool=\"primary\",state=\"in_use\"} 1"));
+    assert!(output.contains("db_pool_connections{pool=\"primary\",state=\"max\"} 10"));
+    assert!(output.contains("jobs_total{queue=\"default\"} 1"));
+}
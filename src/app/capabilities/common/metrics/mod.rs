@@ -0,0 +1,5 @@
+pub mod metrics_middleware;
+pub mod metrics_service;
+
+#[cfg(test)]
+mod metrics_service_test;
@@ -1,2 +1,17 @@
 pub mod config;
+pub mod database;
+pub mod email;
+pub mod events;
 pub mod global_model;
+pub mod health;
+pub mod http;
+pub mod i18n;
+pub mod jobs;
+pub mod lifecycle;
+pub mod logging;
+pub mod metrics;
+pub mod problem;
+pub mod rate_limit;
+pub mod scheduler;
+pub mod telemetry;
+pub mod tls;
@@ -0,0 +1,5 @@
+pub mod problem_details;
+pub mod problem_middleware;
+
+#[cfg(test)]
+mod problem_test;
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use poem::http::StatusCode;
+use poem_openapi::Object;
+use sea_orm::DbErr;
+
+use crate::app::capabilities::common::{
+    global_model::validation_error::ValidationError,
+    i18n::locale_middleware::translate,
+    logging::request_id::{current_request_id, current_request_path},
+};
+
+/// Content type of every error body
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Error body following RFC 7807, served as `application/problem+json`
+#[derive(Object, Clone, Debug, PartialEq, Eq)]
+pub struct ProblemDetails {
+    /// URI reference identifying the kind of problem, `/problems/<code>`
+    #[oai(rename = "type")]
+    pub problem_type: String,
+    /// Short summary of the kind of problem
+    pub title: String,
+    pub status: u16,
+    /// Explanation specific to this occurrence
+    #[oai(skip_serializing_if_is_none)]
+    pub detail: Option<String>,
+    /// Path of the request that failed
+    #[oai(skip_serializing_if_is_none)]
+    pub instance: Option<String>,
+    /// Machine readable code, stable across releases
+    pub code: String,
+    /// Failed rules of `validation_failed` and `password_policy` problems, omitted when empty
+    #[oai(skip_serializing_if_is_empty, default)]
+    pub errors: Vec<ValidationError>,
+    /// Same as the `X-Request-Id` response header, to quote when reporting the error
+    #[oai(skip_serializing_if_is_none)]
+    pub request_id: Option<String>,
+}
+
+impl ProblemDetails {
+    /// Problem for `status` with a code derived from its reason, e.g. `not_found`, and a title
+    /// in the request locale
+    pub fn new(status: StatusCode) -> ProblemDetails {
+        let reason = status.canonical_reason().unwrap_or("Error");
+        let code = reason.to_lowercase().replace([' ', '-'], "_");
+        let title = translate(&format!("status-{}", status.as_u16()), &HashMap::new())
+            .unwrap_or_else(|| reason.to_string());
+        ProblemDetails {
+            problem_type: format!("/problems/{}", code),
+            title,
+            status: status.as_u16(),
+            detail: None,
+            instance: current_request_path(),
+            code,
+            errors: vec![],
+            request_id: current_request_id(),
+        }
+    }
+
+    /// 500 without any detail, the cause only goes to the logs
+    pub fn internal() -> ProblemDetails {
+        ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Set the code, and the detail when the catalogue has an `error-<code>` message
+    pub fn with_code(mut self, code: &str) -> ProblemDetails {
+        self.code = code.to_string();
+        self.problem_type = format!("/problems/{}", code);
+        if let Some(detail) = translate(&format!("error-{}", code), &HashMap::new()) {
+            self.detail = Some(detail);
+        }
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> ProblemDetails {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Attach per-field validation failures, with messages from the `validation-<rule>` messages
+    pub fn with_errors(mut self, errors: Vec<ValidationError>) -> ProblemDetails {
+        self.errors = errors
+            .into_iter()
+            .map(|mut error| {
+                if let Some(message) = translate(&format!("validation-{}", error.rule), &error.args) {
+                    error.message = message;
+                }
+                error
+            })
+            .collect();
+        self
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl From<DbErr> for ProblemDetails {
+    fn from(e: DbErr) -> Self {
+        match e {
+            DbErr::RecordNotFound(_) => ProblemDetails::new(StatusCode::NOT_FOUND),
+            e => {
+                tracing::error!("Database error: {}", e);
+                ProblemDetails::internal()
+            }
+        }
+    }
+}
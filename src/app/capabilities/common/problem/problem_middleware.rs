@@ -0,0 +1,48 @@
+use poem::{http::header, Endpoint, IntoResponse, Middleware, Request, Response, Result};
+use poem_openapi::types::ToJSON;
+
+use super::problem_details::{ProblemDetails, PROBLEM_JSON};
+
+/// Turns errors raised outside of handlers, such as unknown routes, failed authorization or
+/// unparsable payloads, into `application/problem+json` responses
+pub struct ProblemMiddleware;
+
+impl<E: Endpoint> Middleware<E> for ProblemMiddleware {
+    type Output = ProblemEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        ProblemEndpoint { inner: ep }
+    }
+}
+
+pub struct ProblemEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for ProblemEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        match self.inner.call(req).await {
+            Ok(resp) => Ok(resp.into_response()),
+            // the body was already built by an `ApiResponse`
+            Err(e) if e.is_from_response() => Ok(e.into_response()),
+            Err(e) => Ok(problem_response(e)),
+        }
+    }
+}
+
+fn problem_response(e: poem::Error) -> Response {
+    let mut problem = ProblemDetails::new(e.status());
+    if e.status().is_server_error() {
+        tracing::error!("Request failed: {}", e);
+    } else {
+        problem = problem.with_detail(e.to_string());
+    }
+    // keeps the extensions of the error, such as the operation id used by metrics
+    let mut resp = e.into_response();
+    resp.headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static(PROBLEM_JSON));
+    resp.set_body(problem.to_json_string());
+    resp
+}
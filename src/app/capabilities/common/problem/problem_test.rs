@@ -0,0 +1,83 @@
+use poem::{http::{Method, StatusCode}, Endpoint, EndpointExt, Request};
+use poem_openapi::{payload::Json, ApiResponse, OpenApi, OpenApiService};
+use serde_json::Value;
+
+use crate::app::capabilities::{
+    common::{
+        global_model::validation_error::ValidationError,
+        logging::request_id::{RequestIdMiddleware, REQUEST_ID_HEADER},
+    },
+    iam::enums::auth_error::AuthError,
+};
+
+use super::{
+    problem_details::{ProblemDetails, PROBLEM_JSON},
+    problem_middleware::ProblemMiddleware,
+};
+
+#[derive(ApiResponse)]
+enum SignUpResponse {
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+struct Api;
+
+#[OpenApi]
+impl Api {
+    #[oai(path = "/sign-up", method = "post")]
+    async fn sign_up(&self) -> SignUpResponse {
+        let errors = vec![ValidationError::new("password", "min_length", String::from("Too short"))];
+        let problem = ProblemDetails::from(AuthError::PasswordPolicy(errors));
+        SignUpResponse::Problem(problem.status_code(), Json(problem))
+    }
+
+    #[oai(path = "/sign-in", method = "post")]
+    async fn sign_in(&self) -> SignUpResponse {
+        let problem = ProblemDetails::from(AuthError::JWTSignError);
+        SignUpResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+async fn call(uri: &str) -> (StatusCode, String, Value) {
+    let app = OpenApiService::new(Api, "test", "1.0")
+        .with(ProblemMiddleware)
+        .with(RequestIdMiddleware);
+    let req = Request::builder()
+        .method(Method::POST)
+        .uri_str(uri)
+        .header(REQUEST_ID_HEADER, "req-1")
+        .finish();
+    let resp = app.get_response(req).await;
+    let content_type = resp.content_type().unwrap_or_default().to_string();
+    (resp.status(), content_type, resp.into_body().into_json().await.unwrap())
+}
+
+#[tokio::test]
+async fn should_map_auth_errors_to_problems() {
+    let (status, content_type, body) = call("/sign-up").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(content_type, PROBLEM_JSON);
+    assert_eq!(body["type"], "/problems/password_policy");
+    assert_eq!(body["code"], "password_policy");
+    assert_eq!(body["title"], "Bad Request");
+    assert_eq!(body["instance"], "/sign-up");
+    assert_eq!(body["request_id"], "req-1");
+    assert_eq!(body["errors"][0]["field"], "password");
+
+    // internal errors don't leak the variant
+    let (status, _, body) = call("/sign-in").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["code"], "internal_server_error");
+    assert!(body.get("detail").is_none());
+}
+
+#[tokio::test]
+async fn should_turn_errors_outside_handlers_into_problems() {
+    let (status, content_type, body) = call("/missing").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(content_type, PROBLEM_JSON);
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["status"], 404);
+    assert_eq!(body["request_id"], "req-1");
+}
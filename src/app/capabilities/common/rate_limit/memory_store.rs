@@ -0,0 +1,91 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::rate_limit_store::{refill_and_take, Bucket, RateLimitStore, TakeFuture};
+
+/// Time between two scans for full buckets, a full bucket is the same as a missing one
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A bucket with the rule it was taken under, so it is pruned by its own refill rate
+struct MemoryBucket {
+    tokens: f64,
+    updated: Instant,
+    capacity: f64,
+    refill_per_sec: f64,
+}
+
+impl MemoryBucket {
+    fn is_full(&self, now: Instant) -> bool {
+        self.tokens + now.duration_since(self.updated).as_secs_f64() * self.refill_per_sec >= self.capacity
+    }
+}
+
+struct Buckets {
+    buckets: HashMap<String, MemoryBucket>,
+    pruned: Instant,
+}
+
+/// Buckets of this instance only
+#[derive(Clone)]
+pub struct MemoryRateLimitStore {
+    buckets: Arc<Mutex<Buckets>>,
+    prune_interval: Duration,
+}
+
+impl Default for MemoryRateLimitStore {
+    fn default() -> Self {
+        Self::with_prune_interval(PRUNE_INTERVAL)
+    }
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store dropping full buckets at most once per `prune_interval`
+    pub fn with_prune_interval(prune_interval: Duration) -> Self {
+        Self {
+            buckets: Arc::new(Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                pruned: Instant::now(),
+            })),
+            prune_interval,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.buckets.lock().unwrap().buckets.len()
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn take(&self, key: &str, capacity: f64, refill_per_sec: f64) -> TakeFuture<'_> {
+        let now = Instant::now();
+        let mut state = self.buckets.lock().unwrap();
+        if now.duration_since(state.pruned) >= self.prune_interval {
+            state.buckets.retain(|_, bucket| !bucket.is_full(now));
+            state.pruned = now;
+        }
+        let bucket = match state.buckets.get(key) {
+            Some(bucket) => {
+                refill_and_take(bucket.tokens, now.duration_since(bucket.updated).as_secs_f64(), capacity, refill_per_sec)
+            }
+            None => refill_and_take(capacity, 0.0, capacity, refill_per_sec),
+        };
+        state.buckets.insert(
+            key.to_string(),
+            MemoryBucket {
+                tokens: bucket.tokens,
+                updated: now,
+                capacity,
+                refill_per_sec,
+            },
+        );
+        Box::pin(async move { Ok::<Bucket, String>(bucket) })
+    }
+}
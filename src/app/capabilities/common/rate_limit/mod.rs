@@ -0,0 +1,9 @@
+pub mod memory_store;
+pub mod postgres_store;
+pub mod rate_limit_middleware;
+pub mod rate_limit_service;
+pub mod rate_limit_store;
+pub mod route_table;
+
+#[cfg(test)]
+mod rate_limit_test;
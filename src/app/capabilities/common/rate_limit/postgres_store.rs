@@ -0,0 +1,74 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use migration::sea_orm;
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+use crate::app::capabilities::common::database::db_router::DbRouter;
+
+use super::rate_limit_store::{Bucket, RateLimitStore, TakeFuture};
+
+/// Calls between two deletions of idle buckets
+const PURGE_EVERY: u64 = 1_000;
+
+/// Refill and take in one statement, the row lock serializes concurrent requests.
+/// `$2` is the capacity and `$3` the refill rate per second.
+const TAKE: &str = r#"
+INSERT INTO rate_limits AS r (key, tokens, allowed, updated_at)
+VALUES ($1, $2 - 1, true, now())
+ON CONFLICT (key) DO UPDATE SET
+    allowed = LEAST($2, r.tokens + EXTRACT(EPOCH FROM now() - r.updated_at) * $3) >= 1,
+    tokens = LEAST($2, r.tokens + EXTRACT(EPOCH FROM now() - r.updated_at) * $3)
+        - CASE WHEN LEAST($2, r.tokens + EXTRACT(EPOCH FROM now() - r.updated_at) * $3) >= 1 THEN 1 ELSE 0 END,
+    updated_at = now()
+RETURNING tokens::float8 AS tokens, allowed
+"#;
+
+/// Buckets idle this long are full again, rule periods are at most `POSTGRES_MAX_PERIOD_SECS`
+const PURGE: &str = "DELETE FROM rate_limits WHERE updated_at < now() - interval '1 day'";
+
+/// Buckets in the `rate_limits` table, shared by every instance
+#[derive(Clone)]
+pub struct PostgresRateLimitStore {
+    db: DbRouter,
+    calls: Arc<AtomicU64>,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(db: DbRouter) -> Self {
+        Self {
+            db,
+            calls: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl RateLimitStore for PostgresRateLimitStore {
+    fn take(&self, key: &str, capacity: f64, refill_per_sec: f64) -> TakeFuture<'_> {
+        let key = key.to_string();
+        Box::pin(async move {
+            let db = self.db.writer();
+            if self.calls.fetch_add(1, Ordering::Relaxed) % PURGE_EVERY == PURGE_EVERY - 1 {
+                if let Err(e) = db.execute(Statement::from_string(DbBackend::Postgres, PURGE)).await {
+                    tracing::warn!("Failed to purge rate limits: {}", e);
+                }
+            }
+            let statement = Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                TAKE,
+                [key.into(), capacity.into(), refill_per_sec.into()],
+            );
+            let row = db
+                .query_one(statement)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| String::from("no row returned"))?;
+            Ok(Bucket {
+                allowed: row.try_get("", "allowed").map_err(|e| e.to_string())?,
+                tokens: row.try_get("", "tokens").map_err(|e| e.to_string())?,
+            })
+        })
+    }
+}
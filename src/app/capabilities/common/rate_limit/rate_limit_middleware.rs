@@ -0,0 +1,87 @@
+use poem::{
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+};
+use poem_openapi::{types::ToJSON, OperationId};
+
+use crate::app::capabilities::common::problem::problem_details::{ProblemDetails, PROBLEM_JSON};
+
+use super::rate_limit_service::{Decision, RateLimitService};
+
+const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+const RATE_LIMIT_POLICY: HeaderName = HeaderName::from_static("ratelimit-policy");
+
+/// Rejects requests over their rate limit with 429 and reports the limit of the most
+/// restrictive rule in `RateLimit-*` headers
+pub struct RateLimitMiddleware {
+    service: RateLimitService,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(service: RateLimitService) -> Self {
+        Self { service }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RateLimitMiddleware {
+    type Output = RateLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RateLimitEndpoint {
+            inner: ep,
+            service: self.service.clone(),
+        }
+    }
+}
+
+pub struct RateLimitEndpoint<E> {
+    inner: E,
+    service: RateLimitService,
+}
+
+impl<E: Endpoint> Endpoint for RateLimitEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let Some(decision) = self.service.check(&mut req).await else {
+            return self.inner.call(req).await.map(IntoResponse::into_response);
+        };
+        let mut resp = if decision.allowed {
+            self.inner.call(req).await?.into_response()
+        } else {
+            rejection(&decision)
+        };
+        set_headers(resp.headers_mut(), &decision);
+        Ok(resp)
+    }
+}
+
+fn rejection(decision: &Decision) -> Response {
+    let problem = ProblemDetails::new(StatusCode::TOO_MANY_REQUESTS).with_code("rate_limited");
+    let mut resp = Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::CONTENT_TYPE, PROBLEM_JSON)
+        .header(header::RETRY_AFTER, decision.retry_after_secs)
+        .body(problem.to_json_string());
+    // the handler never runs, so metrics would not know the operation otherwise
+    if let Some(operation_id) = decision.operation_id {
+        resp.extensions_mut().insert(OperationId(operation_id));
+    }
+    resp
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    let policy = format!("{};w={}", decision.limit, decision.period_secs);
+    for (name, value) in [
+        (RATE_LIMIT_LIMIT, decision.limit.to_string()),
+        (RATE_LIMIT_REMAINING, decision.remaining.to_string()),
+        (RATE_LIMIT_RESET, decision.reset_secs.to_string()),
+        (RATE_LIMIT_POLICY, policy),
+    ] {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+}
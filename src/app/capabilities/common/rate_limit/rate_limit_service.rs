@@ -0,0 +1,203 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use poem::{http::header::HeaderName, Request};
+use prometheus::IntCounterVec;
+use sha2::{Digest, Sha256};
+
+use crate::app::capabilities::common::{
+    config::app_config::{RateLimitConfig, RateLimitKey, RateLimitRule},
+    metrics::metrics_service::MetricsService,
+};
+
+use super::{
+    rate_limit_store::RateLimitStore,
+    route_table::{Route, RouteTable},
+};
+
+pub const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+const FORWARDED_FOR_HEADER: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+pub type IdentityFuture<'a> = Pin<Box<dyn Future<Output = Option<String>> + Send + 'a>>;
+
+/// Resolves the user behind a request, for rules keyed by user
+pub trait UserIdentity: Send + Sync {
+    /// Stable id of the authenticated user, `None` for anonymous requests. Implementations may
+    /// leave what they verified in the request extensions for the handler.
+    fn user_id<'a>(&'a self, req: &'a mut Request) -> IdentityFuture<'a>;
+}
+
+/// Outcome of the most restrictive rule matching a request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    pub rule: String,
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again
+    pub reset_secs: u64,
+    /// Seconds until the next request is allowed, 0 when allowed
+    pub retry_after_secs: u64,
+    pub period_secs: u64,
+    /// Operation of the request, when the route table knows it
+    pub operation_id: Option<&'static str>,
+}
+
+/// Applies the configured rate limit rules to requests
+#[derive(Clone)]
+pub struct RateLimitService {
+    rules: Arc<Vec<RateLimitRule>>,
+    routes: Arc<RouteTable>,
+    store: Arc<dyn RateLimitStore>,
+    identity: Option<Arc<dyn UserIdentity>>,
+    trust_forwarded_for: bool,
+    rejections: IntCounterVec,
+}
+
+impl RateLimitService {
+    /// Service without any rule when rate limiting is disabled
+    pub fn new(config: &RateLimitConfig, store: Arc<dyn RateLimitStore>, metrics: &MetricsService) -> Self {
+        let rules = if config.enabled { config.rules.clone() } else { vec![] };
+        Self {
+            rules: Arc::new(rules),
+            routes: Arc::new(RouteTable::default()),
+            store,
+            identity: None,
+            trust_forwarded_for: config.trust_forwarded_for,
+            rejections: metrics.counter_vec(
+                "rate_limit_rejections_total",
+                "Requests rejected by rate limiting, by rule",
+                &["rule"],
+            ),
+        }
+    }
+
+    /// Resolve users for rules keyed by user, they fall back to the client IP without it
+    pub fn with_identity(mut self, identity: impl UserIdentity + 'static) -> Self {
+        self.identity = Some(Arc::new(identity));
+        self
+    }
+
+    /// Routes used to match rules by operation id and tag
+    pub fn with_routes(mut self, routes: RouteTable) -> Self {
+        for rule in self.rules.iter() {
+            let selects_operations = !rule.operations.is_empty() || !rule.tags.is_empty();
+            if selects_operations && !routes.routes().iter().any(|route| selects_operation(rule, route)) {
+                tracing::warn!("Rate limit rule {} matches no operation", rule.name);
+            }
+        }
+        self.routes = Arc::new(routes);
+        self
+    }
+
+    /// Take a token from the bucket of every rule matching `req`, `None` when no rule applies.
+    ///
+    /// Store failures are logged and let the request through.
+    pub async fn check(&self, req: &mut Request) -> Option<Decision> {
+        if self.rules.is_empty() {
+            return None;
+        }
+        // owned, the user lookup below may leave its outcome in the request
+        let (method, path) = (req.method().clone(), req.uri().path().to_string());
+        let route = self.routes.find(&method, &path);
+        let mut result: Option<Decision> = None;
+        for rule in self.rules.iter().filter(|rule| applies(rule, &path, route)) {
+            let key = format!("{}:{}", rule.name, self.client_key(rule.key, req).await);
+            let capacity = rule.requests as f64;
+            let refill_per_sec = capacity / rule.period_secs as f64;
+            let bucket = match self.store.take(&key, capacity, refill_per_sec).await {
+                Ok(bucket) => bucket,
+                Err(e) => {
+                    tracing::warn!("Rate limit store failed, allowing request: {}", e);
+                    continue;
+                }
+            };
+            let decision = Decision {
+                rule: rule.name.clone(),
+                allowed: bucket.allowed,
+                limit: rule.requests,
+                remaining: bucket.tokens.floor() as u32,
+                reset_secs: ((capacity - bucket.tokens) / refill_per_sec).ceil() as u64,
+                retry_after_secs: if bucket.allowed {
+                    0
+                } else {
+                    ((1.0 - bucket.tokens) / refill_per_sec).ceil() as u64
+                },
+                period_secs: rule.period_secs,
+                operation_id: route.and_then(|route| route.operation_id),
+            };
+            result = match result {
+                Some(current) if more_restrictive(&current, &decision) => Some(current),
+                _ => Some(decision),
+            };
+        }
+        if let Some(decision) = result.as_ref().filter(|decision| !decision.allowed) {
+            self.rejections.with_label_values(&[decision.rule.as_str()]).inc();
+        }
+        result
+    }
+
+    async fn client_key(&self, key: RateLimitKey, req: &mut Request) -> String {
+        match key {
+            RateLimitKey::Ip => format!("ip:{}", self.client_ip(req)),
+            RateLimitKey::User => {
+                let user = match &self.identity {
+                    Some(identity) => identity.user_id(req).await,
+                    None => None,
+                };
+                match user {
+                    Some(user) => format!("user:{}", user),
+                    None => format!("ip:{}", self.client_ip(req)),
+                }
+            }
+            RateLimitKey::ApiKey => match req.headers().get(API_KEY_HEADER) {
+                // only a digest is stored, the key itself is a secret
+                Some(api_key) => format!("key:{:x}", Sha256::digest(api_key.as_bytes())),
+                None => format!("ip:{}", self.client_ip(req)),
+            },
+        }
+    }
+
+    fn client_ip(&self, req: &Request) -> String {
+        if self.trust_forwarded_for {
+            let forwarded = req
+                .headers()
+                .get(FORWARDED_FOR_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.split(',').next())
+                .map(str::trim)
+                .filter(|value| !value.is_empty());
+            if let Some(ip) = forwarded {
+                return ip.to_string();
+            }
+        }
+        match req.remote_addr().as_socket_addr() {
+            Some(addr) => addr.ip().to_string(),
+            None => String::from("unknown"),
+        }
+    }
+}
+
+fn selects_operation(rule: &RateLimitRule, route: &Route) -> bool {
+    route.operation_id.is_some_and(|id| rule.operations.iter().any(|operation| operation == id))
+        || route.tags.iter().any(|tag| rule.tags.iter().any(|rule_tag| rule_tag == tag))
+}
+
+fn applies(rule: &RateLimitRule, path: &str, route: Option<&Route>) -> bool {
+    if rule.paths.is_empty() && rule.operations.is_empty() && rule.tags.is_empty() {
+        return true;
+    }
+    let path_matches = rule.paths.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => path.starts_with(prefix),
+        None => path == pattern,
+    });
+    path_matches || route.is_some_and(|route| selects_operation(rule, route))
+}
+
+/// Denials first, then the fewest remaining requests
+fn more_restrictive(current: &Decision, other: &Decision) -> bool {
+    match (current.allowed, other.allowed) {
+        (false, true) => true,
+        (true, false) => false,
+        _ => current.remaining <= other.remaining,
+    }
+}
@@ -0,0 +1,37 @@
+use std::{future::Future, pin::Pin};
+
+pub type TakeFuture<'a> = Pin<Box<dyn Future<Output = Result<Bucket, String>> + Send + 'a>>;
+
+/// State of a token bucket after taking a token from it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    /// Whether a token was available, the request is rejected otherwise
+    pub allowed: bool,
+    /// Tokens left, fractional while refilling
+    pub tokens: f64,
+}
+
+/// Storage of token buckets, shared by every rule.
+///
+/// Implementations refill and take atomically, so concurrent requests can't overdraw a bucket.
+pub trait RateLimitStore: Send + Sync {
+    /// Refill bucket `key` by `refill_per_sec` since its last use, up to `capacity`, then take
+    /// one token when there is one. Unknown buckets start full.
+    fn take(&self, key: &str, capacity: f64, refill_per_sec: f64) -> TakeFuture<'_>;
+}
+
+/// Token bucket arithmetic shared by the stores
+pub fn refill_and_take(tokens: f64, elapsed_secs: f64, capacity: f64, refill_per_sec: f64) -> Bucket {
+    let tokens = (tokens + elapsed_secs * refill_per_sec).min(capacity);
+    if tokens >= 1.0 {
+        Bucket {
+            allowed: true,
+            tokens: tokens - 1.0,
+        }
+    } else {
+        Bucket {
+            allowed: false,
+            tokens,
+        }
+    }
+}
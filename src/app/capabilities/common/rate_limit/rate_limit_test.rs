@@ -0,0 +1,147 @@
+use std::{sync::Arc, time::Duration};
+
+use poem::{
+    http::{header, Method, StatusCode},
+    Endpoint, EndpointExt, Request, Route,
+};
+use poem_openapi::{payload::PlainText, OpenApi, OpenApiService};
+
+use crate::app::capabilities::common::{
+    config::app_config::{RateLimitConfig, RateLimitKey, RateLimitRule},
+    metrics::metrics_service::MetricsService,
+    problem::problem_details::PROBLEM_JSON,
+};
+
+use super::{
+    memory_store::MemoryRateLimitStore,
+    rate_limit_middleware::RateLimitMiddleware,
+    rate_limit_service::{RateLimitService, API_KEY_HEADER},
+    rate_limit_store::{refill_and_take, RateLimitStore},
+    route_table::RouteTable,
+};
+
+struct Api;
+
+#[OpenApi]
+impl Api {
+    #[oai(path = "/login", method = "post", operation_id = "login")]
+    async fn login(&self) -> PlainText<&'static str> {
+        PlainText("ok")
+    }
+
+    #[oai(path = "/users/:id", method = "get", operation_id = "getUser")]
+    async fn get_user(&self) -> PlainText<&'static str> {
+        PlainText("ok")
+    }
+
+    #[oai(path = "/users/me", method = "get", operation_id = "getCurrentUser")]
+    async fn get_current_user(&self) -> PlainText<&'static str> {
+        PlainText("ok")
+    }
+}
+
+fn rule(name: &str, key: RateLimitKey, requests: u32) -> RateLimitRule {
+    RateLimitRule {
+        name: name.to_string(),
+        paths: vec![],
+        operations: vec![],
+        tags: vec![],
+        key,
+        requests,
+        period_secs: 60,
+    }
+}
+
+fn service(rules: Vec<RateLimitRule>, metrics: &MetricsService) -> RateLimitService {
+    let config = RateLimitConfig {
+        rules,
+        ..RateLimitConfig::default()
+    };
+    let mut routes = RouteTable::default();
+    routes.add("/api", Api::meta());
+    RateLimitService::new(&config, Arc::new(MemoryRateLimitStore::new()), metrics).with_routes(routes)
+}
+
+#[tokio::test]
+async fn should_reject_requests_over_the_limit_of_an_operation() {
+    let metrics = MetricsService::new();
+    let login = RateLimitRule {
+        operations: vec![String::from("login")],
+        ..rule("auth", RateLimitKey::Ip, 2)
+    };
+    let app = Route::new()
+        .nest("/api", OpenApiService::new(Api, "test", "1.0"))
+        .with(RateLimitMiddleware::new(service(vec![login], &metrics)));
+    let login = || Request::builder().method(Method::POST).uri_str("/api/login").finish();
+
+    let resp = app.get_response(login()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["ratelimit-limit"], "2");
+    assert_eq!(resp.headers()["ratelimit-remaining"], "1");
+    assert_eq!(resp.headers()["ratelimit-policy"], "2;w=60");
+    app.get_response(login()).await;
+
+    let resp = app.get_response(login()).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(resp.headers()["ratelimit-remaining"], "0");
+    assert_eq!(resp.headers()[header::RETRY_AFTER], "30");
+    assert_eq!(resp.content_type(), Some(PROBLEM_JSON));
+    let body: serde_json::Value = resp.into_body().into_json().await.unwrap();
+    assert_eq!(body["code"], "rate_limited");
+    assert!(metrics.render().contains("rate_limit_rejections_total{rule=\"auth\"} 1"));
+
+    // other operations are not limited by the rule
+    let resp = app.get_response(Request::builder().uri_str("/api/users/me").finish()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("ratelimit-limit").is_none());
+}
+
+#[tokio::test]
+async fn should_keep_separate_buckets_per_api_key() {
+    let metrics = MetricsService::new();
+    let service = service(vec![rule("api", RateLimitKey::ApiKey, 1)], &metrics);
+    let request = |api_key: &str| Request::builder().uri_str("/api/users/me").header(API_KEY_HEADER, api_key).finish();
+
+    assert!(service.check(&mut request("first")).await.unwrap().allowed);
+    assert!(service.check(&mut request("second")).await.unwrap().allowed);
+    let decision = service.check(&mut request("first")).await.unwrap();
+    assert!(!decision.allowed);
+    assert_eq!(decision.operation_id, Some("getCurrentUser"));
+}
+
+#[test]
+fn should_match_literal_segments_before_parameters() {
+    let mut routes = RouteTable::default();
+    routes.add("/api", Api::meta());
+
+    let find = |path: &str| routes.find(&Method::GET, path).and_then(|route| route.operation_id);
+    assert_eq!(find("/api/users/me"), Some("getCurrentUser"));
+    assert_eq!(find("/api/users/42"), Some("getUser"));
+    assert_eq!(find("/api/users/42/posts"), None);
+    assert_eq!(routes.find(&Method::GET, "/api/login").map(|route| route.operation_id), None);
+}
+
+#[test]
+fn should_refill_tokens_over_time() {
+    let empty = refill_and_take(0.0, 0.5, 10.0, 1.0);
+    assert!(!empty.allowed);
+    assert_eq!(empty.tokens, 0.5);
+
+    let refilled = refill_and_take(0.5, 1.0, 10.0, 1.0);
+    assert!(refilled.allowed);
+    assert_eq!(refilled.tokens, 0.5);
+
+    // never above capacity
+    assert_eq!(refill_and_take(9.0, 100.0, 10.0, 1.0).tokens, 9.0);
+}
+
+#[tokio::test]
+async fn should_prune_buckets_by_their_own_rule() {
+    let store = MemoryRateLimitStore::with_prune_interval(Duration::ZERO);
+    assert!(store.take("auth:127.0.0.1", 1.0, 1.0 / 60.0).await.unwrap().allowed);
+
+    // a request under a looser rule prunes, the throttled bucket is still there
+    assert!(store.take("default:127.0.0.1", 100.0, 100.0).await.unwrap().allowed);
+    assert!(!store.take("auth:127.0.0.1", 1.0, 1.0 / 60.0).await.unwrap().allowed);
+    assert_eq!(store.len(), 2);
+}
@@ -0,0 +1,67 @@
+use poem::http::Method;
+use poem_openapi::registry::MetaApi;
+
+/// An OpenAPI operation as served, with its path parameters as wildcards
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub method: Method,
+    segments: Vec<Option<String>>,
+    pub operation_id: Option<&'static str>,
+    pub tags: Vec<&'static str>,
+}
+
+impl Route {
+    fn matches(&self, method: &Method, segments: &[&str]) -> bool {
+        self.method == method
+            && self.segments.len() == segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(segments)
+                .all(|(expected, actual)| expected.as_deref().map_or(true, |expected| expected == *actual))
+    }
+}
+
+/// Maps requests to the OpenAPI operation that serves them, before routing happens
+#[derive(Debug, Clone, Default)]
+pub struct RouteTable {
+    routes: Vec<Route>,
+}
+
+impl RouteTable {
+    /// Add the operations of an API nested under `prefix`, e.g. `OpenApi::meta()` of the APIs under `/api`
+    pub fn add(&mut self, prefix: &str, apis: Vec<MetaApi>) {
+        for path in apis.into_iter().flat_map(|api| api.paths) {
+            let full_path = format!("{}{}", prefix.trim_end_matches('/'), path.path);
+            let segments: Vec<Option<String>> = split(&full_path)
+                .into_iter()
+                .map(|segment| (!segment.starts_with('{')).then(|| segment.to_string()))
+                .collect();
+            for operation in path.operations {
+                self.routes.push(Route {
+                    method: operation.method,
+                    segments: segments.clone(),
+                    operation_id: operation.operation_id,
+                    tags: operation.tags,
+                });
+            }
+        }
+    }
+
+    /// Operation serving `method` and `path`, literal segments win over parameters
+    pub fn find(&self, method: &Method, path: &str) -> Option<&Route> {
+        let segments = split(path);
+        self.routes
+            .iter()
+            .filter(|route| route.matches(method, &segments))
+            .max_by_key(|route| route.segments.iter().filter(|segment| segment.is_some()).count())
+    }
+
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+}
+
+fn split(path: &str) -> Vec<&str> {
+    path.split('/').filter(|segment| !segment.is_empty()).collect()
+}
@@ -0,0 +1,5 @@
+pub mod scheduled_task;
+pub mod scheduler_service;
+
+#[cfg(test)]
+mod scheduler_test;
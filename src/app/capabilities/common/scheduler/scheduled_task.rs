@@ -0,0 +1,63 @@
+use std::{future::Future, pin::Pin};
+
+use migration::sea_orm;
+use poem_openapi::Object;
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+use crate::app::capabilities::common::database::db_router::DbRouter;
+
+pub type TaskFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Work run by the scheduler on each tick of its cron expression.
+///
+/// Only one replica runs a given tick, and a tick is skipped while the previous run of the
+/// task is still going on any replica.
+pub trait ScheduledTask: Send + Sync {
+    /// Error shown in the run history
+    fn run(&self) -> TaskFuture<'_>;
+}
+
+/// Runs one SQL statement, for purges and retention
+pub struct SqlTask {
+    db: DbRouter,
+    sql: String,
+}
+
+impl SqlTask {
+    pub fn new(db: DbRouter, sql: impl Into<String>) -> Self {
+        Self { db, sql: sql.into() }
+    }
+}
+
+impl ScheduledTask for SqlTask {
+    fn run(&self) -> TaskFuture<'_> {
+        Box::pin(async move {
+            let statement = Statement::from_string(DbBackend::Postgres, self.sql.clone());
+            let result = self.db.writer().execute(statement).await.map_err(|e| e.to_string())?;
+            tracing::info!(rows = result.rows_affected(), "Statement executed");
+            Ok(())
+        })
+    }
+}
+
+/// One run from the `scheduled_runs` history, times are RFC 3339
+#[derive(Debug, Object, Clone, PartialEq, Eq)]
+pub struct TaskRun {
+    pub scheduled_at: String,
+    /// `running`, `succeeded` or `failed`
+    pub status: String,
+    /// Process that ran the task
+    pub instance: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Object, Clone, PartialEq, Eq)]
+pub struct TaskStatus {
+    pub name: String,
+    pub schedule: String,
+    pub next_run_at: Option<String>,
+    /// Latest runs first
+    pub runs: Vec<TaskRun>,
+}
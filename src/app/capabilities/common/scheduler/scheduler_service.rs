@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeZone, Utc};
+use cron::Schedule;
+use migration::sea_orm;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, QueryResult, Statement, TransactionTrait};
+use sha2::{Digest, Sha256};
+use tokio::{sync::watch, task::JoinHandle};
+
+use crate::app::capabilities::common::{
+    config::app_config::{parse_cron, SchedulerConfig},
+    database::db_router::DbRouter,
+};
+
+use super::scheduled_task::{ScheduledTask, TaskRun, TaskStatus};
+
+/// Runs kept per task in the status report
+const STATUS_RUNS: i64 = 10;
+
+/// Held until the transaction ends, so a crashed run releases it with its connection
+const TRY_LOCK: &str = "SELECT pg_try_advisory_xact_lock($1) AS locked";
+
+/// The unique index on (task, scheduled_at) lets one replica claim a tick
+const START_RUN: &str = r#"
+INSERT INTO scheduled_runs (task, scheduled_at, status, instance)
+VALUES ($1, to_timestamp($2), 'running', $3)
+ON CONFLICT (task, scheduled_at) DO NOTHING
+RETURNING id
+"#;
+
+const FINISH_RUN: &str = r#"
+UPDATE scheduled_runs SET status = $2, error = $3, finished_at = now() WHERE id = $1
+"#;
+
+const RUNS: &str = r#"
+SELECT EXTRACT(EPOCH FROM scheduled_at)::float8 AS scheduled_at, status, instance,
+    EXTRACT(EPOCH FROM started_at)::float8 AS started_at,
+    EXTRACT(EPOCH FROM finished_at)::float8 AS finished_at, error
+FROM scheduled_runs WHERE task = $1 ORDER BY scheduled_at DESC LIMIT $2
+"#;
+
+struct Entry {
+    name: String,
+    expression: String,
+    schedule: Schedule,
+    task: Arc<dyn ScheduledTask>,
+}
+
+/// Cron scheduler whose tasks run on one replica per tick, with the history in `scheduled_runs`
+#[derive(Clone)]
+pub struct SchedulerService {
+    db: DbRouter,
+    config: SchedulerConfig,
+    entries: Arc<Vec<Arc<Entry>>>,
+    instance: String,
+}
+
+/// Stops the task loops of a started scheduler
+pub struct SchedulerHandle {
+    stop: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl SchedulerHandle {
+    /// Stop scheduling and wait for running tasks to finish
+    pub async fn stop(self) {
+        let _ = self.stop.send(true);
+        for task in self.tasks {
+            if let Err(e) = task.await {
+                tracing::error!("Scheduled task loop failed: {}", e);
+            }
+        }
+    }
+}
+
+impl SchedulerService {
+    pub fn new(db: DbRouter, config: &SchedulerConfig) -> Self {
+        Self {
+            db,
+            config: config.clone(),
+            entries: Arc::new(vec![]),
+            instance: format!("{}-{}", std::process::id(), uuid::Uuid::new_v4().simple()),
+        }
+    }
+
+    /// Add a task running on `expression`, unless `scheduler.schedules` overrides it.
+    /// Panics on an invalid default expression, overrides are validated with the config.
+    pub fn with_task(mut self, name: &str, expression: &str, task: impl ScheduledTask + 'static) -> Self {
+        let expression = self.config.schedules.get(name).map_or(expression, String::as_str);
+        let schedule = parse_cron(expression).unwrap_or_else(|e| panic!("task {}: {}", name, e));
+        Arc::make_mut(&mut self.entries).push(Arc::new(Entry {
+            name: name.to_string(),
+            expression: expression.to_string(),
+            schedule,
+            task: Arc::new(task),
+        }));
+        self
+    }
+
+    /// Spawn one loop per task
+    pub fn start(&self) -> SchedulerHandle {
+        let (stop, stopped) = watch::channel(false);
+        let tasks = self
+            .entries
+            .iter()
+            .map(|entry| tokio::spawn(self.clone().run_task(entry.clone(), stopped.clone())))
+            .collect();
+        SchedulerHandle { stop, tasks }
+    }
+
+    async fn run_task(self, entry: Arc<Entry>, mut stopped: watch::Receiver<bool>) {
+        while !*stopped.borrow() {
+            let Some(tick) = entry.schedule.upcoming(Utc).next() else {
+                break;
+            };
+            let wait = (tick - Utc::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {},
+                _ = stopped.changed() => break,
+            }
+            self.run_entry(&entry, tick).await;
+        }
+    }
+
+    /// Run task `name` for `tick` now, unless its previous run still holds the lock or another
+    /// instance already ran the tick. Returns whether it ran, `None` for an unknown task.
+    pub async fn run_tick(&self, name: &str, tick: DateTime<Utc>) -> Option<bool> {
+        let entry = self.entries.iter().find(|entry| entry.name == name)?;
+        Some(self.run_entry(entry, tick).await)
+    }
+
+    async fn run_entry(&self, entry: &Entry, tick: DateTime<Utc>) -> bool {
+        let span = tracing::info_span!("scheduled_task", task = %entry.name, tick = %tick.to_rfc3339());
+        match self.try_run(entry, tick, &span).await {
+            Ok(ran) => ran,
+            Err(e) => {
+                span.in_scope(|| tracing::error!("Failed to run scheduled task: {}", e));
+                false
+            }
+        }
+    }
+
+    async fn try_run(&self, entry: &Entry, tick: DateTime<Utc>, span: &tracing::Span) -> Result<bool, DbErr> {
+        let lock = self.db.writer().begin().await?;
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, TRY_LOCK, [lock_key(&entry.name).into()]);
+        let locked = match lock.query_one(statement).await? {
+            Some(row) => row.try_get::<bool>("", "locked")?,
+            None => false,
+        };
+        if !locked {
+            span.in_scope(|| tracing::debug!("Previous run still going, skipping tick"));
+            lock.rollback().await?;
+            return Ok(false);
+        }
+
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            START_RUN,
+            [
+                entry.name.clone().into(),
+                (tick.timestamp_millis() as f64 / 1000.0).into(),
+                self.instance.clone().into(),
+            ],
+        );
+        let Some(run) = self.db.writer().query_one(statement).await? else {
+            span.in_scope(|| tracing::debug!("Tick already ran on another instance"));
+            lock.rollback().await?;
+            return Ok(false);
+        };
+        let id: i64 = run.try_get("", "id")?;
+
+        span.in_scope(|| tracing::info!("Scheduled task started"));
+        let result = entry.task.run().await;
+        let (status, error) = match result {
+            Ok(_) => ("succeeded", None),
+            Err(e) => {
+                span.in_scope(|| tracing::error!(error = %e, "Scheduled task failed"));
+                ("failed", Some(e))
+            }
+        };
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            FINISH_RUN,
+            [id.into(), status.into(), error.into()],
+        );
+        self.db.writer().execute(statement).await?;
+        lock.commit().await?;
+        Ok(true)
+    }
+
+    /// Every task with its next tick and latest runs
+    pub async fn status(&self) -> Result<Vec<TaskStatus>, DbErr> {
+        let mut statuses = vec![];
+        for entry in self.entries.iter() {
+            let statement =
+                Statement::from_sql_and_values(DbBackend::Postgres, RUNS, [entry.name.clone().into(), STATUS_RUNS.into()]);
+            let rows = self.db.reader().query_all(statement).await?;
+            statuses.push(TaskStatus {
+                name: entry.name.clone(),
+                schedule: entry.expression.clone(),
+                next_run_at: entry.schedule.upcoming(Utc).next().map(|tick| tick.to_rfc3339()),
+                runs: rows.iter().map(task_run).collect::<Result<_, _>>()?,
+            });
+        }
+        Ok(statuses)
+    }
+}
+
+fn task_run(row: &QueryResult) -> Result<TaskRun, DbErr> {
+    let time = |column: &str| -> Result<Option<String>, DbErr> {
+        let epoch: Option<f64> = row.try_get("", column)?;
+        Ok(epoch.map(timestamp))
+    };
+    Ok(TaskRun {
+        scheduled_at: time("scheduled_at")?.unwrap_or_default(),
+        status: row.try_get("", "status")?,
+        instance: row.try_get("", "instance")?,
+        started_at: time("started_at")?.unwrap_or_default(),
+        finished_at: time("finished_at")?,
+        error: row.try_get("", "error")?,
+    })
+}
+
+fn timestamp(epoch: f64) -> String {
+    Utc.timestamp_millis_opt((epoch * 1000.0) as i64)
+        .single()
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Advisory lock id of a task, the first 8 bytes of a SHA-256 of its name
+pub fn lock_key(task: &str) -> i64 {
+    let digest = Sha256::digest(format!("scheduler:{}", task).as_bytes());
+    i64::from_be_bytes(digest[..8].try_into().expect("digest is 32 bytes"))
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use chrono::{TimeZone, Timelike, Utc};
+use migration::sea_orm::{ConnectionTrait, DbBackend, Statement, TransactionTrait};
+
+use crate::app::capabilities::common::{
+    config::app_config::{parse_cron, SchedulerConfig},
+    database::test_postgres::test_postgres,
+};
+
+use super::{
+    scheduled_task::{ScheduledTask, TaskFuture},
+    scheduler_service::{lock_key, SchedulerService},
+};
+
+/// Counts its runs, fails when `fail` is set
+#[derive(Clone, Default)]
+struct CountingTask {
+    runs: Arc<AtomicUsize>,
+    fail: bool,
+}
+
+impl ScheduledTask for CountingTask {
+    fn run(&self) -> TaskFuture<'_> {
+        Box::pin(async move {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(String::from("disk full"));
+            }
+            Ok(())
+        })
+    }
+}
+
+#[test]
+fn should_accept_cron_expressions_with_and_without_seconds() {
+    let after = Utc.with_ymd_and_hms(2026, 10, 19, 12, 7, 30).unwrap();
+
+    let every_quarter = parse_cron("*/15 * * * *").unwrap();
+    let next = every_quarter.after(&after).next().unwrap();
+    assert_eq!((next.hour(), next.minute(), next.second()), (12, 15, 0));
+
+    let with_seconds = parse_cron("30 45 3 * * *").unwrap();
+    let next = with_seconds.after(&after).next().unwrap();
+    assert_eq!((next.hour(), next.minute(), next.second()), (3, 45, 30));
+
+    assert!(parse_cron("every minute").is_err());
+}
+
+#[test]
+fn should_reject_invalid_schedule_overrides() {
+    let config = SchedulerConfig {
+        schedules: HashMap::from([(String::from("purge_expired_sessions"), String::from("61 * * * *"))]),
+        ..SchedulerConfig::default()
+    };
+    let errors = config.validate();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("schedules.purge_expired_sessions: invalid cron expression"));
+}
+
+#[test]
+fn should_derive_a_stable_lock_per_task() {
+    assert_eq!(lock_key("purge_expired_sessions"), lock_key("purge_expired_sessions"));
+    assert_ne!(lock_key("purge_expired_sessions"), lock_key("purge_rate_limits"));
+}
+
+#[tokio::test]
+async fn should_run_each_tick_on_one_instance_only() {
+    let Some(db) = test_postgres().await else { return };
+    let task = CountingTask::default();
+    let first = SchedulerService::new(db.clone(), &SchedulerConfig::default()).with_task("count", "* * * * *", task.clone());
+    let second = SchedulerService::new(db, &SchedulerConfig::default()).with_task("count", "* * * * *", task.clone());
+    let tick = Utc.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+
+    assert_eq!(first.run_tick("count", tick).await, Some(true));
+    assert_eq!(second.run_tick("count", tick).await, Some(false));
+    assert_eq!(first.run_tick("count", tick).await, Some(false));
+    assert_eq!(task.runs.load(Ordering::SeqCst), 1);
+    assert_eq!(first.run_tick("unknown", tick).await, None);
+
+    let status = second.status().await.unwrap();
+    assert_eq!(status[0].runs.len(), 1);
+    assert_eq!(status[0].runs[0].status, "succeeded");
+    assert_eq!(status[0].runs[0].scheduled_at, tick.to_rfc3339());
+    assert!(status[0].runs[0].finished_at.is_some());
+}
+
+#[tokio::test]
+async fn should_skip_ticks_while_the_previous_run_holds_the_lock() {
+    let Some(db) = test_postgres().await else { return };
+    let task = CountingTask::default();
+    let scheduler = SchedulerService::new(db.clone(), &SchedulerConfig::default()).with_task("count", "* * * * *", task.clone());
+    let tick = Utc.with_ymd_and_hms(2026, 10, 19, 12, 1, 0).unwrap();
+
+    // a run of the task on another instance
+    let running = db.writer().begin().await.unwrap();
+    let lock = Statement::from_sql_and_values(DbBackend::Postgres, "SELECT pg_advisory_xact_lock($1)", [lock_key("count").into()]);
+    running.execute(lock).await.unwrap();
+    assert_eq!(scheduler.run_tick("count", tick).await, Some(false));
+    assert_eq!(task.runs.load(Ordering::SeqCst), 0);
+    assert!(scheduler.status().await.unwrap()[0].runs.is_empty());
+
+    running.commit().await.unwrap();
+    assert_eq!(scheduler.run_tick("count", tick).await, Some(true));
+    assert_eq!(task.runs.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn should_record_failed_runs_with_their_error() {
+    let Some(db) = test_postgres().await else { return };
+    let task = CountingTask {
+        fail: true,
+        ..CountingTask::default()
+    };
+    let scheduler = SchedulerService::new(db, &SchedulerConfig::default()).with_task("count", "* * * * *", task);
+    let tick = Utc.with_ymd_and_hms(2026, 10, 19, 12, 2, 0).unwrap();
+
+    assert_eq!(scheduler.run_tick("count", tick).await, Some(true));
+    let run = &scheduler.status().await.unwrap()[0].runs[0];
+    assert_eq!(run.status, "failed");
+    assert_eq!(run.error.as_deref(), Some("disk full"));
+}
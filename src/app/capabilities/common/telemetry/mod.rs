@@ -0,0 +1,7 @@
+pub mod query_spans;
+pub mod telemetry_service;
+pub mod trace_context;
+pub mod tracing_middleware;
+
+#[cfg(test)]
+mod tracing_middleware_test;
@@ -0,0 +1,42 @@
+use std::time::SystemTime;
+
+use migration::sea_orm::metric::Info;
+use opentelemetry::{
+    global,
+    trace::{Span, SpanKind, Status, Tracer},
+    KeyValue,
+};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Record a finished SeaORM query as a client span under the current span.
+///
+/// SeaORM only reports queries after they ran, so the span is built with explicit
+/// start and end times instead of going through `tracing`.
+pub fn record_query(pool: &str, info: &Info<'_>) {
+    let parent = tracing::Span::current().context();
+    let end = SystemTime::now();
+    let sql = &info.statement.sql;
+    let operation = sql
+        .split_whitespace()
+        .next()
+        .unwrap_or("QUERY")
+        .to_uppercase();
+
+    let tracer = global::tracer("sea-orm");
+    let mut span = tracer
+        .span_builder(operation.clone())
+        .with_kind(SpanKind::Client)
+        .with_start_time(end.checked_sub(info.elapsed).unwrap_or(end))
+        .with_attributes(vec![
+            KeyValue::new("db.system.name", "postgresql"),
+            KeyValue::new("db.operation.name", operation),
+            // parameters are bound separately, so no values end up in the span
+            KeyValue::new("db.query.text", sql.clone()),
+            KeyValue::new("db.pool", pool.to_string()),
+        ])
+        .start_with_context(&tracer, &parent);
+    if info.failed {
+        span.set_status(Status::error("query failed"));
+    }
+    span.end_with_timestamp(end);
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use opentelemetry::{global, trace::TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracerProvider},
+    Resource,
+};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::app::capabilities::common::config::app_config::{
+    LogFormat, LoggingConfig, OtlpProtocol, TelemetryConfig,
+};
+
+/// Owns the OpenTelemetry exporter, if any, so buffered spans can be flushed on shutdown
+#[derive(Clone)]
+pub struct TelemetryService {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl TelemetryService {
+    /// Install the global `tracing` subscriber: logs filtered by `RUST_LOG`, falling back to
+    /// `logging.filter`, and spans exported over OTLP when telemetry is enabled
+    pub fn init(logging: &LoggingConfig, config: &TelemetryConfig) -> Result<TelemetryService, String> {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let provider = if config.enabled {
+            Some(tracer_provider(config)?)
+        } else {
+            None
+        };
+        let otel_layer = provider
+            .as_ref()
+            .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("server")));
+
+        let filter = match EnvFilter::try_from_default_env() {
+            Ok(filter) => filter,
+            Err(_) => EnvFilter::try_new(&logging.filter).map_err(|e| e.to_string())?,
+        };
+        // span fields such as `request_id` end up on every line logged inside the span
+        let fmt_layer = match logging.format {
+            LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(false)
+                .with_span_list(true)
+                .boxed(),
+        };
+
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt_layer)
+            .with(otel_layer)
+            .try_init()
+            .map_err(|e| e.to_string())?;
+        Ok(TelemetryService { provider })
+    }
+
+    /// Export every buffered span, then stop the exporter
+    pub async fn shutdown(self) {
+        let Some(provider) = self.provider else {
+            return;
+        };
+        // the sdk blocks while flushing
+        let result = tokio::task::spawn_blocking(move || provider.shutdown()).await;
+        match result {
+            Ok(Err(e)) => tracing::error!("Failed to flush spans: {}", e),
+            Err(e) => tracing::error!("Failed to flush spans: {}", e),
+            Ok(Ok(_)) => (),
+        }
+    }
+}
+
+/// Provider exporting to the configured collector, sampling new traces by ratio and
+/// following the parent's decision otherwise
+pub fn tracer_provider(config: &TelemetryConfig) -> Result<SdkTracerProvider, String> {
+    let timeout = Duration::from_secs(config.export_timeout_secs);
+    let exporter = match config.protocol {
+        OtlpProtocol::Grpc => SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(config.otlp_endpoint.clone())
+            .with_timeout(timeout)
+            .build(),
+        OtlpProtocol::Http => SpanExporter::builder()
+            .with_http()
+            .with_endpoint(config.otlp_endpoint.clone())
+            .with_timeout(timeout)
+            .build(),
+    }
+    .map_err(|e| format!("OTLP exporter: {}", e))?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sampling_ratio,
+        ))))
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build())
+        .build();
+    // used by spans created through the OpenTelemetry API, such as query spans
+    global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
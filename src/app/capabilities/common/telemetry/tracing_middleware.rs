@@ -0,0 +1,61 @@
+use opentelemetry::trace::TraceContextExt;
+use poem::{Endpoint, Middleware, Request, Response, Result};
+use poem_openapi::OperationId;
+use tracing::{field::Empty, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::app::capabilities::common::logging::request_id::RequestId;
+
+use super::trace_context::extract_context;
+
+/// Wraps every request in a server span, continuing the caller's trace when it sent a `traceparent`
+pub struct TracingMiddleware;
+
+impl<E: Endpoint> Middleware<E> for TracingMiddleware {
+    type Output = TracingEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        TracingEndpoint { inner: ep }
+    }
+}
+
+pub struct TracingEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for TracingEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let method = req.method().to_string();
+        let request_id = req.data::<RequestId>().map(|id| id.0.clone()).unwrap_or_default();
+        let span = tracing::info_span!(
+            "request",
+            otel.name = %method,
+            otel.kind = "server",
+            otel.status_code = Empty,
+            http.request.method = %method,
+            url.path = %req.uri().path(),
+            request_id = %request_id,
+            operation = Empty,
+            http.response.status_code = Empty,
+        );
+        if let Err(e) = span.set_parent(extract_context(req.headers())) {
+            tracing::debug!("Ignoring trace parent: {}", e);
+        }
+
+        let resp = self.inner.get_response(req).instrument(span.clone()).await;
+
+        // name the span after the OpenAPI operation, the path may hold ids
+        if let Some(operation) = resp.data::<OperationId>() {
+            span.context().span().update_name(format!("{} {}", method, operation.0));
+            span.record("operation", operation.0);
+        }
+        span.record("http.response.status_code", resp.status().as_u16());
+        if resp.status().is_server_error() {
+            span.record("otel.status_code", "ERROR");
+        }
+        span.in_scope(|| tracing::info!(status = resp.status().as_u16(), "Request finished"));
+        Ok(resp)
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use migration::sea_orm::{metric::Info, DbBackend, Statement};
+use opentelemetry::{global, trace::TracerProvider};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{InMemorySpanExporter, SdkTracerProvider},
+};
+use poem::{http::HeaderMap, Endpoint, EndpointExt, Request};
+use poem_openapi::{payload::PlainText, OpenApi, OpenApiService};
+use tracing_subscriber::layer::SubscriberExt;
+
+use super::{query_spans::record_query, trace_context::inject_context, tracing_middleware::TracingMiddleware};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+struct Api;
+
+#[OpenApi]
+impl Api {
+    /// Runs a query and returns the `traceparent` an outgoing call would carry
+    #[oai(path = "/users/:id", method = "get", operation_id = "getUser")]
+    async fn get_user(&self) -> PlainText<String> {
+        let statement = Statement::from_string(DbBackend::Postgres, "select * from users where id = $1");
+        record_query(
+            "primary",
+            &Info {
+                elapsed: Duration::from_millis(3),
+                statement: &statement,
+                failed: false,
+            },
+        );
+        let mut headers = HeaderMap::new();
+        inject_context(&mut headers);
+        PlainText(headers["traceparent"].to_str().unwrap().to_string())
+    }
+}
+
+#[tokio::test]
+async fn should_continue_incoming_trace_and_propagate_it() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let app = OpenApiService::new(Api, "test", "1.0").with(TracingMiddleware);
+    let req = Request::builder()
+        .uri_str("/users/42")
+        .header("traceparent", format!("00-{}-{}-01", TRACE_ID, PARENT_SPAN_ID))
+        .finish();
+    let outgoing = app.get_response(req).await.into_body().into_string().await.unwrap();
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let server = spans.iter().find(|s| s.name == "GET getUser").unwrap();
+    let query = spans.iter().find(|s| s.name == "SELECT").unwrap();
+    assert_eq!(server.span_context.trace_id().to_string(), TRACE_ID);
+    assert_eq!(server.parent_span_id.to_string(), PARENT_SPAN_ID);
+    assert_eq!(query.span_context.trace_id().to_string(), TRACE_ID);
+    assert_eq!(query.parent_span_id, server.span_context.span_id());
+    assert_eq!(
+        outgoing,
+        format!("00-{}-{}-01", TRACE_ID, server.span_context.span_id())
+    );
+}
@@ -0,0 +1,103 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use poem::{Endpoint, Middleware, Request, Result};
+use sha2::{Digest, Sha256};
+
+/// Client certificate verified during the TLS handshake, added to the request extensions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// Lowercase hex SHA-256 of the leaf certificate in DER form
+    pub fingerprint: String,
+}
+
+impl ClientCertificate {
+    pub fn from_der(der: &[u8]) -> Self {
+        Self {
+            fingerprint: format!("{:x}", Sha256::digest(der)),
+        }
+    }
+}
+
+/// Client certificates of the open TLS connections, keyed by peer address.
+///
+/// poem hands endpoints nothing but the peer address of a connection, so the
+/// listener records certificates here and `ClientCertificateMiddleware` looks them up.
+#[derive(Clone, Default)]
+pub struct ClientCertificates {
+    connections: Arc<Mutex<HashMap<SocketAddr, ClientCertificate>>>,
+}
+
+impl ClientCertificates {
+    /// Record the certificate of a connection until the returned guard is dropped
+    pub fn register(&self, addr: SocketAddr, certificate: ClientCertificate) -> ClientCertificateGuard {
+        self.connections.lock().unwrap().insert(addr, certificate);
+        ClientCertificateGuard {
+            connections: self.clone(),
+            addr,
+        }
+    }
+
+    pub fn get(&self, addr: &SocketAddr) -> Option<ClientCertificate> {
+        self.connections.lock().unwrap().get(addr).cloned()
+    }
+}
+
+/// Removes a connection's certificate once the connection closes
+pub struct ClientCertificateGuard {
+    connections: ClientCertificates,
+    addr: SocketAddr,
+}
+
+impl Drop for ClientCertificateGuard {
+    fn drop(&mut self) {
+        self.connections.connections.lock().unwrap().remove(&self.addr);
+    }
+}
+
+/// Adds the `ClientCertificate` of the connection to each request
+pub struct ClientCertificateMiddleware {
+    certificates: ClientCertificates,
+}
+
+impl ClientCertificateMiddleware {
+    pub fn new(certificates: ClientCertificates) -> Self {
+        Self { certificates }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for ClientCertificateMiddleware {
+    type Output = ClientCertificateEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        ClientCertificateEndpoint {
+            inner: ep,
+            certificates: self.certificates.clone(),
+        }
+    }
+}
+
+pub struct ClientCertificateEndpoint<E> {
+    inner: E,
+    certificates: ClientCertificates,
+}
+
+impl<E: Endpoint> Endpoint for ClientCertificateEndpoint<E> {
+    type Output = E::Output;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        // never trust a certificate smuggled in by an earlier layer
+        req.extensions_mut().remove::<ClientCertificate>();
+        let certificate = req
+            .remote_addr()
+            .as_socket_addr()
+            .and_then(|addr| self.certificates.get(addr));
+        if let Some(certificate) = certificate {
+            req.extensions_mut().insert(certificate);
+        }
+        self.inner.call(req).await
+    }
+}
@@ -0,0 +1,86 @@
+use std::{io, time::Duration};
+
+use poem::{
+    http::{header, StatusCode},
+    listener::{Listener, TcpListener},
+    Endpoint, Request, Response, Result,
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+
+/// Location on the HTTPS listener for a request received over plain HTTP
+pub fn https_location(host: &str, https_port: u16, path_and_query: &str) -> String {
+    let host = match host.strip_prefix('[') {
+        // IPv6 literal, keep the brackets and drop the port
+        Some(rest) => &host[..rest.find(']').map(|i| i + 2).unwrap_or(host.len())],
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    match https_port {
+        443 => format!("https://{}{}", host, path_and_query),
+        port => format!("https://{}:{}{}", host, port, path_and_query),
+    }
+}
+
+/// Answers every request with a permanent redirect to the same URL over HTTPS
+pub struct HttpsRedirect {
+    https_port: u16,
+}
+
+impl HttpsRedirect {
+    pub fn new(https_port: u16) -> Self {
+        Self { https_port }
+    }
+}
+
+impl Endpoint for HttpsRedirect {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let host = req
+            .header(header::HOST)
+            .map(str::to_string)
+            .or_else(|| req.uri().host().map(str::to_string));
+        let Some(host) = host else {
+            return Ok(StatusCode::BAD_REQUEST.into());
+        };
+        let path_and_query = req.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+        Ok(Response::builder()
+            .status(StatusCode::PERMANENT_REDIRECT)
+            .header(header::LOCATION, https_location(&host, self.https_port, path_and_query))
+            .finish())
+    }
+}
+
+/// Plain HTTP listener running `HttpsRedirect` next to the main server
+pub struct RedirectServer {
+    stop: oneshot::Sender<()>,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+impl RedirectServer {
+    /// Bind `address` and serve redirects in the background, failing when the port can't be bound
+    pub async fn spawn(address: String, https_port: u16) -> io::Result<Self> {
+        let acceptor = TcpListener::bind(address).into_acceptor().await?;
+        let (stop, stopped) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            poem::Server::new_with_acceptor(acceptor)
+                .run_with_graceful_shutdown(
+                    HttpsRedirect::new(https_port),
+                    async {
+                        stopped.await.ok();
+                    },
+                    Some(Duration::from_secs(1)),
+                )
+                .await
+        });
+        Ok(Self { stop, handle })
+    }
+
+    pub async fn stop(self) {
+        self.stop.send(()).ok();
+        match self.handle.await {
+            Ok(Err(e)) => tracing::error!("HTTPS redirect server failed: {}", e),
+            Err(e) => tracing::error!("HTTPS redirect server panicked: {}", e),
+            Ok(Ok(_)) => (),
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod client_certificate;
+pub mod https_redirect;
+pub mod tls_listener;
+
+#[cfg(test)]
+mod tls_listener_test;
@@ -0,0 +1,261 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Error, ErrorKind},
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+
+use poem::{
+    http::uri::Scheme,
+    listener::{Acceptor, Listener, TcpAcceptor, TcpListener},
+    web::{LocalAddr, RemoteAddr},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpStream, ToSocketAddrs},
+    task::JoinSet,
+    time::{Interval, MissedTickBehavior},
+};
+use tokio_rustls::{
+    rustls::{server::WebPkiClientVerifier, RootCertStore, ServerConfig},
+    server::TlsStream,
+};
+
+use crate::app::capabilities::common::config::app_config::{ClientAuth, TlsConfig};
+
+use super::client_certificate::{ClientCertificate, ClientCertificateGuard, ClientCertificates};
+
+/// Build the rustls config described by `config`, advertising HTTP/2 and HTTP/1.1
+pub fn load_server_config(config: &TlsConfig) -> io::Result<ServerConfig> {
+    let certs = rustls_pemfile::certs(&mut open(&config.cert_path)?).collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut open(&config.key_path)?)?.ok_or_else(|| {
+        Error::new(ErrorKind::InvalidData, format!("no private key in {}", config.key_path))
+    })?;
+
+    let builder = ServerConfig::builder();
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in rustls_pemfile::certs(&mut open(ca_path)?) {
+                roots.add(cert?).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots));
+            let verifier = match config.client_auth {
+                ClientAuth::Optional => verifier.allow_unauthenticated(),
+                ClientAuth::Required => verifier,
+            };
+            builder.with_client_cert_verifier(verifier.build().map_err(|e| Error::new(ErrorKind::InvalidData, e))?)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+fn open(path: &str) -> io::Result<BufReader<File>> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| Error::new(e.kind(), format!("{}: {}", path, e)))
+}
+
+/// TLS listener that reloads its certificates when the files change and records
+/// verified client certificates in `ClientCertificates`
+pub struct TlsListener<T> {
+    inner: TcpListener<T>,
+    config: TlsConfig,
+    certificates: ClientCertificates,
+}
+
+impl<T> TlsListener<T> {
+    pub fn new(inner: TcpListener<T>, config: TlsConfig, certificates: ClientCertificates) -> Self {
+        Self {
+            inner,
+            config,
+            certificates,
+        }
+    }
+}
+
+impl<T: ToSocketAddrs + Send> Listener for TlsListener<T> {
+    type Acceptor = TlsAcceptor;
+
+    async fn into_acceptor(self) -> io::Result<Self::Acceptor> {
+        // a broken certificate should stop startup rather than every handshake
+        let server_config = load_server_config(&self.config)?;
+        let reload = match self.config.reload_interval_secs {
+            0 => None,
+            secs => {
+                let mut interval = tokio::time::interval(Duration::from_secs(secs));
+                interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                Some(interval)
+            }
+        };
+        let mut watched = vec![PathBuf::from(&self.config.cert_path), PathBuf::from(&self.config.key_path)];
+        watched.extend(self.config.client_ca_path.iter().map(PathBuf::from));
+
+        Ok(TlsAcceptor {
+            inner: self.inner.into_acceptor().await?,
+            acceptor: tokio_rustls::TlsAcceptor::from(Arc::new(server_config)),
+            modified: modified_times(&watched),
+            watched,
+            reload,
+            handshake_timeout: Duration::from_secs(self.config.handshake_timeout_secs),
+            handshakes: JoinSet::new(),
+            config: self.config,
+            certificates: self.certificates,
+        })
+    }
+}
+
+fn modified_times(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| file.metadata().and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+type Handshake = io::Result<(TlsConnection, LocalAddr, RemoteAddr)>;
+
+pub struct TlsAcceptor {
+    inner: TcpAcceptor,
+    config: TlsConfig,
+    acceptor: tokio_rustls::TlsAcceptor,
+    watched: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+    reload: Option<Interval>,
+    handshake_timeout: Duration,
+    handshakes: JoinSet<Handshake>,
+    certificates: ClientCertificates,
+}
+
+impl TlsAcceptor {
+    /// Swap in the certificates on disk if any of the files changed, keeping the
+    /// current ones when the new files don't load
+    fn reload_if_changed(&mut self) {
+        let modified = modified_times(&self.watched);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        match load_server_config(&self.config) {
+            Ok(server_config) => {
+                self.acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_config));
+                tracing::info!("TLS certificates reloaded");
+            }
+            Err(e) => tracing::error!("Keeping current TLS certificates, reload failed: {}", e),
+        }
+    }
+
+    fn start_handshake(&mut self, stream: TcpStream, local_addr: LocalAddr, remote_addr: RemoteAddr) {
+        let acceptor = self.acceptor.clone();
+        let timeout = self.handshake_timeout;
+        let certificates = self.certificates.clone();
+        self.handshakes.spawn(async move {
+            let stream = tokio::time::timeout(timeout, acceptor.accept(stream))
+                .await
+                .map_err(|_| Error::new(ErrorKind::TimedOut, "TLS handshake timed out"))??;
+            let certificate = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| ClientCertificate::from_der(cert));
+            let certificate = match (certificate, remote_addr.as_socket_addr()) {
+                (Some(certificate), Some(addr)) => Some(certificates.register(*addr, certificate)),
+                _ => None,
+            };
+            Ok((
+                TlsConnection {
+                    stream,
+                    _certificate: certificate,
+                },
+                local_addr,
+                remote_addr,
+            ))
+        });
+    }
+}
+
+async fn tick(reload: &mut Option<Interval>) {
+    match reload {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+impl Acceptor for TlsAcceptor {
+    type Io = TlsConnection;
+
+    fn local_addr(&self) -> Vec<LocalAddr> {
+        self.inner.local_addr()
+    }
+
+    async fn accept(&mut self) -> io::Result<(Self::Io, LocalAddr, RemoteAddr, Scheme)> {
+        // handshakes run in the background so a slow client can't hold up the accept loop
+        loop {
+            tokio::select! {
+                _ = tick(&mut self.reload) => self.reload_if_changed(),
+                Some(handshake) = self.handshakes.join_next(), if !self.handshakes.is_empty() => {
+                    match handshake {
+                        Ok(Ok((stream, local_addr, remote_addr))) => {
+                            return Ok((stream, local_addr, remote_addr, Scheme::HTTPS));
+                        }
+                        Ok(Err(e)) => tracing::debug!("TLS handshake failed: {}", e),
+                        Err(e) => tracing::error!("TLS handshake task failed: {}", e),
+                    }
+                }
+                accepted = self.inner.accept() => {
+                    let (stream, local_addr, remote_addr, _) = accepted?;
+                    self.start_handshake(stream, local_addr, remote_addr);
+                }
+            }
+        }
+    }
+}
+
+/// Established TLS connection, forgets its client certificate when dropped
+pub struct TlsConnection {
+    stream: TlsStream<TcpStream>,
+    _certificate: Option<ClientCertificateGuard>,
+}
+
+impl AsyncRead for TlsConnection {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TlsConnection {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,201 @@
+use std::{fs, path::Path, sync::Arc, time::Duration};
+
+use poem::{
+    handler,
+    listener::{Acceptor, Listener, TcpListener},
+    EndpointExt, Request, Route,
+};
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    client::TlsStream,
+    rustls::{
+        pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName},
+        ClientConfig, RootCertStore,
+    },
+    TlsConnector,
+};
+
+use crate::app::capabilities::common::config::app_config::{ClientAuth, TlsConfig};
+
+use super::{
+    client_certificate::{ClientCertificate, ClientCertificateMiddleware, ClientCertificates},
+    https_redirect::{https_location, RedirectServer},
+    tls_listener::TlsListener,
+};
+
+const HTTP1: &[u8] = b"http/1.1";
+
+struct Pki {
+    ca: Certificate,
+    client: Certificate,
+    client_der: Vec<u8>,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let mut params = CertificateParams::new(vec![]);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(params).unwrap();
+        let client = Certificate::from_params(CertificateParams::new(vec![String::from("client")])).unwrap();
+        let client_der = client.serialize_der_with_signer(&ca).unwrap();
+        Pki { ca, client, client_der }
+    }
+
+    /// Write a fresh server certificate for localhost and the CA bundle, returns the server certificate
+    fn write_server_files(&self, dir: &Path) -> Vec<u8> {
+        let server = Certificate::from_params(CertificateParams::new(vec![String::from("localhost")])).unwrap();
+        let server_pem = server.serialize_pem_with_signer(&self.ca).unwrap();
+        fs::write(dir.join("server.pem"), &server_pem).unwrap();
+        fs::write(dir.join("server.key"), server.serialize_private_key_pem()).unwrap();
+        fs::write(dir.join("ca.pem"), self.ca.serialize_pem().unwrap()).unwrap();
+        let server_der = rustls_pemfile::certs(&mut server_pem.as_bytes()).next().unwrap().unwrap();
+        server_der.to_vec()
+    }
+
+    async fn connect(&self, port: u16, with_client_cert: bool, protocol: &[u8]) -> std::io::Result<TlsStream<TcpStream>> {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from(self.ca.serialize_der().unwrap())).unwrap();
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+        let mut config = if with_client_cert {
+            builder
+                .with_client_auth_cert(
+                    vec![CertificateDer::from(self.client_der.clone())],
+                    PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(self.client.serialize_private_key_der())),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+        config.alpn_protocols = vec![protocol.to_vec()];
+        let tcp = TcpStream::connect(("127.0.0.1", port)).await?;
+        TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), tcp)
+            .await
+    }
+}
+
+fn tls_config(dir: &Path, client_auth: ClientAuth) -> TlsConfig {
+    TlsConfig {
+        enabled: true,
+        cert_path: dir.join("server.pem").display().to_string(),
+        key_path: dir.join("server.key").display().to_string(),
+        client_ca_path: Some(dir.join("ca.pem").display().to_string()),
+        client_auth,
+        reload_interval_secs: 1,
+        ..TlsConfig::default()
+    }
+}
+
+#[handler]
+fn fingerprint(req: &Request) -> String {
+    req.extensions()
+        .get::<ClientCertificate>()
+        .map(|c| c.fingerprint.clone())
+        .unwrap_or_default()
+}
+
+/// Serve `fingerprint` over TLS on a random port
+async fn serve(config: TlsConfig) -> u16 {
+    let certificates = ClientCertificates::default();
+    let app = Route::new()
+        .at("/", fingerprint)
+        .with(ClientCertificateMiddleware::new(certificates.clone()));
+    let acceptor = TlsListener::new(TcpListener::bind("127.0.0.1:0"), config, certificates)
+        .into_acceptor()
+        .await
+        .unwrap();
+    let port = acceptor.local_addr()[0].as_socket_addr().unwrap().port();
+    tokio::spawn(poem::Server::new_with_acceptor(acceptor).run(app));
+    port
+}
+
+async fn get(stream: &mut TlsStream<TcpStream>) -> String {
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+fn temp_dir() -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn should_pass_client_certificate_to_requests() {
+    let dir = temp_dir();
+    let pki = Pki::new();
+    pki.write_server_files(&dir);
+    let port = serve(tls_config(&dir, ClientAuth::Optional)).await;
+
+    fs::remove_dir_all(&dir).unwrap();
+
+    let h2 = pki.connect(port, true, b"h2").await.unwrap();
+    assert_eq!(h2.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+
+    let expected = ClientCertificate::from_der(&pki.client_der).fingerprint;
+    let mut stream = pki.connect(port, true, HTTP1).await.unwrap();
+    assert!(get(&mut stream).await.ends_with(&expected));
+
+    // optional client auth still serves clients without a certificate
+    let mut stream = pki.connect(port, false, HTTP1).await.unwrap();
+    assert!(get(&mut stream).await.starts_with("HTTP/1.1 200"));
+}
+
+#[tokio::test]
+async fn should_reject_missing_client_certificate_when_required() {
+    let dir = temp_dir();
+    let pki = Pki::new();
+    pki.write_server_files(&dir);
+    let port = serve(tls_config(&dir, ClientAuth::Required)).await;
+    fs::remove_dir_all(&dir).unwrap();
+
+    let mut stream = pki.connect(port, false, HTTP1).await.unwrap();
+    let mut buf = [0; 1];
+    assert!(stream.read(&mut buf).await.is_err());
+    let mut stream = pki.connect(port, true, HTTP1).await.unwrap();
+    assert!(get(&mut stream).await.starts_with("HTTP/1.1 200"));
+}
+
+#[tokio::test]
+async fn should_reload_changed_certificates() {
+    let dir = temp_dir();
+    let pki = Pki::new();
+    let first = pki.write_server_files(&dir);
+    let port = serve(tls_config(&dir, ClientAuth::Optional)).await;
+
+    let stream = pki.connect(port, false, HTTP1).await.unwrap();
+    assert_eq!(stream.get_ref().1.peer_certificates().unwrap()[0].as_ref(), &first[..]);
+
+    let second = pki.write_server_files(&dir);
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    let stream = pki.connect(port, false, HTTP1).await.unwrap();
+    assert_eq!(stream.get_ref().1.peer_certificates().unwrap()[0].as_ref(), &second[..]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn should_build_https_location() {
+    assert_eq!(https_location("example.com", 443, "/a?b=1"), "https://example.com/a?b=1");
+    assert_eq!(https_location("example.com:80", 8443, "/"), "https://example.com:8443/");
+    assert_eq!(https_location("[::1]:8080", 443, "/x"), "https://[::1]/x");
+}
+
+#[tokio::test]
+async fn should_fail_to_spawn_the_redirect_server_on_a_taken_port() {
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = taken.local_addr().unwrap().to_string();
+    let error = RedirectServer::spawn(address, 443).await.err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::AddrInUse);
+
+    drop(taken);
+    RedirectServer::spawn(String::from("127.0.0.1:0"), 443).await.unwrap().stop().await;
+}
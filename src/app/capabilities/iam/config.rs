@@ -0,0 +1,158 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::app::capabilities::common::config::{app_config::Profile, secret::Secret};
+
+use super::services::password::password_policy_service::PasswordPolicy;
+
+/// Shortest JWT secret accepted in the prod profile
+const MIN_PROD_JWT_SECRET_LENGTH: usize = 32;
+
+/// Config of the IAM capability
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IamConfig {
+    /// HS512 key used to sign session tokens
+    pub jwt_secret: Secret,
+    /// bcrypt work factor
+    pub bcrypt_cost: u32,
+    pub password_policy: PasswordPolicy,
+    /// Users signed in by a mutual TLS client certificate, SHA-256 fingerprint to email
+    pub client_certificates: HashMap<String, String>,
+    /// Emails of the users allowed on `/admin` routes
+    pub admins: Vec<String>,
+    pub session: SessionConfig,
+    pub session_cookie: SessionCookieConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionBackend {
+    /// Stateless signed tokens, valid until they expire
+    #[default]
+    Jwt,
+    Memory,
+    Postgres,
+}
+
+/// How login and register sessions are kept, the `AuthBearer` response is the same for all
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    pub backend: SessionBackend,
+    /// Store-backed sessions expire after this long without a request
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            backend: SessionBackend::default(),
+            idle_timeout_secs: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
+    #[default]
+    Strict,
+    Lax,
+}
+
+/// Sessions kept in an encrypted HttpOnly cookie for browser front-ends, see `POST /auth/session`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SessionCookieConfig {
+    pub enabled: bool,
+    /// Only send the cookies over HTTPS, required in prod
+    pub secure: bool,
+    pub same_site: SameSite,
+}
+
+impl Default for SessionCookieConfig {
+    fn default() -> Self {
+        SessionCookieConfig {
+            enabled: false,
+            secure: true,
+            same_site: SameSite::default(),
+        }
+    }
+}
+
+impl Default for IamConfig {
+    fn default() -> Self {
+        IamConfig {
+            jwt_secret: Secret::default(),
+            bcrypt_cost: bcrypt::DEFAULT_COST,
+            password_policy: PasswordPolicy::default(),
+            client_certificates: HashMap::new(),
+            admins: vec![],
+            session: SessionConfig::default(),
+            session_cookie: SessionCookieConfig::default(),
+        }
+    }
+}
+
+impl IamConfig {
+    pub fn validate(&self, profile: Profile) -> Vec<String> {
+        let mut errors = vec![];
+        if self.jwt_secret.is_empty() {
+            errors.push(String::from("jwt_secret: must be set (IAM_JWT_SECRET)"));
+        } else if profile == Profile::Prod && self.jwt_secret.expose().len() < MIN_PROD_JWT_SECRET_LENGTH {
+            errors.push(format!(
+                "jwt_secret: must be at least {} characters in prod",
+                MIN_PROD_JWT_SECRET_LENGTH
+            ));
+        }
+        if !(4..=31).contains(&self.bcrypt_cost) {
+            errors.push(format!(
+                "bcrypt_cost: must be between 4 and 31, got {}",
+                self.bcrypt_cost
+            ));
+        }
+
+        let policy = &self.password_policy;
+        if policy.min_length == 0 {
+            errors.push(String::from("password_policy.min_length: must be at least 1"));
+        }
+        if policy.min_length > policy.max_length {
+            errors.push(String::from(
+                "password_policy.min_length: must not exceed max_length",
+            ));
+        }
+        if policy.max_length > 72 {
+            errors.push(String::from(
+                "password_policy.max_length: bcrypt only uses the first 72 bytes",
+            ));
+        }
+        if policy.min_strength > 4 {
+            errors.push(String::from("password_policy.min_strength: must be between 0 and 4"));
+        }
+        if let Some(file) = &policy.breached_passwords_file {
+            if !Path::new(file).is_file() {
+                errors.push(format!(
+                    "password_policy.breached_passwords_file: {} does not exist",
+                    file
+                ));
+            }
+        }
+        for fingerprint in self.client_certificates.keys() {
+            if fingerprint.len() != 64 || !fingerprint.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)) {
+                errors.push(format!(
+                    "client_certificates: {} is not a lowercase hex SHA-256 fingerprint",
+                    fingerprint
+                ));
+            }
+        }
+        if self.session.idle_timeout_secs == 0 {
+            errors.push(String::from("session.idle_timeout_secs: must be greater than 0"));
+        }
+        if profile == Profile::Prod && self.session_cookie.enabled && !self.session_cookie.secure {
+            errors.push(String::from("session_cookie.secure: must be true in prod"));
+        }
+        errors
+    }
+}
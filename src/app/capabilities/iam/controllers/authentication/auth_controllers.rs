@@ -1,20 +1,31 @@
-use poem::web::Data;
-use poem_openapi::{payload::Json, types::Email, ApiResponse, Object, OpenApi, Tags};
+use poem::web::{cookie::CookieJar, Data};
+use poem::{http::StatusCode, Request};
+use poem_openapi::{auth::Bearer, payload::Json, types::Email, ApiResponse, Object, OpenApi, SecurityScheme, Tags};
 
 use crate::app::capabilities::{
-    common::global_model::{api_error::ApiError, app_state::AppState},
-    iam::{enums::auth_error::AuthError, models::auth_bearer::AuthBearer},
+    common::{
+        global_model::app_state::AppState,
+        i18n::locale_middleware::Locale,
+        logging::redact::Redacted,
+        problem::problem_details::ProblemDetails,
+    },
+    iam::{
+        enums::auth_error::AuthError,
+        models::{auth_bearer::AuthBearer, cookie_session::CookieSession},
+    },
 };
 
 #[derive(Debug, Object, Clone, Eq, PartialEq)]
 pub struct CreateUser {
-    #[oai(validator(min_length = 2))]
+    /// At least 2 characters
     first_name: String,
-    #[oai(validator(min_length = 2))]
+    /// At least 2 characters
     last_name: String,
-    #[oai(validator(min_length = 8))]
+    /// Checked against the configured password policy
     password: String,
     email: Email,
+    /// Preferred locale for emails, defaults to the one negotiated from `Accept-Language`
+    locale: Option<String>,
 }
 
 
@@ -29,24 +40,83 @@ pub struct Login {
 pub enum RegisterResponse {
     #[oai(status = 200)]
     Ok(Json<AuthBearer>),
-    #[oai(status = 404)]
-    NotFound,
-    #[oai(status = 409)]
-    Conflict,
-    #[oai(status = 500)]
-    InternalServerError(Json<ApiError>),
+    /// `email_taken`, or `validation_failed` / `password_policy` with the failed rules
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for RegisterResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        RegisterResponse::Problem(problem.status_code(), Json(problem))
+    }
 }
 
 #[derive(ApiResponse)]
 pub enum LoginResponse {
     #[oai(status = 200)]
     Ok(Json<AuthBearer>),
-    #[oai(status = 404)]
-    NotFound,
-    #[oai(status = 500)]
-    InternalServerError(Json<ApiError>),
+    /// `invalid_credentials` for an unknown email or a wrong password
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for LoginResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        LoginResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum CreateSessionResponse {
+    /// Sets the `session` and `csrf_token` cookies
+    #[oai(status = 200)]
+    Ok(Json<CookieSession>),
+    /// `invalid_credentials`, or `session_cookie_disabled` when cookie sessions are off
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for CreateSessionResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        CreateSessionResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum DeleteSessionResponse {
+    /// Expires the `session` and `csrf_token` cookies
+    #[oai(status = 204)]
+    NoContent,
+    /// `csrf_failed` when the `X-CSRF-Token` header doesn't match the `csrf_token` cookie
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for DeleteSessionResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        DeleteSessionResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum LogoutResponse {
+    #[oai(status = 204)]
+    NoContent,
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
 }
 
+impl From<ProblemDetails> for LogoutResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        LogoutResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+/// Bearer token of the session to end, not verified
+#[derive(SecurityScheme)]
+#[oai(ty = "bearer")]
+pub struct SessionToken(Bearer);
+
 #[derive(Tags)]
 enum ApiTags {
     /// Operations about user
@@ -55,30 +125,92 @@ enum ApiTags {
 }
 
 #[derive(Default)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct API;
 
 #[OpenApi]
 impl API {
-    #[oai(path = "/auth/login", method = "post", tag = "ApiTags::Login")]
+    #[oai(path = "/auth/login", method = "post", tag = "ApiTags::Login", operation_id = "login")]
     pub async fn login(&self, state: Data<&AppState>, payload: Json<Login>) -> LoginResponse {
+        tracing::debug!(payload = %Redacted(&payload.0), "Login");
         match state.services.iam.login(payload.email.clone(), payload.password.clone()).await {
-            Err(e) => match e {
-                AuthError::NotFound => return LoginResponse::NotFound,
-                _ => LoginResponse::InternalServerError(Json(ApiError::new(format!("{:?}", e)))),
-            },
+            Err(AuthError::NotFound) => ProblemDetails::from(AuthError::NotFound)
+                .with_code("invalid_credentials")
+                .into(),
+            Err(e) => ProblemDetails::from(e).into(),
             Ok(ab) => { 
                 // TODO: Trigger send email
                 return LoginResponse::Ok(Json(ab));
             },
         }
     }
+    /// Login for browser front-ends, the session is kept in an encrypted HttpOnly cookie
+    #[oai(path = "/auth/session", method = "post", tag = "ApiTags::Login", operation_id = "createSession")]
+    pub async fn create_session(
+        &self,
+        state: Data<&AppState>,
+        cookie_jar: &CookieJar,
+        payload: Json<Login>,
+    ) -> CreateSessionResponse {
+        tracing::debug!(payload = %Redacted(&payload.0), "Create session");
+        let iam = &state.services.iam;
+        if !iam.session_cookie().enabled() {
+            return ProblemDetails::new(StatusCode::NOT_FOUND)
+                .with_code("session_cookie_disabled")
+                .into();
+        }
+        match iam.login(payload.email.clone(), payload.password.clone()).await {
+            Err(AuthError::NotFound) => ProblemDetails::from(AuthError::NotFound)
+                .with_code("invalid_credentials")
+                .into(),
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(AuthBearer { token, session_user: Some(session_user) }) => {
+                let csrf_token = iam.session_cookie().start(cookie_jar, &token, iam.session_duration());
+                CreateSessionResponse::Ok(Json(CookieSession { session_user, csrf_token }))
+            },
+            Ok(_) => ProblemDetails::internal().into(),
+        }
+    }
+
+    /// Logout for browser front-ends, requires the `X-CSRF-Token` header like every state-changing request
+    #[oai(path = "/auth/session", method = "delete", tag = "ApiTags::Login", operation_id = "deleteSession")]
+    pub async fn delete_session(
+        &self,
+        state: Data<&AppState>,
+        req: &Request,
+        cookie_jar: &CookieJar,
+    ) -> DeleteSessionResponse {
+        let iam = &state.services.iam;
+        if let Some(token) = iam.session_cookie().token(req.headers()) {
+            if !iam.session_cookie().verify_csrf(req.method(), req.headers()) {
+                return ProblemDetails::new(StatusCode::FORBIDDEN).with_code("csrf_failed").into();
+            }
+            if let Err(e) = iam.revoke_token(token).await {
+                return ProblemDetails::from(e).into();
+            }
+        }
+        iam.session_cookie().end(cookie_jar);
+        DeleteSessionResponse::NoContent
+    }
+
+    /// End the session of a bearer token, only stored sessions can be revoked
+    #[oai(path = "/auth/logout", method = "post", tag = "ApiTags::Login", operation_id = "logout")]
+    pub async fn logout(&self, state: Data<&AppState>, auth: SessionToken) -> LogoutResponse {
+        match state.services.iam.revoke_token(auth.0.token).await {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(_) => LogoutResponse::NoContent,
+        }
+    }
+
     /// Create and return new user
-    #[oai(path = "/auth/register", method = "post", tag = "ApiTags::CreateUser")]
+    #[oai(path = "/auth/register", method = "post", tag = "ApiTags::CreateUser", operation_id = "register")]
     pub async fn register(
         &self,
         state: Data<&AppState>,
+        locale: Data<&Locale>,
         payload: Json<CreateUser>,
     ) -> RegisterResponse {
+        tracing::debug!(payload = %Redacted(&payload.0), "Register");
         match state
             .services
             .iam
@@ -87,13 +219,14 @@ impl API {
                 payload.first_name.clone(),
                 payload.last_name.clone(),
                 payload.password.clone(),
+                payload.locale.clone().unwrap_or_else(|| locale.0.0.to_string()),
             )
             .await
         {
-            Err(e) => match e {
-                AuthError::Conflict => return RegisterResponse::Conflict,
-                _ => RegisterResponse::InternalServerError(Json(ApiError::new(format!("{:?}", e)))),
-            },
+            Err(AuthError::Conflict) => ProblemDetails::from(AuthError::Conflict)
+                .with_code("email_taken")
+                .into(),
+            Err(e) => ProblemDetails::from(e).into(),
             Ok(ab) => { 
                 // TODO: Trigger send email
                 return RegisterResponse::Ok(Json(ab));
@@ -1 +1,2 @@
+#[allow(clippy::needless_return)]
 pub mod auth_controllers;
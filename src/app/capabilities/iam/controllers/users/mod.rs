@@ -1 +1,2 @@
+#[allow(clippy::needless_return)]
 pub mod users_controller;
\ No newline at end of file
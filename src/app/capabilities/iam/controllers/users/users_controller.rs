@@ -1,13 +1,31 @@
-use poem::web::Data;
-use poem_openapi::{payload::Json, ApiResponse, OpenApi, Tags};
+use poem::{
+    http::{header, StatusCode},
+    web::Data,
+};
+use poem_openapi::{payload::Json, types::ToJSON, ApiResponse, Object, OpenApi, Tags};
 
 use crate::app::capabilities::{
-    common::global_model::{api_error::ApiError, app_state::AppState, session_user::SessionUser},
-    iam::{helpers, models::user_data::UserData},
+    common::{
+        global_model::{app_state::AppState, session_user::SessionUser},
+        logging::redact::Redacted,
+        problem::problem_details::{ProblemDetails, PROBLEM_JSON},
+        tls::client_certificate::ClientCertificate,
+    },
+    iam::{
+        enums::auth_error::AuthError,
+        helpers,
+        models::user_data::UserData,
+        services::session_cookie::session_cookie_service::SESSION_COOKIE,
+    },
 };
 
-use poem::Request;
-use poem_openapi::{auth::Bearer, SecurityScheme};
+use poem::{Request, RequestBody};
+use poem_openapi::{
+    auth::Bearer,
+    error::AuthorizationError,
+    registry::{MetaSecurityScheme, Registry},
+    ApiExtractor, ApiExtractorType, ExtractParamOptions, SecurityScheme,
+};
 
 /// Bearer authorization
 #[derive(SecurityScheme)]
@@ -18,41 +36,258 @@ use poem_openapi::{auth::Bearer, SecurityScheme};
 pub struct JWTAuth(SessionUser);
 pub async fn api_checker(req: &Request, bearer: Bearer) -> Option<SessionUser> {
     let state = req.data::<AppState>().unwrap();
-    match state.services.iam.verify_token(bearer.token).await {
+    match state.services.iam.verify_request_token(req, bearer.token).await {
         Ok(session_user) => return Some(session_user),
         _ => return None,
     }
 }
 
+/// Mutual TLS client certificate mapped to a user in `iam.client_certificates`
+pub struct ClientCertAuth(SessionUser);
+
+// poem-openapi can't derive a `mutualTLS` scheme, so the extractor is written out
+impl<'a> ApiExtractor<'a> for ClientCertAuth {
+    const TYPES: &'static [ApiExtractorType] = &[ApiExtractorType::SecurityScheme];
+
+    type ParamType = ();
+    type ParamRawType = ();
+
+    fn register(registry: &mut Registry) {
+        registry.create_security_scheme(
+            "ClientCertAuth",
+            MetaSecurityScheme {
+                ty: "mutualTLS",
+                description: Some("Client certificate presented during the TLS handshake"),
+                name: None,
+                key_in: None,
+                scheme: None,
+                bearer_format: None,
+                flows: None,
+                openid_connect_url: None,
+            },
+        );
+    }
+
+    fn security_schemes() -> Vec<&'static str> {
+        vec!["ClientCertAuth"]
+    }
+
+    async fn from_request(
+        req: &'a Request,
+        _body: &mut RequestBody,
+        _param_opts: ExtractParamOptions<Self::ParamType>,
+    ) -> poem::Result<Self> {
+        let certificate = req.extensions().get::<ClientCertificate>().ok_or(AuthorizationError)?;
+        let state = req.data::<AppState>().unwrap();
+        match state.services.iam.verify_client_certificate(certificate).await {
+            Ok(session_user) => Ok(ClientCertAuth(session_user)),
+            Err(_) => Err(AuthorizationError.into()),
+        }
+    }
+}
+
+/// Encrypted session cookie set by `POST /auth/session`, state-changing requests also need
+/// the `X-CSRF-Token` header to match the `csrf_token` cookie
+pub struct CookieAuth(SessionUser);
+
+impl<'a> ApiExtractor<'a> for CookieAuth {
+    const TYPES: &'static [ApiExtractorType] = &[ApiExtractorType::SecurityScheme];
+
+    type ParamType = ();
+    type ParamRawType = ();
+
+    fn register(registry: &mut Registry) {
+        registry.create_security_scheme(
+            "CookieAuth",
+            MetaSecurityScheme {
+                ty: "apiKey",
+                description: Some("Session cookie, requires the X-CSRF-Token header on state-changing requests"),
+                name: Some(SESSION_COOKIE),
+                key_in: Some("cookie"),
+                scheme: None,
+                bearer_format: None,
+                flows: None,
+                openid_connect_url: None,
+            },
+        );
+    }
+
+    fn security_schemes() -> Vec<&'static str> {
+        vec!["CookieAuth"]
+    }
+
+    async fn from_request(
+        req: &'a Request,
+        _body: &mut RequestBody,
+        _param_opts: ExtractParamOptions<Self::ParamType>,
+    ) -> poem::Result<Self> {
+        let state = req.data::<AppState>().unwrap();
+        let cookies = state.services.iam.session_cookie();
+        let token = cookies.token(req.headers()).ok_or(AuthorizationError)?;
+        if !cookies.verify_csrf(req.method(), req.headers()) {
+            return Err(csrf_failed());
+        }
+        match state.services.iam.verify_request_token(req, token.clone()).await {
+            Ok(session_user) => {
+                let iam = &state.services.iam;
+                if iam.sliding_sessions() {
+                    cookies.refresh(req.cookie(), req.headers(), &token, iam.session_duration());
+                }
+                Ok(CookieAuth(session_user))
+            },
+            Err(_) => Err(AuthorizationError.into()),
+        }
+    }
+}
+
+fn csrf_failed() -> poem::Error {
+    let problem = ProblemDetails::new(StatusCode::FORBIDDEN).with_code("csrf_failed");
+    poem::Error::from_response(
+        poem::Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .header(header::CONTENT_TYPE, PROBLEM_JSON)
+            .body(problem.to_json_string()),
+    )
+}
+
+/// Bearer token, a client certificate when the server runs with mutual TLS, or the session
+/// cookie
+#[derive(SecurityScheme)]
+pub enum UserAuth {
+    Bearer(JWTAuth),
+    ClientCert(ClientCertAuth),
+    // last, so a failed CSRF check is the reported error
+    Cookie(CookieAuth),
+}
+
+impl UserAuth {
+    pub fn session_user(self) -> SessionUser {
+        match self {
+            UserAuth::Bearer(auth) => auth.0,
+            UserAuth::ClientCert(auth) => auth.0,
+            UserAuth::Cookie(auth) => auth.0,
+        }
+    }
+}
+
+#[derive(Debug, Object, Clone, Eq, PartialEq)]
+pub struct ChangePassword {
+    current_password: String,
+    /// Checked against the configured password policy
+    new_password: String,
+}
+
+#[derive(Debug, Object, Clone, Eq, PartialEq)]
+pub struct SetLocale {
+    /// Supported locale, e.g. `de` or `de-AT`
+    locale: String,
+}
+
 
 #[derive(ApiResponse)]
 pub enum GetUserResponse {
     #[oai(status = 200)]
     Ok(Json<UserData>),
-    #[oai(status = 500)]
-    InternalServerError(Json<ApiError>),
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for GetUserResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        GetUserResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum ChangePasswordResponse {
+    #[oai(status = 204)]
+    NoContent,
+    /// `invalid_current_password`, or `password_policy` with the failed rules
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for ChangePasswordResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        ChangePasswordResponse::Problem(problem.status_code(), Json(problem))
+    }
 }
 
+#[derive(ApiResponse)]
+pub enum SetLocaleResponse {
+    #[oai(status = 204)]
+    NoContent,
+    /// `validation_failed` for an unsupported locale
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for SetLocaleResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        SetLocaleResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
 
 #[derive(Tags)]
 enum ApiTags {
     /// Operations about user
-    GetUser
+    GetUser,
+    /// Password management of the logged in user
+    ChangePassword,
+    /// Preferences of the logged in user
+    Preferences,
 }
 
 #[derive(Default)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct API;
 
 #[OpenApi]
 impl API {
-    #[oai(path = "/users/me", method = "get", tag = "ApiTags::GetUser")]
-    pub async fn get_user(&self, state: Data<&AppState>, session_user: JWTAuth) -> GetUserResponse {
-        match state.services.iam.get_user(session_user.0).await {
-            Err(e) => return GetUserResponse::InternalServerError(Json(ApiError::new(format!("{:?}", e)))),
+    #[oai(path = "/users/me", method = "get", tag = "ApiTags::GetUser", operation_id = "getCurrentUser")]
+    pub async fn get_user(&self, state: Data<&AppState>, auth: UserAuth) -> GetUserResponse {
+        match state.services.iam.get_user(auth.session_user()).await {
+            Err(e) => ProblemDetails::from(e).into(),
             Ok(user) => { 
                 // TODO: Trigger send email
                 return GetUserResponse::Ok(Json(helpers::extract_user_api_data(user)));
             },
         }
     }
+
+    /// Change the password of the logged in user
+    #[oai(path = "/users/me/password", method = "put", tag = "ApiTags::ChangePassword", operation_id = "changePassword")]
+    pub async fn change_password(
+        &self,
+        state: Data<&AppState>,
+        auth: UserAuth,
+        payload: Json<ChangePassword>,
+    ) -> ChangePasswordResponse {
+        tracing::debug!(payload = %Redacted(&payload.0), "Change password");
+        match state
+            .services
+            .iam
+            .change_password(
+                auth.session_user(),
+                payload.current_password.clone(),
+                payload.new_password.clone(),
+            )
+            .await
+        {
+            Err(AuthError::BadRequest) => ProblemDetails::from(AuthError::BadRequest)
+                .with_code("invalid_current_password")
+                .into(),
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(_) => ChangePasswordResponse::NoContent,
+        }
+    }
+
+    /// Set the preferred locale of the logged in user, used for emails
+    #[oai(path = "/users/me/locale", method = "put", tag = "ApiTags::Preferences", operation_id = "setLocale")]
+    pub async fn set_locale(&self, state: Data<&AppState>, auth: UserAuth, payload: Json<SetLocale>) -> SetLocaleResponse {
+        match state.services.iam.set_locale(auth.session_user(), payload.locale.clone()).await {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(_) => SetLocaleResponse::NoContent,
+        }
+    }
 }
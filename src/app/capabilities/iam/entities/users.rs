@@ -6,6 +6,8 @@ use uuid::Uuid;
 use validator::Validate;
 use chrono::NaiveDateTime as DateTime;
 
+use crate::app::capabilities::common::global_model::validation_error::ValidationError;
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "users")]
 pub struct Model {
@@ -20,6 +22,8 @@ pub struct Model {
     pub first_name: String,
     pub last_name: String,
     pub password: Option<String>,
+    /// Preferred locale for emails, one of the supported catalogue locales
+    pub locale: String,
 }
 
 
@@ -28,14 +32,41 @@ pub enum Relation {}
 
 #[derive(Debug, Validate, Deserialize)]
 pub struct Validator {
-    #[validate(length(min = 2, message = "Name must be at least 2 characters long."))]
+    #[validate(length(min = 2, code = "name_length", message = "Name must be at least 2 characters long."))]
     pub first_name: String,
-    #[validate(length(min = 2, message = "Name must be at least 2 characters long."))]
+    #[validate(length(min = 2, code = "name_length", message = "Name must be at least 2 characters long."))]
     pub last_name: String,
-    #[validate(email)]
+    #[validate(email(message = "Email address is invalid."))]
     pub email: String,
-    #[validate(length(min = 8, message = "Password must be at least 8 characters long."))]
-    pub password: String,
+}
+
+impl Validator {
+    /// Failed rules by field, with the rule code and its parameters so messages can be localized
+    pub fn errors(&self) -> Vec<ValidationError> {
+        let Err(errors) = self.validate() else {
+            return vec![];
+        };
+        let mut fields: Vec<_> = errors.field_errors().into_iter().collect();
+        fields.sort_by_key(|(field, _)| *field);
+        fields
+            .into_iter()
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| {
+                    let message = error.message.clone().unwrap_or_else(|| error.code.clone());
+                    let mut result = ValidationError::new(field, &error.code, message.to_string());
+                    // `value` is the user input, never echoed back
+                    for (name, value) in error.params.iter().filter(|(name, _)| *name != "value") {
+                        result = match (value.as_i64(), value.as_str()) {
+                            (Some(number), _) => result.with_number_arg(name, number),
+                            (None, Some(text)) => result.with_arg(name, text),
+                            (None, None) => result.with_arg(name, value),
+                        };
+                    }
+                    result
+                })
+            })
+            .collect()
+    }
 }
 
 
@@ -45,7 +76,6 @@ impl ActiveModel {
             first_name: self.first_name.as_ref().to_owned(),
             last_name: self.last_name.as_ref().to_owned(),
             email: self.email.as_ref().to_owned(),
-            password: self.password.as_ref().to_owned().unwrap(),
         }
     }
 }
@@ -1,5 +1,12 @@
+use migration::sea_orm::DbErr;
+use poem::http::StatusCode;
 use serde::{Deserialize, Serialize};
 
+use crate::app::capabilities::common::{
+    database::unit_of_work::is_unique_violation, global_model::validation_error::ValidationError,
+    problem::problem_details::ProblemDetails,
+};
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum AuthError {
     JWTDurationError,
@@ -10,4 +17,59 @@ pub enum AuthError {
     InternalServerError,
     Conflict,
     BadRequest,
+    /// Password rejected by the password policy
+    PasswordPolicy(Vec<ValidationError>),
+    /// Invalid user fields
+    Validation(Vec<ValidationError>),
+}
+
+impl AuthError {
+    /// Variant name without its data, used as a metric label
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            AuthError::JWTDurationError => "JWTDurationError",
+            AuthError::JWTExpirationError => "JWTExpirationError",
+            AuthError::JWTSignError => "JWTSignError",
+            AuthError::JWTVerificationError => "JWTVerificationError",
+            AuthError::NotFound => "NotFound",
+            AuthError::InternalServerError => "InternalServerError",
+            AuthError::Conflict => "Conflict",
+            AuthError::BadRequest => "BadRequest",
+            AuthError::PasswordPolicy(_) => "PasswordPolicy",
+            AuthError::Validation(_) => "Validation",
+        }
+    }
+}
+
+/// A unique violation means another request created the same record first
+impl From<DbErr> for AuthError {
+    fn from(e: DbErr) -> Self {
+        if is_unique_violation(&e) {
+            return AuthError::Conflict;
+        }
+        tracing::error!("{}", e);
+        AuthError::InternalServerError
+    }
+}
+
+impl From<AuthError> for ProblemDetails {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::NotFound => ProblemDetails::new(StatusCode::NOT_FOUND),
+            AuthError::Conflict => ProblemDetails::new(StatusCode::CONFLICT),
+            AuthError::BadRequest => ProblemDetails::new(StatusCode::BAD_REQUEST),
+            AuthError::JWTExpirationError => ProblemDetails::new(StatusCode::UNAUTHORIZED).with_code("token_expired"),
+            AuthError::JWTVerificationError => ProblemDetails::new(StatusCode::UNAUTHORIZED).with_code("invalid_token"),
+            AuthError::PasswordPolicy(errors) => ProblemDetails::new(StatusCode::BAD_REQUEST)
+                .with_code("password_policy")
+                .with_errors(errors),
+            AuthError::Validation(errors) => ProblemDetails::new(StatusCode::BAD_REQUEST)
+                .with_code("validation_failed")
+                .with_errors(errors),
+            AuthError::JWTDurationError | AuthError::JWTSignError | AuthError::InternalServerError => {
+                tracing::error!("Auth error: {:?}", e);
+                ProblemDetails::internal()
+            }
+        }
+    }
 }
@@ -0,0 +1,31 @@
+use migration::sea_orm::prelude::Uuid;
+use serde::{Deserialize, Serialize};
+
+use crate::app::capabilities::common::events::domain_event::DomainEvent;
+
+/// A user signed up, recorded together with the new row in `users`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserRegistered {
+    pub pid: Uuid,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub locale: String,
+}
+
+impl DomainEvent for UserRegistered {
+    const EVENT_TYPE: &'static str = "user.registered";
+}
+
+/// The password of a user was replaced, recorded together with the new hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PasswordChanged {
+    pub pid: Uuid,
+    pub email: String,
+    pub first_name: String,
+    pub locale: String,
+}
+
+impl DomainEvent for PasswordChanged {
+    const EVENT_TYPE: &'static str = "user.password_changed";
+}
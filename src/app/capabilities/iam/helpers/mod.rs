@@ -18,6 +18,7 @@ pub fn extract_user_api_data(user: UserModel) -> UserData {
         first_name: user.first_name,
         last_name: user.last_name,
         email: user.email,
+        locale: user.locale,
         created_at: user.created_at.to_string(),
         updated_at: user.updated_at.to_string()
     }
@@ -0,0 +1,36 @@
+use prometheus::IntCounterVec;
+
+use crate::app::capabilities::common::metrics::metrics_service::MetricsService;
+
+use super::enums::auth_error::AuthError;
+
+/// Metrics of the IAM capability
+#[derive(Clone)]
+pub struct IamMetrics {
+    logins: IntCounterVec,
+    jwt_verification_failures: IntCounterVec,
+}
+
+impl IamMetrics {
+    pub fn new(metrics: &MetricsService) -> Self {
+        Self {
+            logins: metrics.counter_vec("iam_logins_total", "Login attempts by outcome", &["outcome"]),
+            jwt_verification_failures: metrics.counter_vec(
+                "iam_jwt_verification_failures_total",
+                "Rejected session tokens by AuthError variant",
+                &["error"],
+            ),
+        }
+    }
+
+    pub fn login(&self, success: bool) {
+        let outcome = if success { "success" } else { "failure" };
+        self.logins.with_label_values(&[outcome]).inc();
+    }
+
+    pub fn jwt_verification_failed(&self, error: &AuthError) {
+        self.jwt_verification_failures
+            .with_label_values(&[error.variant_name()])
+            .inc();
+    }
+}
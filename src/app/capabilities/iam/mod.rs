@@ -2,6 +2,9 @@ pub mod services;
 pub mod entities;
 pub mod controllers;
 pub mod constants;
+pub mod config;
+pub mod metrics;
 pub mod models;
 pub mod helpers;
 pub mod enums;
+pub mod events;
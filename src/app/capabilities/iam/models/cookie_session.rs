@@ -0,0 +1,12 @@
+use poem_openapi::Object;
+use serde::{Deserialize, Serialize};
+
+use crate::app::capabilities::common::global_model::session_user::SessionUser;
+
+/// Session kept in cookies, the token itself never reaches scripts
+#[derive(Debug, Serialize, Deserialize, Clone, Object)]
+pub struct CookieSession {
+    pub session_user: SessionUser,
+    /// Also in the `csrf_token` cookie, send it as `X-CSRF-Token` on state-changing requests
+    pub csrf_token: String,
+}
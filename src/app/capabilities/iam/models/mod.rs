@@ -1,2 +1,3 @@
+pub mod cookie_session;
 pub mod auth_bearer;
 pub mod user_data;
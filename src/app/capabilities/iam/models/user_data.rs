@@ -8,6 +8,8 @@ pub struct UserData {
     pub first_name: String,
     pub last_name: String,
     pub email: String,
+    /// Preferred locale for emails
+    pub locale: String,
     pub created_at: String,
     pub updated_at: String
 }
@@ -1,5 +1,6 @@
 use crate::app::capabilities::{
-    common::config::config_service::ConfigService, iam::enums::auth_error::AuthError,
+    common::config::{config_service::ConfigService, secret::Secret},
+    iam::enums::auth_error::AuthError,
 };
 use bcrypt::{hash, verify};
 use chrono::Utc;
@@ -15,25 +16,27 @@ pub struct Claims<T> {
 /// Takes care of signing and verification of tokens
 #[derive(Clone)]
 pub struct AuthSerivce{
-    /// Config service to extract auth config
-    config: ConfigService,
+    /// Key used when no explicit secret is passed
+    jwt_secret: Secret,
+    /// bcrypt work factor
+    bcrypt_cost: u32,
 }
 
 impl AuthSerivce {
     /// Create new auth service instance
     pub fn new(config: &ConfigService) -> Self {
         return Self {
-            config: *config,
+            jwt_secret: config.iam().jwt_secret.clone(),
+            bcrypt_cost: config.iam().bcrypt_cost,
         };
     }
-    /// Create jwt from payload
+    /// Create jwt from payload, signed with `secret` or the IAM secret when `None`
     pub fn sign<T: Serialize + for<'b> Deserialize<'b>>(
         &self,
         payload: T,
         duration: i64,
-        secret_key_var: Option<String>,
+        secret: Option<Secret>,
     ) -> Result<String, AuthError> {
-        let key_var = secret_key_var.unwrap_or("IAM_JWT_SECRET".to_owned());
         let exp_result = Utc::now().checked_add_signed(chrono::Duration::seconds(duration));
 
         if exp_result.is_none() {
@@ -45,8 +48,8 @@ impl AuthSerivce {
             exp: expiry,
         };
         let header = Header::new(Algorithm::HS512);
-        let secret: String = self.config.get_env(&key_var);
-        let encoding_key = EncodingKey::from_secret(secret.as_bytes());
+        let secret = secret.unwrap_or_else(|| self.jwt_secret.clone());
+        let encoding_key = EncodingKey::from_secret(secret.expose().as_bytes());
         match encode(&header, &claims, &encoding_key) {
             Ok(jwt) => return Ok(jwt),
             Err(e) => {
@@ -56,14 +59,13 @@ impl AuthSerivce {
         }
     }
 
-    /// Verify and get data from
+    /// Verify and get data from jwt, checked with `secret` or the IAM secret when `None`
     pub fn verify<T: Serialize + for<'b> Deserialize<'b>>(
         &self,
         jwt: String,
-        secret_key_var: Option<String>,
+        secret: Option<Secret>,
     ) -> Result<T, AuthError> {
-        let key_var = secret_key_var.unwrap_or("IAM_JWT_SECRET".to_owned());
-        let secret: String = self.config.get_env(&key_var);
+        let secret = secret.unwrap_or_else(|| self.jwt_secret.clone());
 
         let mut validation = Validation::new(Algorithm::HS512);
         // force validation of expiry
@@ -71,7 +73,7 @@ impl AuthSerivce {
 
         let decode_result = decode::<Claims<T>>(
             &jwt,
-            &DecodingKey::from_secret(secret.as_bytes()),
+            &DecodingKey::from_secret(secret.expose().as_bytes()),
             &validation,
         );
 
@@ -90,14 +92,11 @@ impl AuthSerivce {
 
     /// Create hash of string
     pub fn hash(&self, string: String) -> String {
-        hash(string, self.config.get_env::<u32>("BCRYPT_SALT")).unwrap()
+        hash(string, self.bcrypt_cost).unwrap()
     }
 
     /// verify a hash
     pub fn bcrypt_verify_hash(&self, string: String, hash: String) -> bool {
-        match verify(string, &hash) {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        verify(string, &hash).unwrap_or(false)
     }
 }
@@ -2,11 +2,21 @@ use serde::{Deserialize, Serialize};
 
 use super::auth_service::*;
 
-use crate::app::capabilities::{common::config::config_service::ConfigService, iam::enums::auth_error::AuthError};
+use crate::app::capabilities::{
+    common::config::{app_config::AppConfig, config_service::ConfigService, secret::Secret},
+    iam::enums::auth_error::AuthError,
+};
+
+fn test_config() -> ConfigService {
+    let mut config = AppConfig::default();
+    config.database.url = Secret::new(String::from("postgres://localhost/test"));
+    config.iam.jwt_secret = Secret::new(String::from("TEST_SECRET"));
+    ConfigService::from_config(config).unwrap()
+}
 
 #[test]
 fn should_sign_jwt() {
-    let config = ConfigService::new();
+    let config = test_config();
     let auth_service = AuthSerivce::new(&config);
     #[derive(Debug, Serialize, Deserialize)]
     struct Test {
@@ -23,7 +33,7 @@ fn should_sign_jwt() {
 
 #[test]
 fn should_verify_jwt() {
-    let config = ConfigService::new();
+    let config = test_config();
     let auth_service = AuthSerivce::new(&config);
     #[derive(Debug, Serialize, Deserialize, Clone)]
     struct Test {
@@ -48,7 +58,7 @@ fn should_verify_jwt() {
 
 #[test]
 fn should_reject_expired_tokens() {
-    let config = ConfigService::new();
+    let config = test_config();
     let auth_service = AuthSerivce::new(&config);
     #[derive(Debug, Serialize, Deserialize, Clone)]
     struct Test {
@@ -1,4 +1,6 @@
+#[allow(clippy::needless_return)]
 pub mod auth_service;
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::single_match)]
 mod auth_service_test;
@@ -1,15 +1,32 @@
 use crate::app::capabilities::common::*;
-use config::config_service::ConfigService;
+use crate::app::capabilities::common::config::config_service::ConfigService;
+use crate::app::capabilities::common::global_model::validation_error::ValidationError;
+use crate::app::capabilities::common::i18n::i18n_service::I18nService;
+use crate::app::capabilities::common::metrics::metrics_service::MetricsService;
+use crate::app::capabilities::common::problem::problem_details::ProblemDetails;
+use crate::app::capabilities::common::rate_limit::rate_limit_service::{IdentityFuture, UserIdentity};
+use crate::app::capabilities::common::tls::client_certificate::ClientCertificate;
+use crate::app::capabilities::iam::metrics::IamMetrics;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use constants::Constants;
-use entities::users::Model as UserModel;
+use entities::users::{Model as UserModel, Validator as UserValidator};
 use enums::auth_error::AuthError;
 use global_model::session_user::SessionUser;
-use sea_orm::DatabaseConnection;
+use database::db_router::DbRouter;
 
 use super::super::super::*;
 use models::auth_bearer::AuthBearer;
 use services::auth::auth_service::AuthSerivce;
-use services::users::users::*;
+use services::password::password_policy_service::{PasswordPolicyService, PersonalInfo};
+use services::session::{
+    memory_session_store::MemorySessionStore,
+    postgres_session_store::PostgresSessionStore,
+    session_service::SessionService,
+    session_store::SessionStore,
+};
+use services::session_cookie::session_cookie_service::SessionCookieService;
+use crate::app::capabilities::iam::config::SessionBackend;
+use services::users::user_repository::{NewUser, SeaOrmUserRepository, UserRepository};
 
 
 #[derive(Debug)]
@@ -17,77 +34,272 @@ pub enum IAMError {
     InternalServerError
 }
 
+impl From<IAMError> for ProblemDetails {
+    fn from(e: IAMError) -> Self {
+        match e {
+            IAMError::InternalServerError => ProblemDetails::internal(),
+        }
+    }
+}
+
+/// Outcome of verifying a request's token, left in the request extensions by the rate limiter
+/// so the security schemes don't verify the token, and touch its session, a second time
+#[derive(Clone)]
+pub struct VerifiedToken {
+    token: String,
+    session_user: Option<SessionUser>,
+}
+
+/// Users are identified by the `pid` of a valid bearer token or session cookie
+impl UserIdentity for IAMService {
+    fn user_id<'a>(&'a self, req: &'a mut poem::Request) -> IdentityFuture<'a> {
+        Box::pin(async move {
+            let bearer = req
+                .headers()
+                .get(poem::http::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(str::to_string);
+            let token = bearer.or_else(|| self.session_cookie.token(req.headers()))?;
+            let session_user = self.verify_request_token(req, token.clone()).await.ok();
+            req.extensions_mut().insert(VerifiedToken {
+                token,
+                session_user: session_user.clone(),
+            });
+            Some(session_user?.pid.to_string())
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct IAMService {
-    users: UserService,
+    users: Arc<dyn UserRepository>,
     auth: AuthSerivce,
+    password_policy: PasswordPolicyService,
+    client_certificates: Arc<HashMap<String, String>>,
+    admins: Arc<Vec<String>>,
+    /// Server-side sessions, `None` when sessions are JWTs
+    sessions: Option<SessionService>,
+    session_cookie: SessionCookieService,
+    metrics: IamMetrics,
+    i18n: I18nService,
     iam_constants: Constants,
 }
 
 impl IAMService {
-    pub fn new(db: DatabaseConnection, config: &ConfigService) -> Self {
-        Self { 
-            users: UserService::new(db),
+    pub fn new(db: DbRouter, config: &ConfigService, metrics: &MetricsService, i18n: I18nService) -> Result<Self, String> {
+        let session = &config.iam().session;
+        let store: Option<Arc<dyn SessionStore>> = match session.backend {
+            SessionBackend::Jwt => None,
+            SessionBackend::Memory => Some(Arc::new(MemorySessionStore::new())),
+            SessionBackend::Postgres => Some(Arc::new(PostgresSessionStore::new(db.clone()))),
+        };
+        Self::with_stores(Arc::new(SeaOrmUserRepository::new(db)), store, config, metrics, i18n)
+    }
+
+    /// IAM on the given user repository and session store (`None` for JWTs), e.g. mocks in tests.
+    /// Fails when the breached password list can't be read.
+    pub fn with_stores(
+        users: Arc<dyn UserRepository>,
+        store: Option<Arc<dyn SessionStore>>,
+        config: &ConfigService,
+        metrics: &MetricsService,
+        i18n: I18nService,
+    ) -> Result<Self, String> {
+        let idle_timeout = Duration::from_secs(config.iam().session.idle_timeout_secs);
+        Ok(Self {
+            sessions: store.map(|store| SessionService::new(store, idle_timeout)),
+            users,
             auth: AuthSerivce::new(config),
+            password_policy: PasswordPolicyService::new(config)?,
+            client_certificates: Arc::new(config.iam().client_certificates.clone()),
+            admins: Arc::new(config.iam().admins.clone()),
+            session_cookie: SessionCookieService::new(config),
+            metrics: IamMetrics::new(metrics),
+            i18n,
             iam_constants: Constants::new(),
-        }
+        })
     }
     /// Execute login logic
     pub async fn login(&self, email: String, password: String) -> Result<AuthBearer, AuthError> {
-        match self.users.find_user_by_email(email).await {
-            Ok(Some(user)) => {
-                if self.auth.bcrypt_verify_hash(password, user.password.clone().unwrap()) {
-                    return self.create_session_for_user(user).await;
-                } else {
-                    return Err(AuthError::NotFound);
-                }
+        let result = match self.users.find_by_email(email).await {
+            Ok(Some(user)) if self.auth.bcrypt_verify_hash(password, user.password.clone().unwrap_or_default()) => {
+                self.create_session_for_user(user).await
             },
             _ => {
-                return Err(AuthError::NotFound);
+                Err(AuthError::NotFound)
+            }
+        };
+        self.metrics.login(result.is_ok());
+        result
+    }
+
+    /// Execute register logic, `locale` is the user's preferred locale for emails
+    pub async fn register(&self, email: String, first_name: String, last_name: String, password: String, locale: String) -> Result<AuthBearer, AuthError> {
+        let mut errors = UserValidator {
+            first_name: first_name.clone(),
+            last_name: last_name.clone(),
+            email: email.clone(),
+        }
+        .errors();
+        let locale = match self.supported_locale(&locale) {
+            Ok(locale) => locale,
+            Err(error) => {
+                errors.push(error);
+                locale
             }
+        };
+        if !errors.is_empty() {
+            return Err(AuthError::Validation(errors));
+        }
+
+        let personal_info = PersonalInfo {
+            email: email.clone(),
+            first_name: first_name.clone(),
+            last_name: last_name.clone(),
+        };
+        self.validate_password(&password, &personal_info)?;
+
+        // the check gives a clean conflict for the common case, a concurrent registration that
+        // passes it too fails on the unique index and is mapped to the same conflict
+        let uow = self.users.begin().await?;
+        if self.users.find_by_email_for_write(&uow, email.clone()).await?.is_some() {
+            return Err(AuthError::Conflict);
         }
+        let user = self
+            .users
+            .create(
+                &uow,
+                NewUser {
+                    email,
+                    first_name,
+                    last_name,
+                    password: self.auth.hash(password),
+                    locale,
+                },
+            )
+            .await?;
+        uow.commit().await?;
+        self.create_session_for_user(user).await
     }
 
-    /// Execute register logic
-    pub async fn register(&self, email: String, first_name: String, last_name: String, password: String) -> Result<AuthBearer, AuthError> {
-        match self.users.find_user_by_email(email.clone()).await {
-            Ok(Some(_)) => {
-                return Err(AuthError::Conflict);
-            },
+    /// Change the password of the session user after checking the current one
+    pub async fn change_password(&self, session_user: SessionUser, current_password: String, new_password: String) -> Result<(), AuthError> {
+        let user = match self.users.find_by_pid(session_user.pid).await {
+            Ok(Some(user)) => user,
+            Ok(None) => return Err(AuthError::NotFound),
             Err(e) => {
                 tracing::error!("{}", e);
                 return Err(AuthError::InternalServerError);
             },
-            _ => {();},
         };
+        if !self.auth.bcrypt_verify_hash(current_password, user.password.clone().unwrap_or_default()) {
+            return Err(AuthError::BadRequest);
+        }
+        self.set_password(user, new_password).await
+    }
 
-        match self.users.create_user(email, first_name, last_name, self.auth.hash(password)).await {
-            Ok(Some(user)) => {
-                return self.create_session_for_user(user).await;
-            },
+    /// Store the preferred locale of the session user
+    pub async fn set_locale(&self, session_user: SessionUser, locale: String) -> Result<(), AuthError> {
+        let locale = self
+            .supported_locale(&locale)
+            .map_err(|error| AuthError::Validation(vec![error]))?;
+        let user = match self.users.find_by_pid(session_user.pid).await {
+            Ok(Some(user)) => user,
+            Ok(None) => return Err(AuthError::NotFound),
             Err(e) => {
                 tracing::error!("{}", e);
                 return Err(AuthError::InternalServerError);
             },
-            _ => {
-                tracing::error!("User not created");
-                return Err(AuthError::InternalServerError);
-            }
-        }
+        };
+        self.users.update_locale(user, locale).await?;
+        Ok(())
+    }
+
+    /// Normalize a requested locale to a supported one, `de-AT` is stored as `de`
+    fn supported_locale(&self, locale: &str) -> Result<String, ValidationError> {
+        self.i18n.find(locale).map(|locale| locale.to_string()).ok_or_else(|| {
+            ValidationError::new("locale", "unsupported_locale", String::from("Locale is not supported."))
+        })
+    }
+
+    /// Check a password against the password policy.
+    ///
+    /// Every flow that sets a password (register, change, reset) goes through this.
+    pub fn validate_password(&self, password: &str, personal_info: &PersonalInfo) -> Result<(), AuthError> {
+        self.password_policy
+            .validate(password, personal_info)
+            .map_err(AuthError::PasswordPolicy)
     }
 
-    /// Verify Auth Session
+    /// Verify Auth Session, a JWT or the id of a stored session depending on the session backend
     pub async fn verify_token(&self, jwt: String) -> Result<SessionUser, AuthError> {
-        match self.auth.verify::<SessionUser>(jwt, Some(self.iam_constants.jwt_key_var.clone())) {
-            Ok(session_user) => Ok(session_user),
-            Err(e) =>  Err(e),
+        let result = match &self.sessions {
+            Some(sessions) => sessions.verify(&jwt).await,
+            None => self.auth.verify::<SessionUser>(jwt, None),
+        };
+        result.inspect_err(|e| {
+            self.metrics.jwt_verification_failed(e);
+        })
+    }
+
+    /// Verify `token` sent with `req`, reusing the outcome of an earlier check of the same token
+    pub async fn verify_request_token(&self, req: &poem::Request, token: String) -> Result<SessionUser, AuthError> {
+        match req.extensions().get::<VerifiedToken>() {
+            Some(verified) if verified.token == token => verified.session_user.clone().ok_or(AuthError::JWTVerificationError),
+            _ => self.verify_token(token).await,
+        }
+    }
+
+    /// End a stored session, JWTs stay valid until they expire
+    pub async fn revoke_token(&self, token: String) -> Result<(), AuthError> {
+        match &self.sessions {
+            Some(sessions) => sessions.revoke(&token).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Sign in the user mapped to a verified mutual TLS client certificate
+    pub async fn verify_client_certificate(&self, certificate: &ClientCertificate) -> Result<SessionUser, AuthError> {
+        let Some(email) = self.client_certificates.get(&certificate.fingerprint) else {
+            return Err(AuthError::NotFound);
+        };
+        match self.users.find_by_email(email.clone()).await {
+            Ok(Some(user)) => Ok(helpers::user_to_session(user)),
+            Ok(None) => Err(AuthError::NotFound),
+            Err(e) => {
+                tracing::error!("{}", e);
+                Err(AuthError::InternalServerError)
+            },
+        }
+    }
+
+    /// Whether the user is listed in `iam.admins`
+    pub fn is_admin(&self, session_user: &SessionUser) -> bool {
+        self.admins.iter().any(|email| email.eq_ignore_ascii_case(&session_user.email))
+    }
+
+    /// Cookie sessions for browser front-ends
+    pub fn session_cookie(&self) -> &SessionCookieService {
+        &self.session_cookie
+    }
+
+    /// Whether sessions are store-backed, so each verified request extends them
+    pub fn sliding_sessions(&self) -> bool {
+        self.sessions.is_some()
+    }
+
+    /// Lifetime of the tokens created by login and register, the idle timeout of stored sessions
+    pub fn session_duration(&self) -> Duration {
+        match &self.sessions {
+            Some(sessions) => sessions.idle_timeout(),
+            None => Duration::from_secs(self.iam_constants.login_duration as u64),
         }
-       
     }
 
     /// Get user data from session
     pub async fn get_user(&self, session_user: SessionUser) -> Result<UserModel, IAMError> {
-        match self.users.find_user_by_pid(session_user.pid).await {
+        match self.users.find_by_pid(session_user.pid).await {
             Ok(Some(mut user)) => {
                 user.password = None;
                 return Ok(user);
@@ -100,13 +312,33 @@ impl IAMService {
         }
     }
 
+    /// Validate and store a new password for a user
+    async fn set_password(&self, user: UserModel, password: String) -> Result<(), AuthError> {
+        let personal_info = PersonalInfo {
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+        };
+        self.validate_password(&password, &personal_info)?;
+        let uow = self.users.begin().await?;
+        self.users.update_password(&uow, user, self.auth.hash(password)).await?;
+        uow.commit().await?;
+        Ok(())
+    }
+
     /// create auth bearer from user
     async fn create_session_for_user(&self, user: UserModel) -> Result<AuthBearer, AuthError> {
         let session_user = helpers::user_to_session(user);
+        if let Some(sessions) = &self.sessions {
+            return Ok(AuthBearer {
+                token: sessions.create(&session_user).await?,
+                session_user: Some(session_user),
+            });
+        }
         match self.auth.sign(
             session_user.clone(), 
             self.iam_constants.login_duration, 
-            Some(self.iam_constants.jwt_key_var.clone())
+            None
         ) {
             Ok(jwt) => return Ok(AuthBearer {
                 token: jwt,
@@ -0,0 +1,252 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use chrono::Utc;
+use migration::sea_orm::DbErr;
+use uuid::Uuid;
+
+use super::iam_service::*;
+
+use crate::app::capabilities::{
+    common::{
+        config::{app_config::AppConfig, config_service::ConfigService, secret::Secret},
+        database::unit_of_work::UnitOfWork,
+        global_model::session_user::SessionUser,
+        i18n::i18n_service::I18nService,
+        metrics::metrics_service::MetricsService,
+        rate_limit::rate_limit_service::UserIdentity,
+    },
+    iam::{
+        entities::users::Model as UserModel,
+        enums::auth_error::AuthError,
+        helpers::user_to_session,
+        services::{
+            session::{
+                memory_session_store::MemorySessionStore,
+                session_store::{SessionFuture, SessionStore},
+            },
+            users::user_repository::MockUserRepository,
+        },
+    },
+};
+
+const PASSWORD: &str = "correct horse battery staple";
+
+fn service(users: MockUserRepository) -> IAMService {
+    service_with(users, None, &MetricsService::new())
+}
+
+fn service_with(users: MockUserRepository, store: Option<Arc<dyn SessionStore>>, metrics: &MetricsService) -> IAMService {
+    let mut config = AppConfig::default();
+    config.database.url = Secret::new(String::from("postgres://localhost/test"));
+    config.iam.jwt_secret = Secret::new(String::from("TEST_SECRET"));
+    config.iam.bcrypt_cost = 4;
+    let config = ConfigService::from_config(config).unwrap();
+    IAMService::with_stores(Arc::new(users), store, &config, metrics, I18nService::new("en")).unwrap()
+}
+
+/// Sessions in memory, counting how often one is touched
+#[derive(Default)]
+struct CountingSessionStore {
+    inner: MemorySessionStore,
+    touches: AtomicUsize,
+}
+
+impl SessionStore for CountingSessionStore {
+    fn create<'a>(&'a self, key: &'a str, session_user: &'a SessionUser, ttl: Duration) -> SessionFuture<'a, ()> {
+        self.inner.create(key, session_user, ttl)
+    }
+
+    fn touch<'a>(&'a self, key: &'a str, ttl: Duration) -> SessionFuture<'a, Option<SessionUser>> {
+        self.touches.fetch_add(1, Ordering::Relaxed);
+        self.inner.touch(key, ttl)
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> SessionFuture<'a, ()> {
+        self.inner.delete(key)
+    }
+}
+
+fn bearer_request(token: &str) -> poem::Request {
+    poem::Request::builder()
+        .header(poem::http::header::AUTHORIZATION, format!("Bearer {}", token))
+        .finish()
+}
+
+fn user() -> UserModel {
+    let now = Utc::now().naive_utc();
+    UserModel {
+        created_at: now,
+        updated_at: now,
+        id: 1,
+        pid: Uuid::new_v4(),
+        email: String::from("jane@example.com"),
+        first_name: String::from("Jane"),
+        last_name: String::from("Doe"),
+        password: Some(bcrypt::hash(PASSWORD, 4).unwrap()),
+        locale: String::from("en"),
+    }
+}
+
+async fn register(iam: &IAMService) -> Result<String, AuthError> {
+    iam.register(
+        String::from("jane@example.com"),
+        String::from("Jane"),
+        String::from("Doe"),
+        String::from(PASSWORD),
+        String::from("en"),
+    )
+    .await
+    .map(|bearer| bearer.token)
+}
+
+#[tokio::test]
+async fn should_login_with_the_stored_password() {
+    let user = user();
+    let pid = user.pid;
+    let mut users = MockUserRepository::new();
+    users.expect_find_by_email().returning(move |_| Ok(Some(user.clone())));
+    let iam = service(users);
+
+    let bearer = iam.login(String::from("jane@example.com"), String::from(PASSWORD)).await.unwrap();
+    assert_eq!(iam.verify_token(bearer.token).await.unwrap().pid, pid);
+    let wrong = iam.login(String::from("jane@example.com"), String::from("wrong password")).await;
+    assert_eq!(wrong.unwrap_err(), AuthError::NotFound);
+}
+
+#[tokio::test]
+async fn should_not_tell_unknown_users_from_failed_lookups_on_login() {
+    let mut users = MockUserRepository::new();
+    users.expect_find_by_email().times(1).returning(|_| Ok(None));
+    users
+        .expect_find_by_email()
+        .times(1)
+        .returning(|_| Err(DbErr::Custom(String::from("connection reset"))));
+    let iam = service(users);
+
+    for _ in 0..2 {
+        let result = iam.login(String::from("jane@example.com"), String::from(PASSWORD)).await;
+        assert_eq!(result.unwrap_err(), AuthError::NotFound);
+    }
+}
+
+#[tokio::test]
+async fn should_register_new_users_with_a_hashed_password() {
+    let mut users = MockUserRepository::new();
+    users.expect_begin().times(1).returning(|| Ok(UnitOfWork::detached()));
+    users.expect_find_by_email_for_write().returning(|_, _| Ok(None));
+    users
+        .expect_create()
+        .withf(|_, new_user| new_user.email == "jane@example.com" && bcrypt::verify(PASSWORD, &new_user.password).unwrap())
+        .times(1)
+        .returning(|_, _| Ok(UserModel { password: None, ..user() }));
+    let iam = service(users);
+
+    let token = register(&iam).await.unwrap();
+    assert_eq!(iam.verify_token(token).await.unwrap().email, "jane@example.com");
+}
+
+#[tokio::test]
+async fn should_reject_taken_emails_and_invalid_users_before_creating() {
+    let mut users = MockUserRepository::new();
+    users.expect_begin().times(1).returning(|| Ok(UnitOfWork::detached()));
+    users.expect_find_by_email_for_write().times(1).returning(|_, _| Ok(Some(user())));
+    users.expect_create().never();
+    let iam = service(users);
+
+    assert_eq!(register(&iam).await.unwrap_err(), AuthError::Conflict);
+    let invalid = iam
+        .register(String::from("jane"), String::from("J"), String::from("Doe"), String::from(PASSWORD), String::from("en"))
+        .await;
+    assert!(matches!(invalid, Err(AuthError::Validation(errors)) if errors.len() == 2));
+}
+
+#[tokio::test]
+async fn should_hide_database_errors_on_register() {
+    let mut users = MockUserRepository::new();
+    users.expect_begin().returning(|| Ok(UnitOfWork::detached()));
+    users.expect_find_by_email_for_write().returning(|_, _| Ok(None));
+    users.expect_create().returning(|_, _| Err(DbErr::RecordNotInserted));
+    let iam = service(users);
+
+    assert_eq!(register(&iam).await.unwrap_err(), AuthError::InternalServerError);
+}
+
+#[tokio::test]
+async fn should_not_check_or_create_outside_a_unit_of_work() {
+    let mut users = MockUserRepository::new();
+    users
+        .expect_begin()
+        .returning(|| Err(DbErr::Custom(String::from("connection reset"))));
+    users.expect_find_by_email_for_write().never();
+    users.expect_create().never();
+    let iam = service(users);
+
+    assert_eq!(register(&iam).await.unwrap_err(), AuthError::InternalServerError);
+}
+
+#[tokio::test]
+async fn should_get_the_session_user_without_password() {
+    let user = user();
+    let session_user: SessionUser = user_to_session(user.clone());
+    let mut users = MockUserRepository::new();
+    users
+        .expect_find_by_pid()
+        .withf(move |pid| *pid == user.pid)
+        .returning(move |_| Ok(Some(user.clone())));
+    let iam = service(users);
+
+    let found = iam.get_user(session_user).await.unwrap();
+    assert_eq!(found.email, "jane@example.com");
+    assert_eq!(found.password, None);
+}
+
+#[tokio::test]
+async fn should_fail_get_user_when_the_user_is_gone_or_the_lookup_fails() {
+    let session_user = user_to_session(user());
+    let mut users = MockUserRepository::new();
+    users.expect_find_by_pid().times(1).returning(|_| Ok(None));
+    users
+        .expect_find_by_pid()
+        .times(1)
+        .returning(|_| Err(DbErr::Custom(String::from("connection reset"))));
+    let iam = service(users);
+
+    for _ in 0..2 {
+        let result = iam.get_user(session_user.clone()).await;
+        assert!(matches!(result, Err(IAMError::InternalServerError)));
+    }
+}
+
+#[tokio::test]
+async fn should_verify_the_token_once_per_request() {
+    let user = user();
+    let pid = user.pid;
+    let mut users = MockUserRepository::new();
+    users.expect_find_by_email().returning(move |_| Ok(Some(user.clone())));
+    let store = Arc::new(CountingSessionStore::default());
+    let metrics = MetricsService::new();
+    let iam = service_with(users, Some(store.clone()), &metrics);
+    let token = iam.login(String::from("jane@example.com"), String::from(PASSWORD)).await.unwrap().token;
+
+    // the rate limiter identifies the user, then the security scheme checks the same token
+    let mut req = bearer_request(&token);
+    assert_eq!(iam.user_id(&mut req).await, Some(pid.to_string()));
+    assert_eq!(iam.verify_request_token(&req, token.clone()).await.unwrap().pid, pid);
+    assert_eq!(store.touches.load(Ordering::Relaxed), 1);
+
+    let mut req = bearer_request("forged");
+    assert_eq!(iam.user_id(&mut req).await, None);
+    assert_eq!(iam.verify_request_token(&req, String::from("forged")).await.unwrap_err(), AuthError::JWTVerificationError);
+    assert_eq!(store.touches.load(Ordering::Relaxed), 2);
+    assert!(metrics.render().contains("iam_jwt_verification_failures_total{error=\"JWTVerificationError\"} 1"));
+
+    // a different token on the same request is verified on its own
+    assert!(iam.verify_request_token(&req, token).await.is_ok());
+    assert_eq!(store.touches.load(Ordering::Relaxed), 3);
+}
@@ -1,4 +1,5 @@
+#[allow(clippy::needless_return)]
 pub mod iam_service;
 
-// #[cfg(test)]
-// mod iam_service_test;
+#[cfg(test)]
+mod iam_service_test;
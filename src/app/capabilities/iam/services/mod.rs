@@ -1,3 +1,7 @@
 mod auth;
 pub mod iam;
-mod users;
+pub mod notifications;
+pub mod password;
+pub mod session;
+pub mod session_cookie;
+pub mod users;
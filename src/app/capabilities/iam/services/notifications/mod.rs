@@ -0,0 +1 @@
+pub mod user_emails_subscriber;
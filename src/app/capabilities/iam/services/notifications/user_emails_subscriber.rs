@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use migration::sea_orm::DatabaseTransaction;
+
+use crate::app::capabilities::{
+    common::{
+        email::{email_service::EmailService, send_email_job::SendEmail},
+        events::{
+            domain_event::{DomainEvent, EventEnvelope},
+            event_subscriber::{EventFuture, EventSubscriber},
+        },
+    },
+    iam::events::{PasswordChanged, UserRegistered},
+};
+
+/// Queues the account emails of IAM events in the user's locale
+pub struct UserEmailsSubscriber {
+    pub emails: EmailService,
+}
+
+impl EventSubscriber for UserEmailsSubscriber {
+    fn name(&self) -> &str {
+        "user_emails"
+    }
+
+    fn event_types(&self) -> &[&'static str] {
+        &[UserRegistered::EVENT_TYPE, PasswordChanged::EVENT_TYPE]
+    }
+
+    fn handle<'a>(&'a self, txn: &'a DatabaseTransaction, event: &'a EventEnvelope) -> EventFuture<'a> {
+        Box::pin(async move {
+            let email = match (event.decode::<UserRegistered>(), event.decode::<PasswordChanged>()) {
+                (Some(registered), _) => {
+                    let registered = registered.map_err(|e| e.to_string())?;
+                    SendEmail {
+                        to: registered.email.clone(),
+                        email: String::from("welcome"),
+                        locale: registered.locale,
+                        args: HashMap::from([
+                            (String::from("first_name"), registered.first_name),
+                            (String::from("email"), registered.email),
+                        ]),
+                    }
+                }
+                (_, Some(changed)) => {
+                    let changed = changed.map_err(|e| e.to_string())?;
+                    SendEmail {
+                        to: changed.email,
+                        email: String::from("password_changed"),
+                        locale: changed.locale,
+                        args: HashMap::from([(String::from("first_name"), changed.first_name)]),
+                    }
+                }
+                _ => return Ok(()),
+            };
+            self.emails.enqueue_in(txn, &email).await.map_err(|e| e.to_string())
+        })
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use sha1::{Digest, Sha1};
+
+/// Length of the hash prefix used to bucket the list, same as the HIBP range API
+const PREFIX_LENGTH: usize = 5;
+
+/// Offline list of breached password SHA-1 hashes.
+///
+/// The file holds one upper or lower case hex SHA-1 per line, optionally followed by
+/// `:<count>` (the format of the Have I Been Pwned downloads). Hashes are bucketed by
+/// their first five characters so a lookup only ever compares suffixes of one range.
+#[derive(Debug, Default, Clone)]
+pub struct BreachedPasswords {
+    ranges: HashMap<String, HashSet<String>>,
+}
+
+impl BreachedPasswords {
+    /// List without any entries, nothing is reported as breached
+    pub fn empty() -> BreachedPasswords {
+        BreachedPasswords::default()
+    }
+
+    /// Load the list from a file on disk
+    pub fn load(path: &Path) -> io::Result<BreachedPasswords> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut list = BreachedPasswords::empty();
+        for line in reader.lines() {
+            let line = line?;
+            let hash = line.split(':').next().unwrap_or_default().trim();
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue;
+            }
+            list.insert_hash(hash);
+        }
+        Ok(list)
+    }
+
+    /// Add a hex SHA-1 hash to the list
+    pub fn insert_hash(&mut self, hash: &str) {
+        let hash = hash.to_ascii_uppercase();
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+        self.ranges
+            .entry(prefix.to_string())
+            .or_default()
+            .insert(suffix.to_string());
+    }
+
+    /// Number of hashes in the list
+    pub fn len(&self) -> usize {
+        self.ranges.values().map(|range| range.len()).sum()
+    }
+
+    /// Check if the password appears in the list
+    pub fn contains(&self, password: &str) -> bool {
+        let hash = sha1_hex(password);
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+        match self.ranges.get(prefix) {
+            Some(range) => range.contains(suffix),
+            None => false,
+        }
+    }
+}
+
+/// Upper case hex SHA-1 of a string
+pub fn sha1_hex(value: &str) -> String {
+    let digest = Sha1::digest(value.as_bytes());
+    digest.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
@@ -0,0 +1,6 @@
+pub mod password_policy_service;
+pub mod breached_passwords;
+pub mod strength;
+
+#[cfg(test)]
+mod password_policy_service_test;
@@ -0,0 +1,204 @@
+use std::{path::Path, sync::Arc};
+
+use serde::Deserialize;
+
+use crate::app::capabilities::common::{
+    config::config_service::ConfigService, global_model::validation_error::ValidationError,
+};
+
+use super::{breached_passwords::BreachedPasswords, strength::estimate_strength};
+
+/// Field name reported on password validation errors
+const PASSWORD_FIELD: &str = "password";
+
+/// Shortest user value that is checked for inside a password
+const MIN_PERSONAL_INFO_LENGTH: usize = 3;
+
+/// bcrypt ignores everything after 72 bytes, so longer passwords would share a hash with their prefix
+pub const BCRYPT_MAX_BYTES: usize = 72;
+
+/// Rules a password has to satisfy
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    /// In characters
+    pub min_length: usize,
+    /// In characters, passwords over `BCRYPT_MAX_BYTES` bytes are rejected whatever this is
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Reject passwords containing the user's email or names
+    pub disallow_personal_info: bool,
+    /// Minimum zxcvbn style score (0-4)
+    pub min_strength: u8,
+    /// Path to a file of breached password SHA-1 hashes
+    pub breached_passwords_file: Option<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: 8,
+            max_length: 72,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            disallow_personal_info: true,
+            min_strength: 2,
+            breached_passwords_file: None,
+        }
+    }
+}
+
+/// Values tied to the account that must not be reused in its password
+#[derive(Debug, Clone, Default)]
+pub struct PersonalInfo {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+impl PersonalInfo {
+    fn values(&self) -> Vec<&str> {
+        let local_part = self.email.split('@').next().unwrap_or_default();
+        vec![
+            self.email.as_str(),
+            local_part,
+            self.first_name.as_str(),
+            self.last_name.as_str(),
+        ]
+    }
+}
+
+/// Validates passwords against the configured policy
+#[derive(Clone)]
+pub struct PasswordPolicyService {
+    policy: PasswordPolicy,
+    breached: Arc<BreachedPasswords>,
+}
+
+impl PasswordPolicyService {
+    /// Create new password policy service from the IAM config, fails on an unreadable breached password list
+    pub fn new(config: &ConfigService) -> Result<Self, String> {
+        let policy = config.iam().password_policy.clone();
+        let breached = match &policy.breached_passwords_file {
+            Some(file) => {
+                let list = BreachedPasswords::load(Path::new(file))
+                    .map_err(|e| format!("Could not read breached password list {}: {}", file, e))?;
+                tracing::debug!("Loaded {} breached password hashes", list.len());
+                list
+            }
+            None => BreachedPasswords::empty(),
+        };
+        Ok(Self::with_policy(policy, breached))
+    }
+
+    /// Create service from an explicit policy and breached password list
+    pub fn with_policy(policy: PasswordPolicy, breached: BreachedPasswords) -> Self {
+        Self {
+            policy,
+            breached: Arc::new(breached),
+        }
+    }
+
+    /// Check a password, returning one error per violated rule
+    pub fn validate(&self, password: &str, personal_info: &PersonalInfo) -> Result<(), Vec<ValidationError>> {
+        let policy = &self.policy;
+        let mut errors = vec![];
+        let length = password.chars().count();
+
+        if length < policy.min_length {
+            errors.push(
+                violation(
+                    "min_length",
+                    format!("Password must be at least {} characters long.", policy.min_length),
+                )
+                .with_number_arg("min", policy.min_length as i64),
+            );
+        }
+        if length > policy.max_length {
+            errors.push(
+                violation(
+                    "max_length",
+                    format!("Password must be at most {} characters long.", policy.max_length),
+                )
+                .with_number_arg("max", policy.max_length as i64),
+            );
+        } else if password.len() > BCRYPT_MAX_BYTES {
+            errors.push(
+                violation(
+                    "max_bytes",
+                    format!("Password must be at most {} bytes long.", BCRYPT_MAX_BYTES),
+                )
+                .with_number_arg("max", BCRYPT_MAX_BYTES as i64),
+            );
+        }
+        if policy.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            errors.push(violation(
+                "require_lowercase",
+                String::from("Password must contain a lowercase letter."),
+            ));
+        }
+        if policy.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            errors.push(violation(
+                "require_uppercase",
+                String::from("Password must contain an uppercase letter."),
+            ));
+        }
+        if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            errors.push(violation(
+                "require_digit",
+                String::from("Password must contain a digit."),
+            ));
+        }
+        if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric()) {
+            errors.push(violation(
+                "require_symbol",
+                String::from("Password must contain a symbol."),
+            ));
+        }
+
+        let personal_values = personal_info.values();
+        if policy.disallow_personal_info {
+            let lower = password.to_lowercase();
+            let contains_personal_info = personal_values.iter().any(|value| {
+                let value = value.trim().to_lowercase();
+                value.chars().count() >= MIN_PERSONAL_INFO_LENGTH && lower.contains(&value)
+            });
+            if contains_personal_info {
+                errors.push(violation(
+                    "personal_info",
+                    String::from("Password must not contain your name or email."),
+                ));
+            }
+        }
+
+        let strength = estimate_strength(password, &personal_values);
+        if strength < policy.min_strength {
+            errors.push(violation(
+                "strength",
+                String::from("Password is too easy to guess."),
+            ));
+        }
+
+        if self.breached.contains(password) {
+            errors.push(violation(
+                "breached",
+                String::from("Password has appeared in a data breach, please choose another one."),
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn violation(rule: &str, message: String) -> ValidationError {
+    ValidationError::new(PASSWORD_FIELD, rule, message)
+}
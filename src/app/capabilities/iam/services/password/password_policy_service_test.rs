@@ -0,0 +1,99 @@
+use std::io::Write;
+
+use crate::app::capabilities::common::global_model::validation_error::ValidationError;
+
+use super::breached_passwords::*;
+use super::password_policy_service::*;
+use super::strength::estimate_strength;
+
+fn personal_info() -> PersonalInfo {
+    PersonalInfo {
+        email: String::from("jane.doe@example.com"),
+        first_name: String::from("Jane"),
+        last_name: String::from("Doe"),
+    }
+}
+
+fn rules(result: Result<(), Vec<ValidationError>>) -> Vec<String> {
+    match result {
+        Ok(_) => vec![],
+        Err(errors) => errors.into_iter().map(|e| e.rule).collect(),
+    }
+}
+
+#[test]
+fn should_accept_strong_password() {
+    let service = PasswordPolicyService::with_policy(PasswordPolicy::default(), BreachedPasswords::empty());
+    let result = service.validate("correct horse battery staple", &personal_info());
+    assert!(result.is_ok());
+}
+
+#[test]
+fn should_report_every_violated_rule() {
+    let policy = PasswordPolicy {
+        require_uppercase: true,
+        require_digit: true,
+        require_symbol: true,
+        ..PasswordPolicy::default()
+    };
+    let service = PasswordPolicyService::with_policy(policy, BreachedPasswords::empty());
+    let violated = rules(service.validate("abc", &personal_info()));
+    assert_eq!(
+        violated,
+        vec!["min_length", "require_uppercase", "require_digit", "require_symbol", "strength"]
+    );
+}
+
+#[test]
+fn should_reject_too_long_password() {
+    let service = PasswordPolicyService::with_policy(PasswordPolicy::default(), BreachedPasswords::empty());
+    let password = "Xq7!".repeat(20);
+    assert_eq!(rules(service.validate(&password, &personal_info())), vec!["max_length"]);
+}
+
+#[test]
+fn should_limit_the_length_in_characters_and_bytes() {
+    let service = PasswordPolicyService::with_policy(PasswordPolicy::default(), BreachedPasswords::empty());
+    // 42 characters, 72 bytes
+    let password = format!("{}ЖЖЖЖЖЖ", "Жёлтый-Ёж-7!".repeat(3));
+    assert_eq!(password.len(), 72);
+    assert_eq!(rules(service.validate(&password, &personal_info())), Vec::<String>::new());
+
+    // 72 characters but 144 bytes, bcrypt would only use the first half
+    let password = "Жёлтый-Ёж-7!".repeat(6);
+    assert_eq!(rules(service.validate(&password, &personal_info())), vec!["max_bytes"]);
+}
+
+#[test]
+fn should_reject_personal_info() {
+    let service = PasswordPolicyService::with_policy(PasswordPolicy::default(), BreachedPasswords::empty());
+    let violated = rules(service.validate("Jane.Doe#Rocks!2024", &personal_info()));
+    assert!(violated.contains(&String::from("personal_info")));
+}
+
+#[test]
+fn should_reject_breached_password() {
+    let mut file = std::env::temp_dir();
+    file.push(format!("breached-{}.txt", uuid::Uuid::new_v4()));
+    let mut handle = std::fs::File::create(&file).unwrap();
+    writeln!(handle, "{}:42", sha1_hex("violet-kettle-orbit")).unwrap();
+    writeln!(handle, "not a hash").unwrap();
+
+    let breached = BreachedPasswords::load(&file).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert_eq!(breached.len(), 1);
+
+    let service = PasswordPolicyService::with_policy(PasswordPolicy::default(), breached);
+    assert_eq!(rules(service.validate("violet-kettle-orbit", &personal_info())), vec!["breached"]);
+    assert!(service.validate("violet-kettle-orbits", &personal_info()).is_ok());
+}
+
+#[test]
+fn should_score_weak_passwords_low() {
+    assert_eq!(estimate_strength("password", &[]), 0);
+    assert_eq!(estimate_strength("12345678", &[]), 0);
+    assert_eq!(estimate_strength("aaaaaaaaaaaa", &[]), 0);
+    assert!(estimate_strength("P@ssw0rd", &[]) < 2);
+    assert!(estimate_strength("janedoe1990", &["janedoe"]) < 2);
+    assert_eq!(estimate_strength("correct horse battery staple", &[]), 4);
+}
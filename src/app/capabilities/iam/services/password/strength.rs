@@ -0,0 +1,196 @@
+//! Password strength estimation in the spirit of zxcvbn.
+//!
+//! The password is split into the cheapest sequence of patterns (dictionary words,
+//! repeats, sequences, years and brute force characters) and the number of guesses an
+//! attacker needs is estimated from it. The estimate is mapped to zxcvbn's 0-4 score.
+
+use std::collections::HashMap;
+
+/// Passwords longer than this are scored on their prefix only
+const MAX_ANALYSED_LENGTH: usize = 100;
+
+/// Most common passwords and password fragments, ordered by popularity
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "123456", "12345678", "qwerty", "123456789", "12345", "1234", "111111",
+    "1234567", "dragon", "123123", "baseball", "abc123", "football", "monkey", "letmein",
+    "shadow", "master", "696969", "mustang", "666666", "qwertyuiop", "123321", "1234567890",
+    "michael", "654321", "superman", "1qaz2wsx", "7777777", "121212", "000000", "qazwsx",
+    "123qwe", "killer", "trustno1", "jordan", "jennifer", "zxcvbnm", "asdfgh", "hunter",
+    "buster", "soccer", "harley", "batman", "andrew", "tigger", "sunshine", "iloveyou",
+    "whatever", "2000", "charlie", "robert", "thomas", "hockey", "ranger", "daniel",
+    "starwars", "klaster", "112233", "george", "computer", "michelle", "jessica", "pepper",
+    "1111", "zxcvbn", "555555", "11111111", "131313", "freedom", "777777", "pass",
+    "maggie", "159753", "aaaaaa", "ginger", "princess", "joshua", "cheese", "amanda",
+    "summer", "love", "ashley", "nicole", "chelsea", "biteme", "matthew", "access",
+    "yankees", "987654321", "dallas", "austin", "thunder", "taylor", "matrix", "admin",
+    "welcome", "login", "passw0rd", "secret", "qwerty123", "changeme", "default", "guest",
+    "root", "test", "user", "letmein1", "monkey1", "dragon1", "football1", "asdf",
+    "asdfghjkl", "qwer", "zaq12wsx", "hello", "flower", "lovely", "angel", "winter",
+    "spring", "autumn", "company", "server", "database", "secure", "security",
+];
+
+/// Lowest log10 guesses charged for any segment, so chaining cheap patterns still costs something
+const MIN_SEGMENT_LOG10_GUESSES: f64 = 1.0;
+
+/// zxcvbn score thresholds in log10 guesses
+const SCORE_THRESHOLDS: [f64; 4] = [3.0, 6.0, 8.0, 10.0];
+
+/// Estimate the strength of a password on a 0 (trivial) to 4 (very strong) scale.
+///
+/// `user_inputs` holds values tied to the account (name, email) which are treated
+/// as the most likely dictionary words.
+pub fn estimate_strength(password: &str, user_inputs: &[&str]) -> u8 {
+    let log10_guesses = estimate_log10_guesses(password, user_inputs);
+    SCORE_THRESHOLDS
+        .iter()
+        .take_while(|threshold| log10_guesses >= **threshold)
+        .count() as u8
+}
+
+/// Estimate the log10 of the number of guesses needed to crack the password
+pub fn estimate_log10_guesses(password: &str, user_inputs: &[&str]) -> f64 {
+    let chars: Vec<char> = password.chars().take(MAX_ANALYSED_LENGTH).collect();
+    if chars.is_empty() {
+        return 0.0;
+    }
+    let dictionary = build_dictionary(user_inputs);
+    let n = chars.len();
+
+    // best[i] is the cheapest log10 guesses for chars[..i]
+    let mut best = vec![f64::INFINITY; n + 1];
+    best[0] = 0.0;
+    for end in 1..=n {
+        for start in 0..end {
+            if best[start].is_infinite() {
+                continue;
+            }
+            let segment = &chars[start..end];
+            let cost = best[start] + segment_log10_guesses(segment, &dictionary);
+            if cost < best[end] {
+                best[end] = cost;
+            }
+        }
+    }
+    best[n]
+}
+
+fn build_dictionary(user_inputs: &[&str]) -> HashMap<String, usize> {
+    let mut dictionary: HashMap<String, usize> = COMMON_PASSWORDS
+        .iter()
+        .enumerate()
+        .map(|(rank, word)| (word.to_string(), rank + 1))
+        .collect();
+    for input in user_inputs {
+        let input = input.trim().to_lowercase();
+        if input.chars().count() >= 3 {
+            dictionary.insert(input, 1);
+        }
+    }
+    dictionary
+}
+
+/// Cheapest way to guess one segment, in log10
+fn segment_log10_guesses(segment: &[char], dictionary: &HashMap<String, usize>) -> f64 {
+    let mut guesses = brute_force_log10(segment);
+    if segment.len() >= 3 {
+        if let Some(g) = dictionary_log10(segment, dictionary) {
+            guesses = guesses.min(g);
+        }
+        if let Some(g) = repeat_log10(segment) {
+            guesses = guesses.min(g);
+        }
+        if let Some(g) = sequence_log10(segment) {
+            guesses = guesses.min(g);
+        }
+        if let Some(g) = year_log10(segment) {
+            guesses = guesses.min(g);
+        }
+    }
+    guesses.max(MIN_SEGMENT_LOG10_GUESSES)
+}
+
+fn cardinality(c: char) -> f64 {
+    if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii_lowercase() || c.is_ascii_uppercase() {
+        26.0
+    } else if c.is_ascii() {
+        33.0
+    } else {
+        100.0
+    }
+}
+
+fn brute_force_log10(segment: &[char]) -> f64 {
+    segment.iter().map(|c| cardinality(*c).log10()).sum()
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' | '!' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        _ => c,
+    }
+}
+
+fn dictionary_log10(segment: &[char], dictionary: &HashMap<String, usize>) -> Option<f64> {
+    let lower: String = segment.iter().collect::<String>().to_lowercase();
+    let has_upper = segment.iter().any(|c| c.is_uppercase());
+    let case_factor = if has_upper { 2.0 } else { 1.0 };
+
+    if let Some(rank) = dictionary.get(&lower) {
+        return Some((*rank as f64 * case_factor).log10());
+    }
+    let unleeted: String = lower.chars().map(unleet).collect();
+    if unleeted != lower {
+        if let Some(rank) = dictionary.get(&unleeted) {
+            return Some((*rank as f64 * case_factor * 2.0).log10());
+        }
+    }
+    None
+}
+
+fn repeat_log10(segment: &[char]) -> Option<f64> {
+    let first = segment[0];
+    if segment.iter().all(|c| *c == first) {
+        return Some((cardinality(first) * segment.len() as f64).log10());
+    }
+    None
+}
+
+fn sequence_log10(segment: &[char]) -> Option<f64> {
+    let delta = segment[1] as i64 - segment[0] as i64;
+    if delta.abs() != 1 {
+        return None;
+    }
+    let is_sequence = segment
+        .windows(2)
+        .all(|pair| pair[1] as i64 - pair[0] as i64 == delta);
+    if !is_sequence {
+        return None;
+    }
+    let start = segment[0].to_ascii_lowercase();
+    let start_guesses = if start == 'a' || start == '1' || start == '0' {
+        4.0
+    } else {
+        cardinality(start)
+    };
+    let direction_factor = if delta < 0 { 2.0 } else { 1.0 };
+    Some((start_guesses * segment.len() as f64 * direction_factor).log10())
+}
+
+fn year_log10(segment: &[char]) -> Option<f64> {
+    if segment.len() != 4 || !segment.iter().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let year: String = segment.iter().collect();
+    if year.starts_with("19") || year.starts_with("20") {
+        // roughly the span of years people pick
+        return Some(120f64.log10());
+    }
+    None
+}
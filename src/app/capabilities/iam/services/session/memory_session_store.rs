@@ -0,0 +1,61 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+// the tokio clock, which tests can pause and advance
+use tokio::time::Instant;
+
+use crate::app::capabilities::common::global_model::session_user::SessionUser;
+
+use super::session_store::{SessionFuture, SessionStore};
+
+/// Sessions above which expired ones are dropped
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Sessions of this instance only, lost on restart
+#[derive(Clone, Default)]
+pub struct MemorySessionStore {
+    sessions: Arc<Mutex<HashMap<String, (SessionUser, Instant)>>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn create<'a>(&'a self, key: &'a str, session_user: &'a SessionUser, ttl: Duration) -> SessionFuture<'a, ()> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.len() >= PRUNE_THRESHOLD {
+            sessions.retain(|_, (_, expires_at)| *expires_at > now);
+        }
+        sessions.insert(key.to_string(), (session_user.clone(), now + ttl));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn touch<'a>(&'a self, key: &'a str, ttl: Duration) -> SessionFuture<'a, Option<SessionUser>> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        let session_user = match sessions.get_mut(key) {
+            Some((session_user, expires_at)) if *expires_at > now => {
+                *expires_at = now + ttl;
+                Some(session_user.clone())
+            }
+            Some(_) => {
+                sessions.remove(key);
+                None
+            }
+            None => None,
+        };
+        Box::pin(async { Ok(session_user) })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> SessionFuture<'a, ()> {
+        self.sessions.lock().unwrap().remove(key);
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -0,0 +1,7 @@
+pub mod memory_session_store;
+pub mod postgres_session_store;
+pub mod session_service;
+pub mod session_store;
+
+#[cfg(test)]
+mod session_service_test;
@@ -0,0 +1,90 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use migration::sea_orm;
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+
+use crate::app::capabilities::common::{database::db_router::DbRouter, global_model::session_user::SessionUser};
+
+use super::session_store::{SessionFuture, SessionStore};
+
+/// Calls between two deletions of expired sessions
+const PURGE_EVERY: u64 = 1_000;
+
+const CREATE: &str = r#"
+INSERT INTO sessions (id, user_pid, session_user, expires_at)
+VALUES ($1, $2, $3::jsonb, now() + make_interval(secs => $4))
+"#;
+
+const TOUCH: &str = r#"
+UPDATE sessions SET expires_at = now() + make_interval(secs => $2)
+WHERE id = $1 AND expires_at > now()
+RETURNING session_user::text AS session_user
+"#;
+
+const DELETE: &str = "DELETE FROM sessions WHERE id = $1";
+
+const PURGE: &str = "DELETE FROM sessions WHERE expires_at < now()";
+
+/// Sessions in the `sessions` table, shared by every instance and revoked by deleting the row
+#[derive(Clone)]
+pub struct PostgresSessionStore {
+    db: DbRouter,
+    calls: Arc<AtomicU64>,
+}
+
+impl PostgresSessionStore {
+    pub fn new(db: DbRouter) -> Self {
+        Self {
+            db,
+            calls: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl SessionStore for PostgresSessionStore {
+    fn create<'a>(&'a self, key: &'a str, session_user: &'a SessionUser, ttl: Duration) -> SessionFuture<'a, ()> {
+        Box::pin(async move {
+            let db = self.db.writer();
+            if self.calls.fetch_add(1, Ordering::Relaxed) % PURGE_EVERY == PURGE_EVERY - 1 {
+                if let Err(e) = db.execute(Statement::from_string(DbBackend::Postgres, PURGE)).await {
+                    tracing::warn!("Failed to purge sessions: {}", e);
+                }
+            }
+            let json = serde_json::to_string(session_user).map_err(|e| e.to_string())?;
+            let statement = Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                CREATE,
+                [key.into(), session_user.pid.into(), json.into(), ttl.as_secs_f64().into()],
+            );
+            db.execute(statement).await.map_err(|e| e.to_string())?;
+            Ok(())
+        })
+    }
+
+    fn touch<'a>(&'a self, key: &'a str, ttl: Duration) -> SessionFuture<'a, Option<SessionUser>> {
+        Box::pin(async move {
+            // an update, so it has to run on the primary even though it's a lookup
+            let statement =
+                Statement::from_sql_and_values(DbBackend::Postgres, TOUCH, [key.into(), ttl.as_secs_f64().into()]);
+            let Some(row) = self.db.writer().query_one(statement).await.map_err(|e| e.to_string())? else {
+                return Ok(None);
+            };
+            let json: String = row.try_get("", "session_user").map_err(|e| e.to_string())?;
+            serde_json::from_str(&json).map(Some).map_err(|e| e.to_string())
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> SessionFuture<'a, ()> {
+        Box::pin(async move {
+            let statement = Statement::from_sql_and_values(DbBackend::Postgres, DELETE, [key.into()]);
+            self.db.writer().execute(statement).await.map_err(|e| e.to_string())?;
+            Ok(())
+        })
+    }
+}
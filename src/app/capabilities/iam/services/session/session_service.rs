@@ -0,0 +1,63 @@
+use std::{sync::Arc, time::Duration};
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::app::capabilities::{
+    common::global_model::session_user::SessionUser, iam::enums::auth_error::AuthError,
+};
+
+use super::session_store::SessionStore;
+
+/// Opaque session ids handed out as bearer tokens, only their hash is stored
+#[derive(Clone)]
+pub struct SessionService {
+    store: Arc<dyn SessionStore>,
+    idle_timeout: Duration,
+}
+
+impl SessionService {
+    pub fn new(store: Arc<dyn SessionStore>, idle_timeout: Duration) -> Self {
+        Self { store, idle_timeout }
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Start a session, returns its id
+    pub async fn create(&self, session_user: &SessionUser) -> Result<String, AuthError> {
+        let id = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        self.store
+            .create(&key(&id), session_user, self.idle_timeout)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to store session: {}", e);
+                AuthError::InternalServerError
+            })?;
+        Ok(id)
+    }
+
+    /// User of a live session, which is kept alive for another idle timeout
+    pub async fn verify(&self, id: &str) -> Result<SessionUser, AuthError> {
+        match self.store.touch(&key(id), self.idle_timeout).await {
+            Ok(Some(session_user)) => Ok(session_user),
+            Ok(None) => Err(AuthError::JWTVerificationError),
+            Err(e) => {
+                tracing::error!("Failed to load session: {}", e);
+                Err(AuthError::InternalServerError)
+            }
+        }
+    }
+
+    pub async fn revoke(&self, id: &str) -> Result<(), AuthError> {
+        self.store.delete(&key(id)).await.map_err(|e| {
+            tracing::error!("Failed to delete session: {}", e);
+            AuthError::InternalServerError
+        })
+    }
+}
+
+fn key(id: &str) -> String {
+    format!("{:x}", Sha256::digest(id.as_bytes()))
+}
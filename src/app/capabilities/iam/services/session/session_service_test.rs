@@ -0,0 +1,45 @@
+use std::{sync::Arc, time::Duration};
+
+use uuid::Uuid;
+
+use super::{memory_session_store::MemorySessionStore, session_service::SessionService};
+
+use crate::app::capabilities::{
+    common::global_model::session_user::SessionUser, iam::enums::auth_error::AuthError,
+};
+
+fn session_user() -> SessionUser {
+    SessionUser {
+        pid: Uuid::new_v4(),
+        first_name: String::from("Jane"),
+        last_name: String::from("Doe"),
+        email: String::from("jane@example.com"),
+    }
+}
+
+#[tokio::test]
+async fn should_verify_and_revoke_sessions() {
+    let sessions = SessionService::new(Arc::new(MemorySessionStore::new()), Duration::from_secs(60));
+    let user = session_user();
+
+    let id = sessions.create(&user).await.unwrap();
+    assert_eq!(id.len(), 64);
+    assert_eq!(sessions.verify(&id).await.unwrap().pid, user.pid);
+    assert_eq!(sessions.verify("unknown").await.unwrap_err(), AuthError::JWTVerificationError);
+
+    sessions.revoke(&id).await.unwrap();
+    assert_eq!(sessions.verify(&id).await.unwrap_err(), AuthError::JWTVerificationError);
+}
+
+#[tokio::test(start_paused = true)]
+async fn should_slide_the_expiration_of_used_sessions() {
+    let sessions = SessionService::new(Arc::new(MemorySessionStore::new()), Duration::from_millis(100));
+    let id = sessions.create(&session_user()).await.unwrap();
+
+    for _ in 0..3 {
+        tokio::time::advance(Duration::from_millis(60)).await;
+        assert!(sessions.verify(&id).await.is_ok());
+    }
+    tokio::time::advance(Duration::from_millis(150)).await;
+    assert!(sessions.verify(&id).await.is_err());
+}
@@ -0,0 +1,17 @@
+use std::{future::Future, pin::Pin, time::Duration};
+
+use crate::app::capabilities::common::global_model::session_user::SessionUser;
+
+pub type SessionFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, String>> + Send + 'a>>;
+
+/// Storage of server-side sessions, keyed by the SHA-256 of the opaque session id.
+///
+/// Expiration slides: every successful `touch` moves it `ttl` into the future.
+pub trait SessionStore: Send + Sync {
+    fn create<'a>(&'a self, key: &'a str, session_user: &'a SessionUser, ttl: Duration) -> SessionFuture<'a, ()>;
+
+    /// User of an unexpired session, extending it by `ttl`
+    fn touch<'a>(&'a self, key: &'a str, ttl: Duration) -> SessionFuture<'a, Option<SessionUser>>;
+
+    fn delete<'a>(&'a self, key: &'a str) -> SessionFuture<'a, ()>;
+}
@@ -0,0 +1,4 @@
+pub mod session_cookie_service;
+
+#[cfg(test)]
+mod session_cookie_service_test;
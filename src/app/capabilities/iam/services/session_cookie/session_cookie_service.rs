@@ -0,0 +1,117 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use poem::{
+    http::{header, HeaderMap, Method},
+    web::cookie::{Cookie, CookieJar, CookieKey, SameSite as CookieSameSite},
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::app::capabilities::{
+    common::config::config_service::ConfigService,
+    iam::config::{SameSite, SessionCookieConfig},
+};
+
+/// Encrypted cookie holding the session token, not readable by scripts
+pub const SESSION_COOKIE: &str = "session";
+/// Readable cookie holding the CSRF token, echoed by the front-end in `CSRF_HEADER`
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Keeps session tokens in an encrypted HttpOnly cookie and checks double-submit CSRF tokens
+#[derive(Clone)]
+pub struct SessionCookieService {
+    config: SessionCookieConfig,
+    key: Arc<CookieKey>,
+}
+
+impl SessionCookieService {
+    /// The cookie key is derived from the JWT secret, rotating it signs everyone out
+    pub fn new(config: &ConfigService) -> Self {
+        let secret = Sha256::digest(config.iam().jwt_secret.expose().as_bytes());
+        Self {
+            config: config.iam().session_cookie.clone(),
+            key: Arc::new(CookieKey::derive_from(&secret)),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Set the session and CSRF cookies, returns the CSRF token
+    pub fn start(&self, jar: &CookieJar, token: &str, duration: Duration) -> String {
+        let csrf_token = Uuid::new_v4().simple().to_string();
+        self.set(jar, token, &csrf_token, duration);
+        csrf_token
+    }
+
+    /// Set both cookies again to expire in `duration`, keeping the CSRF token of the request.
+    /// Used when a store-backed session slides, so the cookie lives as long as the session.
+    pub fn refresh(&self, jar: &CookieJar, headers: &HeaderMap, token: &str, duration: Duration) {
+        if let Some(csrf) = request_cookies(headers).get(CSRF_COOKIE) {
+            self.set(jar, token, csrf.value_str(), duration);
+        }
+    }
+
+    fn set(&self, jar: &CookieJar, token: &str, csrf_token: &str, duration: Duration) {
+        let mut session = self.cookie(SESSION_COOKIE, token, duration);
+        session.set_http_only(true);
+        jar.private_with_key(&self.key).add(session);
+        jar.add(self.cookie(CSRF_COOKIE, csrf_token, duration));
+    }
+
+    /// Expire both cookies
+    pub fn end(&self, jar: &CookieJar) {
+        for name in [SESSION_COOKIE, CSRF_COOKIE] {
+            let mut cookie = self.cookie(name, "", Duration::ZERO);
+            cookie.make_removal();
+            jar.add(cookie);
+        }
+    }
+
+    /// Decrypted session token of a request, `None` if missing or tampered with
+    pub fn token(&self, headers: &HeaderMap) -> Option<String> {
+        if !self.config.enabled {
+            return None;
+        }
+        let jar = request_cookies(headers);
+        let cookie = jar.private_with_key(&self.key).get(SESSION_COOKIE)?;
+        Some(cookie.value_str().to_string())
+    }
+
+    /// Safe methods pass, others need `CSRF_HEADER` to match the CSRF cookie
+    pub fn verify_csrf(&self, method: &Method, headers: &HeaderMap) -> bool {
+        if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            return true;
+        }
+        let Some(cookie) = request_cookies(headers).get(CSRF_COOKIE) else {
+            return false;
+        };
+        let header = headers.get(CSRF_HEADER).and_then(|value| value.to_str().ok());
+        header.is_some_and(|header| !header.is_empty() && header == cookie.value_str())
+    }
+
+    fn cookie(&self, name: &str, value: &str, duration: Duration) -> Cookie {
+        let mut cookie = Cookie::new_with_str(name, value);
+        cookie.set_path("/");
+        cookie.set_secure(self.config.secure);
+        cookie.set_same_site(match self.config.same_site {
+            SameSite::Strict => CookieSameSite::Strict,
+            SameSite::Lax => CookieSameSite::Lax,
+        });
+        cookie.set_max_age(duration);
+        cookie
+    }
+}
+
+/// Cookies sent with a request, readable outside of the `CookieJarManager`
+fn request_cookies(headers: &HeaderMap) -> CookieJar {
+    let cookies: Vec<&str> = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    let Ok(jar) = CookieJar::from_str(&cookies.join("; "));
+    jar
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use poem::{
+    http::{header, HeaderMap, HeaderValue, Method},
+    web::cookie::CookieJar,
+};
+
+use super::session_cookie_service::*;
+
+use crate::app::capabilities::common::config::{app_config::AppConfig, config_service::ConfigService, secret::Secret};
+
+fn service(enabled: bool) -> SessionCookieService {
+    let mut config = AppConfig::default();
+    config.database.url = Secret::new(String::from("postgres://localhost/test"));
+    config.iam.jwt_secret = Secret::new(String::from("TEST_SECRET"));
+    config.iam.session_cookie.enabled = enabled;
+    SessionCookieService::new(&ConfigService::from_config(config).unwrap())
+}
+
+/// Request headers sending back the cookies set in `jar`
+fn cookie_headers(jar: &CookieJar, csrf_header: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let session = jar.get(SESSION_COOKIE).unwrap();
+    let csrf = jar.get(CSRF_COOKIE).unwrap();
+    let cookies = format!("{}={}; {}={}", SESSION_COOKIE, session.value_str(), CSRF_COOKIE, csrf.value_str());
+    headers.insert(header::COOKIE, HeaderValue::from_str(&cookies).unwrap());
+    if let Some(csrf_header) = csrf_header {
+        headers.insert(CSRF_HEADER, HeaderValue::from_str(csrf_header).unwrap());
+    }
+    headers
+}
+
+#[test]
+fn should_keep_the_token_in_an_encrypted_cookie() {
+    let service = service(true);
+    let jar = CookieJar::default();
+    service.start(&jar, "jwt", Duration::from_secs(60));
+
+    let session = jar.get(SESSION_COOKIE).unwrap();
+    assert_ne!(session.value_str(), "jwt");
+    assert!(session.http_only());
+    assert!(session.secure());
+    assert_eq!(service.token(&cookie_headers(&jar, None)), Some(String::from("jwt")));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::COOKIE, HeaderValue::from_static("session=jwt"));
+    assert_eq!(service.token(&headers), None);
+}
+
+#[test]
+fn should_ignore_cookies_when_disabled() {
+    let jar = CookieJar::default();
+    service(true).start(&jar, "jwt", Duration::from_secs(60));
+    assert_eq!(service(false).token(&cookie_headers(&jar, None)), None);
+}
+
+#[test]
+fn should_require_the_csrf_token_on_state_changing_requests() {
+    let service = service(true);
+    let jar = CookieJar::default();
+    let csrf_token = service.start(&jar, "jwt", Duration::from_secs(60));
+    assert!(!jar.get(CSRF_COOKIE).unwrap().http_only());
+
+    assert!(service.verify_csrf(&Method::GET, &cookie_headers(&jar, None)));
+    assert!(service.verify_csrf(&Method::PUT, &cookie_headers(&jar, Some(&csrf_token))));
+    assert!(!service.verify_csrf(&Method::PUT, &cookie_headers(&jar, None)));
+    assert!(!service.verify_csrf(&Method::DELETE, &cookie_headers(&jar, Some("forged"))));
+}
+
+#[test]
+fn should_refresh_both_cookies_with_the_same_csrf_token() {
+    let service = service(true);
+    let jar = CookieJar::default();
+    let csrf_token = service.start(&jar, "session-id", Duration::from_secs(60));
+
+    let refreshed = CookieJar::default();
+    service.refresh(&refreshed, &cookie_headers(&jar, None), "session-id", Duration::from_secs(600));
+    assert_eq!(refreshed.get(CSRF_COOKIE).unwrap().value_str(), csrf_token);
+    assert_eq!(refreshed.get(CSRF_COOKIE).unwrap().max_age(), Some(Duration::from_secs(600)));
+    assert_eq!(service.token(&cookie_headers(&refreshed, None)), Some(String::from("session-id")));
+}
@@ -1 +1,3 @@
-pub mod users;
\ No newline at end of file
+pub mod user_repository;
+#[allow(clippy::module_inception)]
+pub mod users;
@@ -0,0 +1,90 @@
+use migration::sea_orm;
+use sea_orm::{prelude::async_trait::async_trait, prelude::Uuid, DbErr};
+
+use super::super::super::entities::users::Model as UserModel;
+use super::users::UserService;
+use crate::app::capabilities::common::database::{db_router::DbRouter, unit_of_work::UnitOfWork};
+
+/// A user to insert, `password` is already hashed
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewUser {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub password: String,
+    pub locale: String,
+}
+
+/// Storage of users as seen by `IAMService`.
+///
+/// Lookups return the password hash so it can be checked, writes return the user without it.
+/// Writes that record events run in the caller's unit of work, so they commit with its other writes.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    /// Unit of work on the users' database, for the checks and writes that commit together
+    async fn begin(&self) -> Result<UnitOfWork, DbErr>;
+
+    async fn find_by_email(&self, email: String) -> Result<Option<UserModel>, DbErr>;
+
+    /// Lookup in `uow` that sees the latest writes, for uniqueness checks
+    async fn find_by_email_for_write(&self, uow: &UnitOfWork, email: String) -> Result<Option<UserModel>, DbErr>;
+
+    async fn find_by_pid(&self, pid: Uuid) -> Result<Option<UserModel>, DbErr>;
+
+    /// Insert the user with its `UserRegistered` event, a taken email fails with a unique violation
+    async fn create(&self, uow: &UnitOfWork, user: NewUser) -> Result<UserModel, DbErr>;
+
+    /// Replace the password hash, with its `PasswordChanged` event
+    async fn update_password(&self, uow: &UnitOfWork, user: UserModel, password: String) -> Result<UserModel, DbErr>;
+
+    async fn update_locale(&self, user: UserModel, locale: String) -> Result<UserModel, DbErr>;
+}
+
+/// `UserRepository` on the application database
+pub struct SeaOrmUserRepository {
+    db: DbRouter,
+    users: UserService,
+}
+
+impl SeaOrmUserRepository {
+    pub fn new(db: DbRouter) -> Self {
+        Self {
+            users: UserService::new(db.clone()),
+            db,
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for SeaOrmUserRepository {
+    async fn begin(&self) -> Result<UnitOfWork, DbErr> {
+        UnitOfWork::begin(&self.db).await
+    }
+
+    async fn find_by_email(&self, email: String) -> Result<Option<UserModel>, DbErr> {
+        self.users.find_user_by_email(email).await
+    }
+
+    async fn find_by_email_for_write(&self, uow: &UnitOfWork, email: String) -> Result<Option<UserModel>, DbErr> {
+        self.users.find_user_by_email_for_write(uow.conn(), email).await
+    }
+
+    async fn find_by_pid(&self, pid: Uuid) -> Result<Option<UserModel>, DbErr> {
+        self.users.find_user_by_pid(pid).await
+    }
+
+    async fn create(&self, uow: &UnitOfWork, user: NewUser) -> Result<UserModel, DbErr> {
+        self.users
+            .create_user(uow.conn(), user.email, user.first_name, user.last_name, user.password, user.locale)
+            .await
+    }
+
+    async fn update_password(&self, uow: &UnitOfWork, user: UserModel, password: String) -> Result<UserModel, DbErr> {
+        self.users.update_password(uow.conn(), user, password).await
+    }
+
+    async fn update_locale(&self, user: UserModel, locale: String) -> Result<UserModel, DbErr> {
+        self.users.update_locale(self.db.writer(), user, locale).await
+    }
+}
@@ -2,49 +2,82 @@ use super::super::super::*;
 use entities::users::{self, Entity as User};
 use migration::sea_orm;
 use sea_orm::prelude::Uuid;
-use sea_orm::{ DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter, Set};
 use sea_orm::ColumnTrait;
+use crate::app::capabilities::common::{database::db_router::DbRouter, events::outbox};
+use events::{PasswordChanged, UserRegistered};
 
 #[derive(Clone)]
 pub struct UserService {
-    db: DatabaseConnection
+    db: DbRouter
 }
 
 impl UserService {
-    pub fn new(db: DatabaseConnection) -> Self {
+    pub fn new(db: DbRouter) -> Self {
         Self { db }
     }
+    /// Read-only lookup, served by a read replica when configured
     pub async fn find_user_by_email(&self, email: String) -> Result<Option<users::Model>, DbErr> {
-        User::find().filter(users::Column::Email.eq(email)).one(&self.db).await
+        User::find().filter(users::Column::Email.eq(email)).one(self.db.reader()).await
     }
 
-    pub async fn create_user(&self, email: String, first_name: String, last_name: String, password: String) -> Result<Option<users::Model>, DbErr> {
-        let existing_user_result = &self.find_user_by_email(email.clone()).await;
-        match existing_user_result {
-            Ok(Some(_)) => return Err(DbErr::Custom(String::from("Already Exists"))),
-            _ => (),
-        }
-
-            let user = users::ActiveModel {
-                email: Set(email.to_string()),
-                first_name: Set(first_name.to_string()),
-                last_name: Set(last_name.to_string()),
-                password: Set(Some(password.to_string())),
-                pid: Set(Uuid::new_v4()),
-                ..Default::default()
-            };
-
-            let res = User::insert(user).exec_with_returning(&self.db).await;
-            match res {
-                Ok(mut user) => {
-                    user.password = None;
-                    return Ok(Some(user))
-                },
-                Err(e) => return Err(e),
-            }
+    /// Lookup on `conn`, for checks that must see the latest writes of a unit of work
+    pub async fn find_user_by_email_for_write<C: ConnectionTrait>(&self, conn: &C, email: String) -> Result<Option<users::Model>, DbErr> {
+        User::find().filter(users::Column::Email.eq(email)).one(conn).await
     }
 
+    /// Insert the user with its `UserRegistered` event, a taken email fails with a unique violation
+    pub async fn create_user<C: ConnectionTrait>(&self, conn: &C, email: String, first_name: String, last_name: String, password: String, locale: String) -> Result<users::Model, DbErr> {
+        let user = users::ActiveModel {
+            email: Set(email.to_string()),
+            first_name: Set(first_name.to_string()),
+            last_name: Set(last_name.to_string()),
+            password: Set(Some(password.to_string())),
+            locale: Set(locale),
+            pid: Set(Uuid::new_v4()),
+            ..Default::default()
+        };
+
+        let mut user = User::insert(user).exec_with_returning(conn).await?;
+        outbox::record(conn, &UserRegistered {
+            pid: user.pid,
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+            locale: user.locale.clone(),
+        }).await?;
+        user.password = None;
+        Ok(user)
+    }
+
+    /// Read-only lookup, served by a read replica when configured
     pub async fn find_user_by_pid(&self, pid: Uuid) -> Result<Option<users::Model>, DbErr> {
-        User::find().filter(users::Column::Pid.eq(pid)).one(&self.db).await
+        User::find().filter(users::Column::Pid.eq(pid)).one(self.db.reader()).await
+    }
+
+    /// Replace the stored password hash of a user, with its `PasswordChanged` event
+    pub async fn update_password<C: ConnectionTrait>(&self, conn: &C, user: users::Model, password: String) -> Result<users::Model, DbErr> {
+        let mut user = user.into_active_model();
+        user.password = Set(Some(password));
+        user.updated_at = Set(chrono::Utc::now().naive_utc());
+        let mut user = user.update(conn).await?;
+        outbox::record(conn, &PasswordChanged {
+            pid: user.pid,
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            locale: user.locale.clone(),
+        }).await?;
+        user.password = None;
+        Ok(user)
+    }
+
+    /// Change the preferred locale of a user
+    pub async fn update_locale<C: ConnectionTrait>(&self, conn: &C, user: users::Model, locale: String) -> Result<users::Model, DbErr> {
+        let mut user = user.into_active_model();
+        user.locale = Set(locale);
+        user.updated_at = Set(chrono::Utc::now().naive_utc());
+        let mut user = user.update(conn).await?;
+        user.password = None;
+        Ok(user)
     }
-}
\ No newline at end of file
+}
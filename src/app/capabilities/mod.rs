@@ -1,2 +1,3 @@
 pub mod iam;
 pub mod common;
+pub mod webhooks;
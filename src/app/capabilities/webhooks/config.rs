@@ -0,0 +1,45 @@
+use serde::Deserialize;
+
+/// Config of the webhooks capability
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhooksConfig {
+    /// Reject endpoint URLs that are not `https://`
+    pub require_https: bool,
+    /// Accept endpoints on loopback, link-local and private addresses, for local development
+    pub allow_private_networks: bool,
+    /// Wait for a receiver's response before the attempt fails
+    pub timeout_secs: u64,
+    /// Attempts per event and endpoint, retried with the `jobs` backoff
+    pub max_attempts: i32,
+    /// Failed attempts in a row, across events, after which an endpoint is disabled
+    pub disable_after_failures: i32,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        WebhooksConfig {
+            require_https: false,
+            allow_private_networks: false,
+            timeout_secs: 10,
+            max_attempts: 8,
+            disable_after_failures: 20,
+        }
+    }
+}
+
+impl WebhooksConfig {
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = vec![];
+        if self.timeout_secs == 0 {
+            errors.push(String::from("timeout_secs: must be greater than 0"));
+        }
+        if self.max_attempts < 1 {
+            errors.push(String::from("max_attempts: must be at least 1"));
+        }
+        if self.disable_after_failures < 1 {
+            errors.push(String::from("disable_after_failures: must be at least 1"));
+        }
+        errors
+    }
+}
@@ -0,0 +1,191 @@
+use poem::{http::StatusCode, web::Data};
+use poem_openapi::{param::Path, payload::Json, ApiResponse, OpenApi, Tags};
+use uuid::Uuid;
+
+use crate::app::capabilities::{
+    common::{global_model::app_state::AppState, problem::problem_details::ProblemDetails},
+    iam::controllers::users::users_controller::UserAuth,
+    webhooks::models::webhook::{
+        CreatedWebhookEndpoint, UpdateWebhookEndpoint, WebhookDelivery, WebhookEndpoint, WebhookEndpointInput,
+    },
+};
+
+#[derive(ApiResponse)]
+pub enum CreateWebhookResponse {
+    #[oai(status = 201)]
+    Created(Json<CreatedWebhookEndpoint>),
+    /// `validation_failed` for an invalid URL or unknown event types
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for CreateWebhookResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        CreateWebhookResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum ListWebhooksResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<WebhookEndpoint>>),
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for ListWebhooksResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        ListWebhooksResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum UpdateWebhookResponse {
+    #[oai(status = 200)]
+    Ok(Json<WebhookEndpoint>),
+    /// `not_found`, or `validation_failed` for an invalid URL or unknown event types
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for UpdateWebhookResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        UpdateWebhookResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum DeleteWebhookResponse {
+    #[oai(status = 204)]
+    NoContent,
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for DeleteWebhookResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        DeleteWebhookResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum WebhookDeliveriesResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<WebhookDelivery>>),
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for WebhookDeliveriesResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        WebhookDeliveriesResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum TestWebhookResponse {
+    /// The logged attempt, `success` is false when the endpoint failed
+    #[oai(status = 200)]
+    Ok(Json<WebhookDelivery>),
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for TestWebhookResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        TestWebhookResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(Tags)]
+enum ApiTags {
+    /// Endpoints notified of the events of the logged in user's tenant
+    Webhooks,
+}
+
+#[derive(Default)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct API;
+
+#[OpenApi]
+impl API {
+    /// Register an endpoint, the response holds its signing secret which isn't shown again
+    #[oai(path = "/webhooks", method = "post", tag = "ApiTags::Webhooks", operation_id = "createWebhook")]
+    pub async fn create_webhook(
+        &self,
+        state: Data<&AppState>,
+        auth: UserAuth,
+        payload: Json<WebhookEndpointInput>,
+    ) -> CreateWebhookResponse {
+        let payload = payload.0;
+        match state
+            .services
+            .webhooks
+            .create(auth.session_user().pid, payload.url, payload.event_types)
+            .await
+        {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(endpoint) => CreateWebhookResponse::Created(Json(endpoint)),
+        }
+    }
+
+    #[oai(path = "/webhooks", method = "get", tag = "ApiTags::Webhooks", operation_id = "listWebhooks")]
+    pub async fn list_webhooks(&self, state: Data<&AppState>, auth: UserAuth) -> ListWebhooksResponse {
+        match state.services.webhooks.list(auth.session_user().pid).await {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(endpoints) => ListWebhooksResponse::Ok(Json(endpoints)),
+        }
+    }
+
+    /// Change an endpoint, or enable it again after it was disabled
+    #[oai(path = "/webhooks/:id", method = "put", tag = "ApiTags::Webhooks", operation_id = "updateWebhook")]
+    pub async fn update_webhook(
+        &self,
+        state: Data<&AppState>,
+        auth: UserAuth,
+        id: Path<Uuid>,
+        payload: Json<UpdateWebhookEndpoint>,
+    ) -> UpdateWebhookResponse {
+        let payload = payload.0;
+        match state
+            .services
+            .webhooks
+            .update(auth.session_user().pid, id.0, payload.url, payload.event_types, payload.enabled)
+            .await
+        {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(endpoint) => UpdateWebhookResponse::Ok(Json(endpoint)),
+        }
+    }
+
+    #[oai(path = "/webhooks/:id", method = "delete", tag = "ApiTags::Webhooks", operation_id = "deleteWebhook")]
+    pub async fn delete_webhook(&self, state: Data<&AppState>, auth: UserAuth, id: Path<Uuid>) -> DeleteWebhookResponse {
+        match state.services.webhooks.delete(auth.session_user().pid, id.0).await {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(_) => DeleteWebhookResponse::NoContent,
+        }
+    }
+
+    /// Latest delivery attempts to an endpoint, newest first
+    #[oai(
+        path = "/webhooks/:id/deliveries",
+        method = "get",
+        tag = "ApiTags::Webhooks",
+        operation_id = "listWebhookDeliveries"
+    )]
+    pub async fn list_deliveries(&self, state: Data<&AppState>, auth: UserAuth, id: Path<Uuid>) -> WebhookDeliveriesResponse {
+        match state.services.webhooks.deliveries(auth.session_user().pid, id.0).await {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(deliveries) => WebhookDeliveriesResponse::Ok(Json(deliveries)),
+        }
+    }
+
+    /// Post a `webhook.test` event to an endpoint and wait for its response
+    #[oai(path = "/webhooks/:id/test", method = "post", tag = "ApiTags::Webhooks", operation_id = "testWebhook")]
+    pub async fn test_webhook(&self, state: Data<&AppState>, auth: UserAuth, id: Path<Uuid>) -> TestWebhookResponse {
+        match state.services.webhooks.send_test(auth.session_user().pid, id.0).await {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(delivery) => TestWebhookResponse::Ok(Json(delivery)),
+        }
+    }
+}
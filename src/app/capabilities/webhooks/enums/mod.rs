@@ -0,0 +1 @@
+pub mod webhook_error;
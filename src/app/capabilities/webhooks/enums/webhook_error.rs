@@ -0,0 +1,33 @@
+use migration::sea_orm::DbErr;
+use poem::http::StatusCode;
+
+use crate::app::capabilities::common::{
+    global_model::validation_error::ValidationError, problem::problem_details::ProblemDetails,
+};
+
+#[derive(Debug)]
+pub enum WebhookError {
+    /// No endpoint with the id in the caller's tenant
+    NotFound,
+    /// Invalid endpoint fields
+    Validation(Vec<ValidationError>),
+    Database(DbErr),
+}
+
+impl From<DbErr> for WebhookError {
+    fn from(e: DbErr) -> Self {
+        WebhookError::Database(e)
+    }
+}
+
+impl From<WebhookError> for ProblemDetails {
+    fn from(e: WebhookError) -> Self {
+        match e {
+            WebhookError::NotFound => ProblemDetails::new(StatusCode::NOT_FOUND),
+            WebhookError::Validation(errors) => ProblemDetails::new(StatusCode::BAD_REQUEST)
+                .with_code("validation_failed")
+                .with_errors(errors),
+            WebhookError::Database(e) => ProblemDetails::from(e),
+        }
+    }
+}
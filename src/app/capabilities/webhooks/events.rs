@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::capabilities::common::events::domain_event::DomainEvent;
+
+/// Sent by `POST /webhooks/{id}/test`, straight to the endpoint without the outbox
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookTest {
+    pub endpoint_id: Uuid,
+}
+
+impl DomainEvent for WebhookTest {
+    const EVENT_TYPE: &'static str = "webhook.test";
+}
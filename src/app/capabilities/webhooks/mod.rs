@@ -0,0 +1,6 @@
+pub mod config;
+pub mod controllers;
+pub mod enums;
+pub mod events;
+pub mod models;
+pub mod services;
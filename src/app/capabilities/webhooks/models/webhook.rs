@@ -0,0 +1,58 @@
+use poem_openapi::Object;
+use uuid::Uuid;
+
+/// A receiver of events of the tenant
+#[derive(Debug, Object, Clone, PartialEq)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub url: String,
+    /// Event types posted to the endpoint, e.g. `user.registered`
+    pub event_types: Vec<String>,
+    /// Cleared after `webhooks.disable_after_failures` failed attempts in a row
+    pub enabled: bool,
+    pub consecutive_failures: i32,
+    #[oai(skip_serializing_if_is_none)]
+    pub disabled_at: Option<String>,
+    pub created_at: String,
+}
+
+/// A new endpoint with the signing secret, which is only shown once
+#[derive(Debug, Object, Clone, PartialEq)]
+pub struct CreatedWebhookEndpoint {
+    #[oai(flatten)]
+    pub endpoint: WebhookEndpoint,
+    /// HMAC-SHA256 key of the `Webhook-Signature` header
+    pub secret: String,
+}
+
+#[derive(Debug, Object, Clone, PartialEq, Eq)]
+pub struct WebhookEndpointInput {
+    pub url: String,
+    pub event_types: Vec<String>,
+}
+
+#[derive(Debug, Object, Clone, PartialEq, Eq)]
+pub struct UpdateWebhookEndpoint {
+    pub url: String,
+    pub event_types: Vec<String>,
+    /// Enabling a disabled endpoint resets its failure count
+    pub enabled: bool,
+}
+
+/// One attempt to post an event to an endpoint
+#[derive(Debug, Object, Clone, PartialEq)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub event_id: Uuid,
+    pub event_type: String,
+    /// Starts at 1 for each event
+    pub attempt: i32,
+    /// Missing when no response was received
+    #[oai(skip_serializing_if_is_none)]
+    pub status_code: Option<i32>,
+    pub success: bool,
+    #[oai(skip_serializing_if_is_none)]
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: String,
+}
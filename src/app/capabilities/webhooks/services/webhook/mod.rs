@@ -0,0 +1,9 @@
+pub mod webhook_address;
+pub mod webhook_client;
+pub mod webhook_delivery_job;
+pub mod webhook_service;
+pub mod webhook_signature;
+pub mod webhook_subscriber;
+
+#[cfg(test)]
+mod webhooks_test;
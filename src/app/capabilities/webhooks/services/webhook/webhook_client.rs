@@ -0,0 +1,102 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use reqwest::{header::CONTENT_TYPE, redirect::Policy, Client, Url};
+
+use crate::app::capabilities::common::events::domain_event::EventEnvelope;
+
+use super::{
+    webhook_address::{is_internal_url, PublicResolver},
+    webhook_signature::{self, ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+};
+
+/// Result of one POST to an endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryOutcome {
+    pub status_code: Option<u16>,
+    /// Set when the request failed or the response wasn't a 2xx
+    pub error: Option<String>,
+    pub duration: Duration,
+}
+
+impl DeliveryOutcome {
+    pub fn success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Posts signed events
+#[derive(Clone)]
+pub struct WebhookClient {
+    client: Client,
+    allow_private_networks: bool,
+}
+
+impl WebhookClient {
+    /// Unless `allow_private_networks`, endpoints on loopback, link-local or private addresses
+    /// are refused, so endpoints can't be used to probe the server's network
+    pub fn new(timeout: Duration, allow_private_networks: bool) -> Self {
+        let mut builder = Client::builder()
+            .timeout(timeout)
+            // a redirect could point the signed payload anywhere
+            .redirect(Policy::none())
+            .user_agent(concat!("server-webhooks/", env!("CARGO_PKG_VERSION")));
+        if !allow_private_networks {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = builder.build().expect("Failed to build the webhook HTTP client");
+        Self {
+            client,
+            allow_private_networks,
+        }
+    }
+
+    /// POST `event` as JSON to `url`, any 2xx response is a success
+    pub async fn send(&self, url: &str, secret: &str, event: &EventEnvelope) -> DeliveryOutcome {
+        let started = Instant::now();
+        // addresses in the URL skip the resolver
+        if !self.allow_private_networks && Url::parse(url).is_ok_and(|url| is_internal_url(&url)) {
+            return DeliveryOutcome {
+                status_code: None,
+                error: Some(String::from("Endpoint is on a private network address")),
+                duration: started.elapsed(),
+            };
+        }
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(e) => {
+                return DeliveryOutcome {
+                    status_code: None,
+                    error: Some(e.to_string()),
+                    duration: started.elapsed(),
+                }
+            }
+        };
+        let timestamp = chrono::Utc::now().timestamp();
+        let result = self
+            .client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ID_HEADER, event.id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, webhook_signature::sign(secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await;
+        let (status_code, error) = match result {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                Some(format!("Endpoint responded with {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.without_url().to_string())),
+        };
+        DeliveryOutcome {
+            status_code,
+            error,
+            duration: started.elapsed(),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::capabilities::common::{
+    events::domain_event::EventEnvelope,
+    jobs::job_handler::{JobFuture, JobHandler},
+};
+
+use super::webhook_service::WebhookService;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDeliveryPayload {
+    pub endpoint_id: Uuid,
+    pub event: EventEnvelope,
+}
+
+/// Posts one event to one endpoint, retried by the job queue until `webhooks.max_attempts`
+pub struct WebhookDeliveryJob {
+    pub webhooks: WebhookService,
+}
+
+impl JobHandler for WebhookDeliveryJob {
+    const KIND: &'static str = "webhook_delivery";
+    type Payload = WebhookDeliveryPayload;
+
+    fn handle(&self, payload: WebhookDeliveryPayload) -> JobFuture<'_> {
+        Box::pin(async move { self.webhooks.deliver(payload.endpoint_id, &payload.event).await })
+    }
+}
@@ -0,0 +1,384 @@
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use migration::sea_orm;
+use prometheus::IntCounterVec;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, QueryResult, Statement};
+use uuid::Uuid;
+
+use crate::app::capabilities::{
+    common::{
+        config::config_service::ConfigService,
+        database::db_router::DbRouter,
+        events::domain_event::{DomainEvent, EventEnvelope},
+        global_model::validation_error::ValidationError,
+        metrics::metrics_service::MetricsService,
+    },
+    iam::events::{PasswordChanged, UserRegistered},
+    webhooks::{
+        config::WebhooksConfig,
+        enums::webhook_error::WebhookError,
+        events::WebhookTest,
+        models::webhook::{CreatedWebhookEndpoint, WebhookDelivery, WebhookEndpoint},
+    },
+};
+
+use super::{
+    webhook_address::is_internal_url,
+    webhook_client::{DeliveryOutcome, WebhookClient},
+};
+
+/// Event types endpoints can subscribe to
+pub const EVENT_TYPES: &[&str] = &[UserRegistered::EVENT_TYPE, PasswordChanged::EVENT_TYPE];
+
+/// Deliveries listed per endpoint, newest first
+const DELIVERY_LOG_LIMIT: i64 = 50;
+
+const ENDPOINT_COLUMNS: &str = r#"
+id, url, event_types::text AS event_types, enabled, consecutive_failures,
+EXTRACT(EPOCH FROM disabled_at)::float8 AS disabled_at, EXTRACT(EPOCH FROM created_at)::float8 AS created_at
+"#;
+
+const DELIVERY_COLUMNS: &str = r#"
+id, event_id, event_type, attempt, status_code, success, error, duration_ms,
+EXTRACT(EPOCH FROM created_at)::float8 AS created_at
+"#;
+
+const INSERT_ENDPOINT: &str = r#"
+INSERT INTO webhook_endpoints (id, tenant_pid, url, secret, event_types) VALUES ($1, $2, $3, $4, $5::jsonb)
+"#;
+
+const LIST_ENDPOINTS: &str = "FROM webhook_endpoints WHERE tenant_pid = $1 ORDER BY created_at, id";
+
+/// Enabling an endpoint gives it a clean slate
+const UPDATE_ENDPOINT: &str = r#"
+UPDATE webhook_endpoints SET
+    url = $3, event_types = $4::jsonb, enabled = $5,
+    consecutive_failures = CASE WHEN $5 THEN 0 ELSE consecutive_failures END,
+    disabled_at = CASE WHEN $5 THEN NULL ELSE disabled_at END,
+    updated_at = now()
+WHERE id = $1 AND tenant_pid = $2
+"#;
+
+const DELETE_ENDPOINT: &str = "DELETE FROM webhook_endpoints WHERE id = $1 AND tenant_pid = $2";
+
+/// `$1` is the endpoint id and `$2` an optional tenant, the delivery job loads endpoints without one
+const FIND_TARGET: &str = r#"
+SELECT url, secret, enabled FROM webhook_endpoints WHERE id = $1 AND ($2::uuid IS NULL OR tenant_pid = $2)
+"#;
+
+/// Endpoints of the tenant the event is about, and of admins who see every event
+const TARGETS_FOR_EVENT: &str = r#"
+SELECT e.id FROM webhook_endpoints e JOIN users u ON u.pid = e.tenant_pid
+WHERE e.enabled AND e.event_types @> jsonb_build_array($1::text)
+    AND (e.tenant_pid = $2 OR u.email IN (SELECT jsonb_array_elements_text($3::jsonb)))
+"#;
+
+const RECORD_DELIVERY: &str = r#"
+INSERT INTO webhook_deliveries (endpoint_id, event_id, event_type, attempt, status_code, success, error, duration_ms)
+VALUES ($1, $2, $3, (SELECT count(*) + 1 FROM webhook_deliveries WHERE endpoint_id = $1 AND event_id = $2), $4, $5, $6, $7)
+"#;
+
+const RESET_FAILURES: &str = r#"
+UPDATE webhook_endpoints SET consecutive_failures = 0, updated_at = now() WHERE id = $1 AND consecutive_failures > 0
+"#;
+
+/// Disables the endpoint once `$2` attempts in a row failed, returns whether it is still enabled
+const COUNT_FAILURE: &str = r#"
+UPDATE webhook_endpoints SET
+    consecutive_failures = consecutive_failures + 1,
+    enabled = enabled AND consecutive_failures + 1 < $2,
+    disabled_at = CASE WHEN enabled AND consecutive_failures + 1 >= $2 THEN now() ELSE disabled_at END,
+    updated_at = now()
+WHERE id = $1
+RETURNING enabled
+"#;
+
+/// Where and how to post events for an endpoint
+struct Target {
+    url: String,
+    secret: String,
+    enabled: bool,
+}
+
+/// Webhook endpoints of tenants and their delivery log.
+///
+/// A tenant is the user account that registered the endpoints.
+#[derive(Clone)]
+pub struct WebhookService {
+    db: DbRouter,
+    config: WebhooksConfig,
+    /// Emails of `iam.admins`, whose endpoints receive the events of every tenant
+    admins: Vec<String>,
+    client: WebhookClient,
+    deliveries: IntCounterVec,
+}
+
+impl WebhookService {
+    pub fn new(db: DbRouter, config: &ConfigService, metrics: &MetricsService) -> Self {
+        let webhooks = config.webhooks().clone();
+        Self {
+            db,
+            client: WebhookClient::new(Duration::from_secs(webhooks.timeout_secs), webhooks.allow_private_networks),
+            config: webhooks,
+            admins: config.iam().admins.clone(),
+            deliveries: metrics.counter_vec("webhook_deliveries_total", "Webhook delivery attempts", &["outcome"]),
+        }
+    }
+
+    pub fn config(&self) -> &WebhooksConfig {
+        &self.config
+    }
+
+    /// Register an endpoint, its secret is only returned here
+    pub async fn create(&self, tenant: Uuid, url: String, event_types: Vec<String>) -> Result<CreatedWebhookEndpoint, WebhookError> {
+        validate_endpoint(&url, &event_types, &self.config)?;
+        let id = Uuid::new_v4();
+        let secret = format!("whsec_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            INSERT_ENDPOINT,
+            [
+                id.into(),
+                tenant.into(),
+                url.into(),
+                secret.clone().into(),
+                serde_json::to_string(&event_types).unwrap_or_default().into(),
+            ],
+        );
+        self.db.writer().execute(statement).await?;
+        let endpoint = self.find(tenant, id).await?;
+        Ok(CreatedWebhookEndpoint { endpoint, secret })
+    }
+
+    pub async fn list(&self, tenant: Uuid) -> Result<Vec<WebhookEndpoint>, WebhookError> {
+        let sql = format!("SELECT {} {}", ENDPOINT_COLUMNS, LIST_ENDPOINTS);
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, sql, [tenant.into()]);
+        let rows = self.db.reader().query_all(statement).await?;
+        Ok(rows.iter().map(endpoint).collect::<Result<_, _>>()?)
+    }
+
+    pub async fn update(
+        &self,
+        tenant: Uuid,
+        id: Uuid,
+        url: String,
+        event_types: Vec<String>,
+        enabled: bool,
+    ) -> Result<WebhookEndpoint, WebhookError> {
+        validate_endpoint(&url, &event_types, &self.config)?;
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            UPDATE_ENDPOINT,
+            [
+                id.into(),
+                tenant.into(),
+                url.into(),
+                serde_json::to_string(&event_types).unwrap_or_default().into(),
+                enabled.into(),
+            ],
+        );
+        if self.db.writer().execute(statement).await?.rows_affected() == 0 {
+            return Err(WebhookError::NotFound);
+        }
+        self.find(tenant, id).await
+    }
+
+    /// Remove an endpoint with its delivery log, queued deliveries are dropped
+    pub async fn delete(&self, tenant: Uuid, id: Uuid) -> Result<(), WebhookError> {
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, DELETE_ENDPOINT, [id.into(), tenant.into()]);
+        match self.db.writer().execute(statement).await?.rows_affected() {
+            0 => Err(WebhookError::NotFound),
+            _ => Ok(()),
+        }
+    }
+
+    /// Latest delivery attempts to an endpoint, newest first
+    pub async fn deliveries(&self, tenant: Uuid, id: Uuid) -> Result<Vec<WebhookDelivery>, WebhookError> {
+        self.find(tenant, id).await?;
+        let sql = format!(
+            "SELECT {} FROM webhook_deliveries WHERE endpoint_id = $1 ORDER BY id DESC LIMIT $2",
+            DELIVERY_COLUMNS
+        );
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, sql, [id.into(), DELIVERY_LOG_LIMIT.into()]);
+        let rows = self.db.reader().query_all(statement).await?;
+        Ok(rows.iter().map(delivery).collect::<Result<_, _>>()?)
+    }
+
+    /// Post a `webhook.test` event to an endpoint right away, even when it is disabled.
+    /// The attempt is logged but doesn't count towards disabling the endpoint.
+    pub async fn send_test(&self, tenant: Uuid, id: Uuid) -> Result<WebhookDelivery, WebhookError> {
+        let target = self.find_target(Some(tenant), id).await?.ok_or(WebhookError::NotFound)?;
+        let event = EventEnvelope::new(&WebhookTest { endpoint_id: id }).map_err(|e| DbErr::Custom(e.to_string()))?;
+        let outcome = self.client.send(&target.url, &target.secret, &event).await;
+        Ok(self.record_delivery(id, &event, &outcome).await?)
+    }
+
+    /// Endpoints that should receive `event`, read on `conn` so they can be queued in its transaction
+    pub async fn targets<C: ConnectionTrait>(&self, conn: &C, event: &EventEnvelope) -> Result<Vec<Uuid>, DbErr> {
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            TARGETS_FOR_EVENT,
+            [
+                event.event_type.clone().into(),
+                event_tenant(event).into(),
+                serde_json::to_string(&self.admins).unwrap_or_default().into(),
+            ],
+        );
+        let rows = conn.query_all(statement).await?;
+        rows.iter().map(|row| row.try_get("", "id")).collect()
+    }
+
+    /// Post `event` to an endpoint and log the attempt. An `Err` asks for a retry; deleted and
+    /// disabled endpoints are skipped.
+    pub async fn deliver(&self, endpoint_id: Uuid, event: &EventEnvelope) -> Result<(), String> {
+        let target = match self.find_target(None, endpoint_id).await.map_err(|e| e.to_string())? {
+            Some(target) if target.enabled => target,
+            _ => {
+                tracing::debug!(%endpoint_id, "Webhook endpoint deleted or disabled, delivery dropped");
+                self.deliveries.with_label_values(&["dropped"]).inc();
+                return Ok(());
+            }
+        };
+        let outcome = self.client.send(&target.url, &target.secret, event).await;
+        self.record_delivery(endpoint_id, event, &outcome).await.map_err(|e| e.to_string())?;
+        let Some(error) = outcome.error else {
+            let statement = Statement::from_sql_and_values(DbBackend::Postgres, RESET_FAILURES, [endpoint_id.into()]);
+            self.db.writer().execute(statement).await.map_err(|e| e.to_string())?;
+            self.deliveries.with_label_values(&["delivered"]).inc();
+            return Ok(());
+        };
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            COUNT_FAILURE,
+            [endpoint_id.into(), self.config.disable_after_failures.into()],
+        );
+        let row = self.db.writer().query_one(statement).await.map_err(|e| e.to_string())?;
+        let enabled: bool = row.map(|row| row.try_get("", "enabled")).transpose().map_err(|e| e.to_string())?.unwrap_or(false);
+        if enabled {
+            self.deliveries.with_label_values(&["failed"]).inc();
+            return Err(error);
+        }
+        tracing::warn!(%endpoint_id, error, "Webhook endpoint disabled after repeated failures");
+        self.deliveries.with_label_values(&["disabled"]).inc();
+        Ok(())
+    }
+
+    async fn find(&self, tenant: Uuid, id: Uuid) -> Result<WebhookEndpoint, WebhookError> {
+        let sql = format!("SELECT {} FROM webhook_endpoints WHERE id = $1 AND tenant_pid = $2", ENDPOINT_COLUMNS);
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, sql, [id.into(), tenant.into()]);
+        let row = self.db.writer().query_one(statement).await?.ok_or(WebhookError::NotFound)?;
+        Ok(endpoint(&row)?)
+    }
+
+    async fn find_target(&self, tenant: Option<Uuid>, id: Uuid) -> Result<Option<Target>, DbErr> {
+        let statement = Statement::from_sql_and_values(DbBackend::Postgres, FIND_TARGET, [id.into(), tenant.into()]);
+        let Some(row) = self.db.writer().query_one(statement).await? else {
+            return Ok(None);
+        };
+        Ok(Some(Target {
+            url: row.try_get("", "url")?,
+            secret: row.try_get("", "secret")?,
+            enabled: row.try_get("", "enabled")?,
+        }))
+    }
+
+    async fn record_delivery(&self, endpoint_id: Uuid, event: &EventEnvelope, outcome: &DeliveryOutcome) -> Result<WebhookDelivery, DbErr> {
+        let sql = format!("{} RETURNING {}", RECORD_DELIVERY, DELIVERY_COLUMNS);
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [
+                endpoint_id.into(),
+                event.id.into(),
+                event.event_type.clone().into(),
+                outcome.status_code.map(i32::from).into(),
+                outcome.success().into(),
+                outcome.error.clone().into(),
+                (outcome.duration.as_millis() as i64).into(),
+            ],
+        );
+        let row = self.db.writer().query_one(statement).await?;
+        delivery(&row.ok_or_else(|| DbErr::RecordNotInserted)?)
+    }
+}
+
+/// The tenant an event is about, the `pid` of its payload
+pub fn event_tenant(event: &EventEnvelope) -> Option<Uuid> {
+    event.payload.get("pid")?.as_str()?.parse().ok()
+}
+
+/// Check the URL and subscriptions of an endpoint
+pub fn validate_endpoint(url: &str, event_types: &[String], config: &WebhooksConfig) -> Result<(), WebhookError> {
+    let mut errors = vec![];
+    let schemes: &[&str] = match config.require_https {
+        true => &["https"],
+        false => &["http", "https"],
+    };
+    match reqwest::Url::parse(url) {
+        Ok(url) if !config.allow_private_networks && is_internal_url(&url) => errors.push(ValidationError::new(
+            "url",
+            "webhook_url_private",
+            String::from("URL must not point to a private network address."),
+        )),
+        Ok(url) if schemes.contains(&url.scheme()) && url.host().is_some() => {}
+        _ => errors.push(
+            ValidationError::new("url", "webhook_url", String::from("URL must be an absolute http(s) URL."))
+                .with_arg("schemes", schemes.join(", ")),
+        ),
+    }
+    if event_types.is_empty() {
+        errors.push(ValidationError::new(
+            "event_types",
+            "event_types_empty",
+            String::from("Subscribe to at least one event type."),
+        ));
+    }
+    for event_type in event_types.iter().filter(|t| !EVENT_TYPES.contains(&t.as_str())) {
+        errors.push(
+            ValidationError::new("event_types", "unknown_event_type", format!("Unknown event type {}.", event_type))
+                .with_arg("event_type", event_type),
+        );
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(WebhookError::Validation(errors)),
+    }
+}
+
+fn endpoint(row: &QueryResult) -> Result<WebhookEndpoint, DbErr> {
+    let event_types: String = row.try_get("", "event_types")?;
+    let disabled_at: Option<f64> = row.try_get("", "disabled_at")?;
+    let created_at: f64 = row.try_get("", "created_at")?;
+    Ok(WebhookEndpoint {
+        id: row.try_get("", "id")?,
+        url: row.try_get("", "url")?,
+        event_types: serde_json::from_str(&event_types).map_err(|e| DbErr::Custom(e.to_string()))?,
+        enabled: row.try_get("", "enabled")?,
+        consecutive_failures: row.try_get("", "consecutive_failures")?,
+        disabled_at: disabled_at.map(timestamp),
+        created_at: timestamp(created_at),
+    })
+}
+
+fn delivery(row: &QueryResult) -> Result<WebhookDelivery, DbErr> {
+    let created_at: f64 = row.try_get("", "created_at")?;
+    Ok(WebhookDelivery {
+        id: row.try_get("", "id")?,
+        event_id: row.try_get("", "event_id")?,
+        event_type: row.try_get("", "event_type")?,
+        attempt: row.try_get("", "attempt")?,
+        status_code: row.try_get("", "status_code")?,
+        success: row.try_get("", "success")?,
+        error: row.try_get("", "error")?,
+        duration_ms: row.try_get("", "duration_ms")?,
+        created_at: timestamp(created_at),
+    })
+}
+
+fn timestamp(epoch: f64) -> String {
+    Utc.timestamp_millis_opt((epoch * 1000.0) as i64)
+        .single()
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
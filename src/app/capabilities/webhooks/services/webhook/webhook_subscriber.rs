@@ -0,0 +1,55 @@
+use migration::sea_orm::DatabaseTransaction;
+
+use crate::app::capabilities::{
+    common::{
+        events::{
+            domain_event::EventEnvelope,
+            event_subscriber::{EventFuture, EventSubscriber},
+        },
+        jobs::job_service::{EnqueueOptions, JobService},
+    },
+    webhooks::services::webhook::webhook_service::EVENT_TYPES,
+};
+
+use super::{
+    webhook_delivery_job::{WebhookDeliveryJob, WebhookDeliveryPayload},
+    webhook_service::WebhookService,
+};
+
+/// Queues a delivery job per endpoint subscribed to an event
+pub struct WebhookSubscriber {
+    pub webhooks: WebhookService,
+    pub jobs: JobService,
+}
+
+impl EventSubscriber for WebhookSubscriber {
+    fn name(&self) -> &str {
+        "webhooks"
+    }
+
+    fn event_types(&self) -> &[&'static str] {
+        EVENT_TYPES
+    }
+
+    fn handle<'a>(&'a self, txn: &'a DatabaseTransaction, event: &'a EventEnvelope) -> EventFuture<'a> {
+        Box::pin(async move {
+            let targets = self.webhooks.targets(txn, event).await.map_err(|e| e.to_string())?;
+            for endpoint_id in targets {
+                let payload = WebhookDeliveryPayload {
+                    endpoint_id,
+                    event: event.clone(),
+                };
+                let options = EnqueueOptions {
+                    max_attempts: Some(self.webhooks.config().max_attempts),
+                    unique_key: Some(format!("webhook:{}:{}", endpoint_id, event.id)),
+                    ..EnqueueOptions::default()
+                };
+                self.jobs
+                    .enqueue_in::<WebhookDeliveryJob, _>(txn, &payload, options)
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use poem::{
+    handler,
+    http::{HeaderMap, StatusCode},
+    listener::{Acceptor, Listener, TcpListener},
+    web::Data,
+    Body, EndpointExt, Route,
+};
+use uuid::Uuid;
+
+use crate::app::capabilities::{
+    common::events::domain_event::EventEnvelope,
+    iam::events::UserRegistered,
+    webhooks::{config::WebhooksConfig, enums::webhook_error::WebhookError},
+};
+
+use super::{
+    webhook_address::is_internal_url,
+    webhook_client::WebhookClient,
+    webhook_service::{event_tenant, validate_endpoint},
+    webhook_signature::{self, ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+};
+
+const SECRET: &str = "whsec_test";
+
+/// Events received with a valid signature
+type Received = Arc<Mutex<Vec<EventEnvelope>>>;
+
+#[handler]
+async fn receive(headers: &HeaderMap, body: Body, received: Data<&Received>, status: Data<&StatusCode>) -> StatusCode {
+    let body = body.into_vec().await.unwrap();
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    let timestamp = header(TIMESTAMP_HEADER).parse().unwrap_or_default();
+    let now = chrono::Utc::now().timestamp();
+    if !webhook_signature::verify(SECRET, timestamp, &body, header(SIGNATURE_HEADER), now, 300) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    let event: EventEnvelope = serde_json::from_slice(&body).unwrap();
+    assert_eq!(header(ID_HEADER), event.id.to_string());
+    received.lock().unwrap().push(event);
+    *status.0
+}
+
+/// Serve `receive` on a random port, answering signed requests with `status`
+async fn receiver(status: StatusCode) -> (String, Received) {
+    let received = Received::default();
+    let app = Route::new().at("/hook", receive).data(received.clone()).data(status);
+    let acceptor = TcpListener::bind("127.0.0.1:0").into_acceptor().await.unwrap();
+    let port = acceptor.local_addr()[0].as_socket_addr().unwrap().port();
+    tokio::spawn(poem::Server::new_with_acceptor(acceptor).run(app));
+    (format!("http://127.0.0.1:{}/hook", port), received)
+}
+
+fn user_registered() -> EventEnvelope {
+    EventEnvelope::new(&UserRegistered {
+        pid: Uuid::new_v4(),
+        email: String::from("jane@example.com"),
+        first_name: String::from("Jane"),
+        last_name: String::from("Doe"),
+        locale: String::from("en"),
+    })
+    .unwrap()
+}
+
+#[test]
+fn should_reject_tampered_or_stale_signatures() {
+    let signature = webhook_signature::sign(SECRET, 1_000, b"{}");
+    assert!(signature.starts_with("v1="));
+    assert!(webhook_signature::verify(SECRET, 1_000, b"{}", &signature, 1_100, 300));
+    assert!(!webhook_signature::verify(SECRET, 1_000, b"{ }", &signature, 1_100, 300));
+    assert!(!webhook_signature::verify("whsec_other", 1_000, b"{}", &signature, 1_100, 300));
+    assert!(!webhook_signature::verify(SECRET, 1_001, b"{}", &signature, 1_100, 300));
+    assert!(!webhook_signature::verify(SECRET, 1_000, b"{}", &signature, 2_000, 300));
+    assert!(!webhook_signature::verify(SECRET, 1_000, b"{}", &signature[3..], 1_100, 300));
+}
+
+#[tokio::test]
+async fn should_post_signed_events() {
+    let (url, received) = receiver(StatusCode::NO_CONTENT).await;
+    let event = user_registered();
+
+    let outcome = WebhookClient::new(Duration::from_secs(5), true).send(&url, SECRET, &event).await;
+    assert!(outcome.success(), "{:?}", outcome.error);
+    assert_eq!(outcome.status_code, Some(204));
+    assert_eq!(*received.lock().unwrap(), vec![event]);
+}
+
+#[tokio::test]
+async fn should_report_failed_deliveries() {
+    let client = WebhookClient::new(Duration::from_secs(5), true);
+    let (url, received) = receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+    let outcome = client.send(&url, SECRET, &user_registered()).await;
+    assert_eq!(outcome.status_code, Some(500));
+    assert!(!outcome.success());
+    assert_eq!(received.lock().unwrap().len(), 1);
+
+    let outcome = client.send(&url, "whsec_wrong", &user_registered()).await;
+    assert_eq!(outcome.status_code, Some(401));
+
+    // nothing listens on port 1
+    let outcome = client.send("http://127.0.0.1:1/hook", SECRET, &user_registered()).await;
+    assert_eq!(outcome.status_code, None);
+    assert!(outcome.error.is_some());
+}
+
+#[test]
+fn should_validate_endpoints() {
+    let config = WebhooksConfig::default();
+    let types = vec![String::from("user.registered")];
+    assert!(validate_endpoint("http://example.com:3000/hook", &types, &config).is_ok());
+
+    let Err(WebhookError::Validation(errors)) = validate_endpoint("ftp://example.com", &[String::from("user.deleted")], &config)
+    else {
+        panic!("expected validation errors");
+    };
+    let rules: Vec<_> = errors.iter().map(|e| e.rule.as_str()).collect();
+    assert_eq!(rules, vec!["webhook_url", "unknown_event_type"]);
+
+    let config = WebhooksConfig {
+        require_https: true,
+        ..WebhooksConfig::default()
+    };
+    assert!(validate_endpoint("http://example.com/hook", &types, &config).is_err());
+    assert!(validate_endpoint("https://example.com/hook", &types, &config).is_ok());
+    assert!(validate_endpoint("https://example.com/hook", &[], &config).is_err());
+}
+
+#[test]
+fn should_reject_private_network_endpoints() {
+    let config = WebhooksConfig::default();
+    let types = vec![String::from("user.registered")];
+    for url in [
+        "http://localhost:3000/hook",
+        "http://api.localhost/hook",
+        "http://127.0.0.1/hook",
+        "http://2130706433/hook",
+        "http://169.254.169.254/latest/meta-data",
+        "http://10.0.0.5/hook",
+        "http://192.168.1.1/hook",
+        "http://100.64.0.1/hook",
+        "http://0.0.0.0/hook",
+        "http://[::1]/hook",
+        "http://[fd00::1]/hook",
+        "http://[fe80::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+    ] {
+        let Err(WebhookError::Validation(errors)) = validate_endpoint(url, &types, &config) else {
+            panic!("expected {} to be rejected", url);
+        };
+        assert_eq!(errors[0].rule, "webhook_url_private", "{}", url);
+    }
+    assert!(!is_internal_url(&"http://8.8.8.8/hook".parse().unwrap()));
+    assert!(!is_internal_url(&"http://[2001:4860::8888]/hook".parse().unwrap()));
+
+    let config = WebhooksConfig {
+        allow_private_networks: true,
+        ..WebhooksConfig::default()
+    };
+    assert!(validate_endpoint("http://localhost:3000/hook", &types, &config).is_ok());
+    assert!(validate_endpoint("http://10.0.0.5/hook", &types, &config).is_ok());
+}
+
+#[tokio::test]
+async fn should_not_deliver_to_private_networks() {
+    let (url, received) = receiver(StatusCode::NO_CONTENT).await;
+    let client = WebhookClient::new(Duration::from_secs(5), false);
+
+    let outcome = client.send(&url, SECRET, &user_registered()).await;
+    assert_eq!(outcome.status_code, None);
+    assert!(outcome.error.is_some());
+
+    // a name resolving to loopback is refused by the resolver
+    let outcome = client.send(&url.replace("127.0.0.1", "localhost"), SECRET, &user_registered()).await;
+    assert_eq!(outcome.status_code, None);
+    assert!(outcome.error.is_some());
+    assert!(received.lock().unwrap().is_empty());
+}
+
+#[test]
+fn should_scope_events_to_the_user_they_are_about() {
+    let event = user_registered();
+    assert_eq!(event_tenant(&event), event.payload["pid"].as_str().unwrap().parse().ok());
+    let mut other = event.clone();
+    other.payload = serde_json::json!({"endpoint_id": Uuid::new_v4()});
+    assert_eq!(event_tenant(&other), None);
+}
@@ -1,3 +1,9 @@
 mod routes;
+pub mod app_builder;
 pub mod bootstrap;
+pub mod startup_error;
 mod capabilities;
+
+pub use capabilities::common::{config, database, events, global_model, i18n, jobs, lifecycle, logging, telemetry, tls};
+/// For receivers checking the `Webhook-Signature` of delivered events
+pub use capabilities::webhooks::services::webhook::webhook_signature;
@@ -0,0 +1,145 @@
+use poem::{http::StatusCode, web::Data};
+use poem_openapi::{
+    param::{Path, Query},
+    payload::{Html, Json},
+    ApiResponse, OpenApi, Tags,
+};
+
+use crate::app::capabilities::{
+    common::{
+        email::email_templates::{EmailError, RenderedEmail},
+        global_model::app_state::AppState,
+        problem::problem_details::ProblemDetails,
+        scheduler::scheduled_task::TaskStatus,
+    },
+    iam::controllers::users::users_controller::UserAuth,
+};
+
+#[derive(ApiResponse)]
+pub enum SchedulerStatusResponse {
+    #[oai(status = 200)]
+    Ok(Json<Vec<TaskStatus>>),
+    /// `admin_required` for users not listed in `iam.admins`
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for SchedulerStatusResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        SchedulerStatusResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum EmailPreviewResponse {
+    #[oai(status = 200)]
+    Ok(Json<RenderedEmail>),
+    /// `admin_required`, or `not_found` for an unknown email
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for EmailPreviewResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        EmailPreviewResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum EmailHtmlPreviewResponse {
+    #[oai(status = 200)]
+    Ok(Html<String>),
+    /// `admin_required`, or `not_found` for an unknown email
+    #[oai(content_type = "application/problem+json")]
+    Problem(StatusCode, Json<ProblemDetails>),
+}
+
+impl From<ProblemDetails> for EmailHtmlPreviewResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        EmailHtmlPreviewResponse::Problem(problem.status_code(), Json(problem))
+    }
+}
+
+#[derive(Tags)]
+enum ApiTags {
+    /// Operations restricted to `iam.admins`
+    Admin,
+}
+
+#[derive(Default)]
+pub struct Api;
+
+/// Admin operations on the scheduler tables, only mounted on Postgres
+#[derive(Default)]
+pub struct SchedulerApi;
+
+#[OpenApi]
+impl SchedulerApi {
+    /// Scheduled tasks with their next tick and latest runs
+    #[oai(path = "/admin/scheduler", method = "get", tag = "ApiTags::Admin", operation_id = "schedulerStatus")]
+    pub async fn scheduler_status(&self, state: Data<&AppState>, auth: UserAuth) -> SchedulerStatusResponse {
+        if !state.services.iam.is_admin(&auth.session_user()) {
+            return ProblemDetails::new(StatusCode::FORBIDDEN).with_code("admin_required").into();
+        }
+        match state.scheduler.status().await {
+            Err(e) => ProblemDetails::from(e).into(),
+            Ok(status) => SchedulerStatusResponse::Ok(Json(status)),
+        }
+    }
+}
+
+#[OpenApi]
+impl Api {
+    /// An email rendered with sample data, in `locale` or the default locale
+    #[oai(path = "/admin/emails/:email/preview", method = "get", tag = "ApiTags::Admin", operation_id = "previewEmail")]
+    pub async fn preview_email(
+        &self,
+        state: Data<&AppState>,
+        auth: UserAuth,
+        email: Path<String>,
+        locale: Query<Option<String>>,
+    ) -> EmailPreviewResponse {
+        match preview(&state, auth, &email, locale.0.as_deref()) {
+            Err(problem) => (*problem).into(),
+            Ok(rendered) => EmailPreviewResponse::Ok(Json(rendered)),
+        }
+    }
+
+    /// The HTML part of `previewEmail`, to open in a browser
+    #[oai(
+        path = "/admin/emails/:email/preview.html",
+        method = "get",
+        tag = "ApiTags::Admin",
+        operation_id = "previewEmailHtml"
+    )]
+    pub async fn preview_email_html(
+        &self,
+        state: Data<&AppState>,
+        auth: UserAuth,
+        email: Path<String>,
+        locale: Query<Option<String>>,
+    ) -> EmailHtmlPreviewResponse {
+        match preview(&state, auth, &email, locale.0.as_deref()) {
+            Err(problem) => (*problem).into(),
+            Ok(rendered) => EmailHtmlPreviewResponse::Ok(Html(rendered.html)),
+        }
+    }
+}
+
+fn preview(state: &AppState, auth: UserAuth, email: &str, locale: Option<&str>) -> Result<RenderedEmail, Box<ProblemDetails>> {
+    if !state.services.iam.is_admin(&auth.session_user()) {
+        return Err(Box::new(ProblemDetails::new(StatusCode::FORBIDDEN).with_code("admin_required")));
+    }
+    let locale = locale
+        .and_then(|locale| state.i18n.find(locale))
+        .unwrap_or_else(|| state.i18n.default_locale().clone());
+    state.email.preview(email, &locale).map_err(|e| {
+        Box::new(match e {
+            EmailError::UnknownEmail(_) => ProblemDetails::new(StatusCode::NOT_FOUND),
+            EmailError::Render(e) => {
+                tracing::error!("Failed to render email preview: {:#}", e);
+                ProblemDetails::internal()
+            }
+        })
+    })
+}
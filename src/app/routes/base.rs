@@ -1,16 +1,16 @@
 use poem::web::Data;
 use poem_openapi::{payload::Json, ApiResponse, Object, OpenApi, Tags};
 
-use crate::app::capabilities::common::global_model::app_state::AppState;
+use crate::app::capabilities::common::{
+    database::db_router::PoolStats,
+    global_model::app_state::AppState,
+    health::health_check::{CheckResult, HealthReport, HealthStatus},
+};
 
 #[derive(Debug, Object, Clone, Eq, PartialEq)]
 pub struct Ping {
     up: bool
 }
-#[derive(Debug, Object, Clone, Eq, PartialEq)]
-pub struct Health {
-    db: bool,
-}
 
 #[derive(ApiResponse)]
 pub enum PingResponse {
@@ -20,9 +20,41 @@ pub enum PingResponse {
 
 
 #[derive(ApiResponse)]
-pub enum HealthResponse {
+pub enum ProbeResponse {
+    #[oai(status = 200)]
+    Ok(Json<HealthReport>),
+    /// A required check failed, or the app is starting or shutting down
+    #[oai(status = 503)]
+    ServiceUnavailable(Json<HealthReport>),
+}
+
+impl From<HealthReport> for ProbeResponse {
+    fn from(report: HealthReport) -> Self {
+        match report.status {
+            HealthStatus::Up => ProbeResponse::Ok(Json(report)),
+            HealthStatus::Down => ProbeResponse::ServiceUnavailable(Json(report)),
+        }
+    }
+}
+
+/// Report for a probe answered by the lifecycle state alone
+fn lifecycle_report(status: HealthStatus, error: Option<&str>) -> HealthReport {
+    HealthReport {
+        status,
+        checks: vec![CheckResult {
+            name: String::from("lifecycle"),
+            status,
+            required: true,
+            latency_ms: 0,
+            error: error.map(String::from),
+        }],
+    }
+}
+
+#[derive(ApiResponse)]
+pub enum PoolStatsResponse {
     #[oai(status = 200)]
-    Ok(Json<Health>)
+    Ok(Json<Vec<PoolStats>>)
 }
 
 
@@ -30,7 +62,10 @@ pub enum HealthResponse {
 enum ApiTags {
     /// Operations about user
     PingResponse,
-    HealthResponse
+    /// Liveness, readiness and startup probes
+    Probes,
+    /// Database connection pool usage
+    DbPool
 }
 
 #[derive(Default)]
@@ -39,20 +74,37 @@ pub struct Api;
 
 #[OpenApi]
 impl Api {
-    #[oai(path = "/ping", method = "get", tag = "ApiTags::PingResponse")]
+    #[oai(path = "/ping", method = "get", tag = "ApiTags::PingResponse", operation_id = "ping")]
     pub async fn ping(&self) -> PingResponse {
         PingResponse::Ok(Json(Ping { up: true })) 
     }
 
-    #[oai(path = "/health", method = "get", tag = "ApiTags::HealthResponse")]
-    pub async fn health(&self, state: Data<&AppState>) -> HealthResponse {
-        let mut db_state = false;
-        match state.db.ping().await {
-            Ok(_) => db_state = true,
-            Err(e) => tracing::error!("{}", e)
+    /// The process is up, no dependencies are checked so a database outage doesn't restart it
+    #[oai(path = "/livez", method = "get", tag = "ApiTags::Probes", operation_id = "livez")]
+    pub async fn livez(&self) -> ProbeResponse {
+        lifecycle_report(HealthStatus::Up, None).into()
+    }
+
+    /// The app should receive traffic: not shutting down and every required check passes
+    #[oai(path = "/readyz", method = "get", tag = "ApiTags::Probes", operation_id = "readyz")]
+    pub async fn readyz(&self, state: Data<&AppState>) -> ProbeResponse {
+        if !state.lifecycle.is_ready() {
+            return lifecycle_report(HealthStatus::Down, Some("not ready")).into();
         }
-        HealthResponse::Ok(Json(Health {
-            db: db_state,
-        }))
+        state.health.run().await.into()
+    }
+
+    /// Startup finished and every required check passes
+    #[oai(path = "/startupz", method = "get", tag = "ApiTags::Probes", operation_id = "startupz")]
+    pub async fn startupz(&self, state: Data<&AppState>) -> ProbeResponse {
+        if !state.lifecycle.is_started() {
+            return lifecycle_report(HealthStatus::Down, Some("starting")).into();
+        }
+        state.health.run().await.into()
+    }
+
+    #[oai(path = "/db/pool", method = "get", tag = "ApiTags::DbPool", operation_id = "dbPoolStats")]
+    pub async fn pool_stats(&self, state: Data<&AppState>) -> PoolStatsResponse {
+        PoolStatsResponse::Ok(Json(state.db.stats()))
     }
 }
@@ -0,0 +1,14 @@
+use poem::{handler, web::Data, IntoResponse, Response};
+
+use crate::app::capabilities::common::global_model::app_state::AppState;
+
+/// Prometheus scrape endpoint
+#[handler]
+pub async fn metrics(state: Data<&AppState>) -> Response {
+    state.metrics.observe_pools(&state.db.stats());
+    state
+        .metrics
+        .render()
+        .with_content_type("text/plain; version=0.0.4")
+        .into_response()
+}
@@ -1 +1,3 @@
+pub mod admin;
 pub mod base;
+pub mod metrics;
@@ -0,0 +1,43 @@
+use std::fmt;
+
+use migration::sea_orm::DbErr;
+
+use super::config::config_service::ConfigError;
+
+/// Why the app could not be built, returned instead of panicking so binaries and tests decide how to fail
+#[derive(Debug)]
+pub enum StartupError {
+    Config(ConfigError),
+    /// Connecting to the primary or a replica failed
+    Database(DbErr),
+    Migration(DbErr),
+    /// A service rejected its config, e.g. an unreadable breached password list
+    Service { name: &'static str, message: String },
+}
+
+impl fmt::Display for StartupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartupError::Config(e) => write!(f, "{}", e),
+            StartupError::Database(e) => write!(f, "Database connection failed: {}", e),
+            StartupError::Migration(e) => write!(f, "Migrations failed: {}", e),
+            StartupError::Service { name, message } => write!(f, "Could not start {}: {}", name, message),
+        }
+    }
+}
+
+impl std::error::Error for StartupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StartupError::Config(e) => Some(e),
+            StartupError::Database(e) | StartupError::Migration(e) => Some(e),
+            StartupError::Service { .. } => None,
+        }
+    }
+}
+
+impl From<ConfigError> for StartupError {
+    fn from(e: ConfigError) -> Self {
+        StartupError::Config(e)
+    }
+}
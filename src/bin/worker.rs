@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use server::app::{
+    app_builder::AppBuilder,
+    config::config_service::ConfigService,
+    lifecycle::lifecycle_service::{hook_order, shutdown_signal},
+    telemetry::telemetry_service::TelemetryService,
+};
+
+/// Runs background jobs without serving HTTP, scale it separately from the server
+#[tokio::main]
+async fn main() {
+    let _ = dotenvy::from_path(Path::new(format!("{}/.env", env!("CARGO_MANIFEST_DIR")).as_str()));
+
+    let config = match ConfigService::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if config.database().is_sqlite() {
+        eprintln!("The worker needs Postgres, jobs can't run on a SQLite database");
+        std::process::exit(1);
+    }
+    let telemetry = match TelemetryService::init(config.logging(), config.telemetry()) {
+        Ok(telemetry) => telemetry,
+        Err(e) => {
+            eprintln!("Failed to set up tracing: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let app = match AppBuilder::from_config(&config).build().await {
+        Ok(app) => app,
+        Err(e) => {
+            tracing::error!("{}", e);
+            telemetry.shutdown().await;
+            std::process::exit(1);
+        }
+    };
+    let lifecycle = app.state().lifecycle.clone();
+    lifecycle.on_shutdown("telemetry", hook_order::FLUSH, move || telemetry.shutdown());
+    app.start_workers();
+    if config.scheduler().enabled {
+        app.start_scheduler();
+    }
+
+    shutdown_signal().await;
+    tracing::info!("Shutdown signal received, finishing running jobs");
+    lifecycle.run_shutdown_hooks().await;
+    tracing::info!("Shutdown complete");
+}
@@ -1,25 +1,83 @@
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
-use poem::listener::TcpListener;
-use server::app::bootstrap::build_app;
+use poem::listener::{Listener, TcpListener};
+use server::app::{
+    app_builder::AppBuilder,
+    config::config_service::ConfigService,
+    lifecycle::lifecycle_service::hook_order,
+    telemetry::telemetry_service::TelemetryService,
+    tls::{https_redirect::RedirectServer, tls_listener::TlsListener},
+};
 
 #[tokio::main]
 async fn main() -> Result<(), std::io::Error> {
-    std::env::set_var("RUST_LOG", "debug");
     // load .env
     let _ = dotenvy::from_path(Path::new(format!("{}/.env", env!("CARGO_MANIFEST_DIR")).as_str()));
 
-    tracing_subscriber::fmt::init();
+    let config = match ConfigService::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let telemetry = match TelemetryService::init(config.logging(), config.telemetry()) {
+        Ok(telemetry) => telemetry,
+        Err(e) => {
+            eprintln!("Failed to set up tracing: {}", e);
+            std::process::exit(1);
+        }
+    };
+    tracing::debug!("{:?}", config.get());
 
-    // Base environment variables
-    let host = std::env::var("HOST").expect("HOST is not set in .env file");
-    let port = std::env::var("PORT").expect("PORT is not set in .env file");
-    let server_url = format!("{host}:{port}");
+    let app = match AppBuilder::from_config(&config).build().await {
+        Ok(app) => app,
+        Err(e) => {
+            tracing::error!("{}", e);
+            telemetry.shutdown().await;
+            std::process::exit(1);
+        }
+    };
+    let lifecycle = app.state().lifecycle.clone();
+    lifecycle.on_shutdown("telemetry", hook_order::FLUSH, move || telemetry.shutdown());
+    if config.jobs().run_in_server {
+        app.start_workers();
+    }
+    if config.scheduler().enabled {
+        app.start_scheduler();
+    }
+    let client_certificates = app.state().client_certificates.clone();
+    let app = app.into_endpoint();
 
+    let server_config = config.server();
+    let listener = TcpListener::bind(server_config.address());
+    let listener = if server_config.tls.enabled {
+        if let Some(port) = server_config.tls.redirect_http_port {
+            let redirect = match RedirectServer::spawn(format!("{}:{}", server_config.host, port), server_config.port).await {
+                Ok(redirect) => redirect,
+                Err(e) => {
+                    tracing::error!("Failed to bind the HTTPS redirect listener on port {}: {}", port, e);
+                    lifecycle.run_shutdown_hooks().await;
+                    return Err(e);
+                }
+            };
+            lifecycle.on_shutdown("https_redirect", hook_order::STOP_WORKERS, move || redirect.stop());
+        }
+        TlsListener::new(listener, server_config.tls.clone(), client_certificates).boxed()
+    } else {
+        listener.boxed()
+    };
+    let result = poem::Server::new(listener)
+        .run_with_graceful_shutdown(
+            app,
+            lifecycle
+                .clone()
+                .wait_for_shutdown(Duration::from_secs(server_config.shutdown_readiness_delay_secs)),
+            Some(Duration::from_secs(server_config.drain_timeout_secs)),
+        )
+        .await;
 
-    let app = build_app().await;
-
-    poem::Server::new(TcpListener::bind(server_url))
-        .run(app)
-        .await
+    lifecycle.run_shutdown_hooks().await;
+    tracing::info!("Shutdown complete");
+    result
 }
@@ -0,0 +1,110 @@
+use migration::sea_orm::Database;
+use poem::{
+    get, handler,
+    http::StatusCode,
+    middleware::SetHeader,
+    web::Data,
+    Route,
+};
+use server::app::{
+    app_builder::{AppBuilder, Capability},
+    config::secret::Secret,
+    global_model::app_state::AppState,
+    startup_error::StartupError,
+};
+
+use crate::harness::{test_config, TestApp};
+
+#[handler]
+fn greeting(state: Data<&AppState>) -> String {
+    format!("hello in {}", state.i18n.default_locale())
+}
+
+struct Greetings;
+
+impl Capability for Greetings {
+    fn routes(&self, route: Route, _state: &AppState) -> Route {
+        route.at("/greetings", get(greeting))
+    }
+}
+
+#[tokio::test]
+async fn should_mount_capabilities_inside_the_built_in_middleware() {
+    let app = TestApp::from_builder(
+        AppBuilder::new(test_config())
+            .capability(Greetings)
+            .middleware(SetHeader::new().overriding("x-layer", "first"))
+            .middleware(SetHeader::new().overriding("x-layer", "second")),
+    )
+    .await;
+
+    let resp = app.client.get("/greetings").send().await;
+    resp.assert_status_is_ok();
+    resp.assert_header("x-layer", "first");
+    resp.assert_header_exist("x-request-id");
+    resp.assert_text("hello in en").await;
+
+    let resp = app.client.get("/api/livez").send().await;
+    resp.assert_header("x-layer", "first");
+    let resp = app.client.get("/unknown").send().await;
+    resp.assert_status(StatusCode::NOT_FOUND);
+    resp.assert_content_type("application/problem+json");
+}
+
+#[tokio::test]
+async fn should_migrate_and_use_a_given_connection() {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let app = TestApp::from_builder(AppBuilder::new(test_config()).database(db)).await;
+
+    let user = app.user().await;
+    let resp = app.client.get("/api/users/me").header("authorization", user.bearer()).send().await;
+    resp.assert_status_is_ok();
+}
+
+#[tokio::test]
+async fn should_check_the_config_against_the_given_connection() {
+    // no url needed when a connection is given
+    let mut config = test_config();
+    config.database.url = Default::default();
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    assert!(AppBuilder::new(config).database(db).build().await.is_ok());
+
+    // the connection is SQLite whatever the url says
+    let mut config = test_config();
+    config.database.url = Secret::new(String::from("postgres://localhost/app"));
+    config.scheduler.enabled = true;
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    match AppBuilder::new(config).database(db).build().await {
+        Err(StartupError::Config(e)) => assert_eq!(
+            e.errors,
+            vec![String::from("scheduler.enabled: the scheduler needs Postgres, turn off on SQLite")]
+        ),
+        _ => panic!("expected a config error"),
+    }
+}
+
+#[tokio::test]
+async fn should_return_startup_errors_instead_of_panicking() {
+    let mut config = test_config();
+    config.iam.jwt_secret = Default::default();
+    match AppBuilder::new(config).build().await {
+        Err(StartupError::Config(e)) => assert_eq!(e.errors, vec![String::from("iam.jwt_secret: must be set (IAM_JWT_SECRET)")]),
+        _ => panic!("expected a config error"),
+    }
+
+    // exists, so the config is valid, but isn't text
+    let file = std::env::temp_dir().join(format!("breached-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&file, [0xff, 0xfe, b'\n']).unwrap();
+    let mut config = test_config();
+    config.iam.password_policy.breached_passwords_file = Some(file.display().to_string());
+    let result = AppBuilder::new(config).build().await;
+    std::fs::remove_file(&file).unwrap();
+    match result {
+        Err(e @ StartupError::Service { name: "iam", .. }) => assert!(e.to_string().starts_with("Could not start iam")),
+        _ => panic!("expected an iam error"),
+    }
+
+    let mut config = test_config();
+    config.database.url = Secret::new(String::from("sqlite:///nonexistent/dir/app.db"));
+    assert!(matches!(AppBuilder::new(config).build().await, Err(StartupError::Database(_))));
+}
@@ -0,0 +1,188 @@
+use poem::{
+    endpoint::BoxEndpoint,
+    http::{header, StatusCode},
+    test::{TestClient, TestResponse},
+};
+use serde_json::Value;
+use server::app::{
+    app_builder::AppBuilder,
+    bootstrap::openapi_spec,
+    config::{
+        app_config::{AppConfig, EmailTransport},
+        secret::Secret,
+    },
+    global_model::app_state::AppState,
+};
+use uuid::Uuid;
+
+pub const PASSWORD: &str = "correct horse battery staple";
+
+/// Valid config for a private in-memory SQLite database, without background workers
+pub fn test_config() -> AppConfig {
+    let mut config = AppConfig::default();
+    config.database.url = Secret::new(String::from("sqlite::memory:"));
+    config.database.auto_migrate = true;
+    config.iam.jwt_secret = Secret::new(String::from("TEST_SECRET"));
+    config.iam.bcrypt_cost = 4;
+    config.jobs.run_in_server = false;
+    config.scheduler.enabled = false;
+    config.rate_limit.enabled = false;
+    config.email.transport = EmailTransport::Log;
+    config
+}
+
+/// The app built by `AppBuilder` behind a `TestClient`, with its state for direct checks
+pub struct TestApp {
+    pub client: TestClient<BoxEndpoint<'static>>,
+    pub state: AppState,
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        Self::with_config(|_| {}).await
+    }
+
+    /// App on a fresh migrated database, after `configure` adjusted the `test_config`
+    pub async fn with_config(configure: impl FnOnce(&mut AppConfig)) -> Self {
+        let mut config = test_config();
+        configure(&mut config);
+        Self::from_builder(AppBuilder::new(config)).await
+    }
+
+    pub async fn from_builder(builder: AppBuilder) -> Self {
+        let app = builder.build().await.unwrap();
+        let state = app.state().clone();
+        Self {
+            client: TestClient::new(app.into_endpoint()),
+            state,
+        }
+    }
+
+    /// Register a user with the defaults of `UserFactory`
+    pub async fn user(&self) -> TestUser {
+        UserFactory::default().create(self).await
+    }
+}
+
+/// Fields of a user to register, the email is unique per factory
+pub struct UserFactory {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub password: String,
+    pub locale: String,
+}
+
+impl Default for UserFactory {
+    fn default() -> Self {
+        Self {
+            email: format!("user-{}@example.com", Uuid::new_v4().simple()),
+            first_name: String::from("Jane"),
+            last_name: String::from("Doe"),
+            password: String::from(PASSWORD),
+            locale: String::from("en"),
+        }
+    }
+}
+
+impl UserFactory {
+    pub fn email(mut self, email: &str) -> Self {
+        self.email = email.to_string();
+        self
+    }
+
+    /// Request body of `POST /api/auth/register`
+    pub fn payload(&self) -> Value {
+        serde_json::json!({
+            "email": self.email,
+            "first_name": self.first_name,
+            "last_name": self.last_name,
+            "password": self.password,
+            "locale": self.locale,
+        })
+    }
+
+    /// Register through `IAMService`, skipping HTTP
+    pub async fn create(self, app: &TestApp) -> TestUser {
+        let bearer = app
+            .state
+            .services
+            .iam
+            .register(self.email.clone(), self.first_name, self.last_name, self.password, self.locale)
+            .await
+            .unwrap();
+        TestUser {
+            pid: bearer.session_user.unwrap().pid,
+            email: self.email,
+            token: bearer.token,
+        }
+    }
+}
+
+/// A registered user, their password is the one of the factory (`PASSWORD` by default)
+pub struct TestUser {
+    pub pid: Uuid,
+    pub email: String,
+    pub token: String,
+}
+
+impl TestUser {
+    pub fn bearer(&self) -> String {
+        format!("Bearer {}", self.token)
+    }
+}
+
+/// Status and JSON body of a response, after checking both against the OpenAPI document
+pub async fn conforming(method: &str, path: &str, resp: TestResponse) -> (StatusCode, Value) {
+    let status = resp.0.status();
+    let content_type = resp
+        .0
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = resp.0.into_body().into_string().await.unwrap();
+
+    let spec: Value = serde_json::from_str(&openapi_spec()).unwrap();
+    let operation = &spec["paths"][path][method];
+    assert!(operation.is_object(), "{} {} is not documented", method, path);
+    let responses = &operation["responses"];
+    let documented = match &responses[status.as_str()] {
+        Value::Null => &responses["default"],
+        response => response,
+    };
+    assert!(documented.is_object(), "{} {} does not document status {}", method, path, status);
+
+    let Some(content) = documented["content"].as_object() else {
+        assert!(body.is_empty(), "{} {} {} has an undocumented body", method, path, status);
+        return (status, Value::Null);
+    };
+    let content_type = content_type.expect("response without content type");
+    let schema = content
+        .iter()
+        .find(|(documented, _)| same_media_type(documented, &content_type))
+        .map(|(_, media)| &media["schema"])
+        .unwrap_or_else(|| panic!("{} {} {} is documented without {}", method, path, status, content_type));
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_required_fields(&spec, schema, &body);
+    (status, body)
+}
+
+fn same_media_type(a: &str, b: &str) -> bool {
+    let essence = |s: &str| s.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence(a) == essence(b)
+}
+
+/// Every required property of the (referenced) object schema is present
+fn assert_required_fields(spec: &Value, schema: &Value, body: &Value) {
+    let schema = match schema["$ref"].as_str() {
+        Some(reference) => {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            &spec["components"]["schemas"][name]
+        }
+        None => schema,
+    };
+    for field in schema["required"].as_array().into_iter().flatten() {
+        let field = field.as_str().unwrap();
+        assert!(body.get(field).is_some(), "missing required field {} in {}", field, body);
+    }
+}
@@ -0,0 +1,207 @@
+use poem::{
+    http::{header, StatusCode},
+    test::TestResponse,
+};
+use serde_json::json;
+
+use crate::harness::{conforming, TestApp, UserFactory, PASSWORD};
+
+#[tokio::test]
+async fn should_register_login_and_get_the_current_user() {
+    let app = TestApp::new().await;
+    let user = UserFactory::default().email("jane@example.com");
+
+    let resp = app.client.post("/api/auth/register").body_json(&user.payload()).send().await;
+    let (status, body) = conforming("post", "/api/auth/register", resp).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["session_user"]["email"], "jane@example.com");
+
+    let resp = app
+        .client
+        .post("/api/auth/login")
+        .body_json(&json!({ "email": "jane@example.com", "password": PASSWORD }))
+        .send()
+        .await;
+    let (status, body) = conforming("post", "/api/auth/login", resp).await;
+    assert_eq!(status, StatusCode::OK);
+    let token = body["token"].as_str().unwrap();
+
+    let resp = app
+        .client
+        .get("/api/users/me")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .send()
+        .await;
+    let (status, body) = conforming("get", "/api/users/me", resp).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email"], "jane@example.com");
+    assert_eq!(body["first_name"], "Jane");
+    assert_eq!(body["locale"], "en");
+    assert!(body.get("password").is_none());
+}
+
+#[tokio::test]
+async fn should_reject_taken_emails() {
+    let app = TestApp::new().await;
+    let user = app.user().await;
+
+    let payload = UserFactory::default().email(&user.email).payload();
+    let resp = app.client.post("/api/auth/register").body_json(&payload).send().await;
+    let (status, body) = conforming("post", "/api/auth/register", resp).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["code"], "email_taken");
+}
+
+#[tokio::test]
+async fn should_report_invalid_registrations() {
+    let app = TestApp::new().await;
+    let user = UserFactory {
+        first_name: String::from("J"),
+        locale: String::from("xx"),
+        ..UserFactory::default()
+    };
+
+    let resp = app.client.post("/api/auth/register").body_json(&user.payload()).send().await;
+    let (status, body) = conforming("post", "/api/auth/register", resp).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "validation_failed");
+    let fields: Vec<&str> = body["errors"].as_array().unwrap().iter().map(|e| e["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, vec!["first_name", "locale"]);
+
+    let user = UserFactory {
+        password: String::from("short"),
+        ..UserFactory::default()
+    };
+    let resp = app.client.post("/api/auth/register").body_json(&user.payload()).send().await;
+    let (status, body) = conforming("post", "/api/auth/register", resp).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "password_policy");
+}
+
+#[tokio::test]
+async fn should_not_tell_wrong_passwords_from_unknown_emails() {
+    let app = TestApp::new().await;
+    let user = app.user().await;
+
+    for (email, password) in [(user.email.as_str(), "wrong password"), ("nobody@example.com", PASSWORD)] {
+        let resp = app
+            .client
+            .post("/api/auth/login")
+            .body_json(&json!({ "email": email, "password": password }))
+            .send()
+            .await;
+        let (status, body) = conforming("post", "/api/auth/login", resp).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "invalid_credentials");
+    }
+}
+
+#[tokio::test]
+async fn should_require_a_valid_token_for_the_current_user() {
+    let app = TestApp::new().await;
+
+    let resp = app.client.get("/api/users/me").send().await;
+    let (status, body) = conforming("get", "/api/users/me", resp).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["instance"], "/api/users/me");
+
+    let resp = app
+        .client
+        .get("/api/users/me")
+        .header(header::AUTHORIZATION, "Bearer not-a-token")
+        .send()
+        .await;
+    let (status, _) = conforming("get", "/api/users/me", resp).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn should_store_the_preferred_locale() {
+    let app = TestApp::new().await;
+    let user = app.user().await;
+
+    let resp = app
+        .client
+        .put("/api/users/me/locale")
+        .header(header::AUTHORIZATION, user.bearer())
+        .body_json(&json!({ "locale": "de-AT" }))
+        .send()
+        .await;
+    let (status, _) = conforming("put", "/api/users/me/locale", resp).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let resp = app.client.get("/api/users/me").header(header::AUTHORIZATION, user.bearer()).send().await;
+    let (_, body) = conforming("get", "/api/users/me", resp).await;
+    assert_eq!(body["locale"], "de");
+    assert_eq!(body["pid"], user.pid.to_string());
+}
+
+/// `Cookie` header and CSRF token of a new cookie session of a new user
+async fn cookie_session(app: &TestApp) -> (String, String) {
+    let user = app.user().await;
+    let resp = app
+        .client
+        .post("/api/auth/session")
+        .body_json(&json!({ "email": user.email, "password": PASSWORD }))
+        .send()
+        .await;
+    let cookies = set_cookies(&resp).join("; ");
+    let (status, body) = conforming("post", "/api/auth/session", resp).await;
+    assert_eq!(status, StatusCode::OK);
+    (cookies, body["csrf_token"].as_str().unwrap().to_string())
+}
+
+/// `name=value` of every `Set-Cookie` header
+fn set_cookies(resp: &TestResponse) -> Vec<String> {
+    resp.0
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .map(|value| value.to_str().unwrap().split(';').next().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn should_require_the_csrf_token_to_delete_a_cookie_session() {
+    let app = TestApp::with_config(|config| {
+        config.iam.session_cookie.enabled = true;
+        config.iam.session_cookie.secure = false;
+    })
+    .await;
+    let (cookies, csrf_token) = cookie_session(&app).await;
+
+    let resp = app.client.delete("/api/auth/session").header(header::COOKIE, &cookies).send().await;
+    let (status, body) = conforming("delete", "/api/auth/session", resp).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["code"], "csrf_failed");
+
+    let resp = app
+        .client
+        .delete("/api/auth/session")
+        .header(header::COOKIE, &cookies)
+        .header("x-csrf-token", csrf_token)
+        .send()
+        .await;
+    let (status, _) = conforming("delete", "/api/auth/session", resp).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn should_extend_the_cookies_of_sliding_sessions() {
+    let app = TestApp::with_config(|config| {
+        config.iam.session = serde_json::from_value(json!({ "backend": "memory", "idle_timeout_secs": 600 })).unwrap();
+        config.iam.session_cookie.enabled = true;
+        config.iam.session_cookie.secure = false;
+    })
+    .await;
+    let (cookies, csrf_token) = cookie_session(&app).await;
+
+    let resp = app.client.get("/api/users/me").header(header::COOKIE, &cookies).send().await;
+    let mut names: Vec<String> = set_cookies(&resp).into_iter().map(|c| c.split('=').next().unwrap().to_string()).collect();
+    names.sort();
+    assert_eq!(names, vec!["csrf_token", "session"]);
+    assert!(resp.0.headers().get_all(header::SET_COOKIE).iter().all(|c| c.to_str().unwrap().contains("Max-Age=600")));
+    assert!(set_cookies(&resp).contains(&format!("csrf_token={}", csrf_token)));
+    let (status, _) = conforming("get", "/api/users/me", resp).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,7 @@
+//! HTTP tests of the app built by `AppBuilder`, each on its own in-memory SQLite database
+mod harness;
+
+mod app_builder_test;
+mod iam_test;
+mod openapi_test;
+mod webhooks_test;
@@ -0,0 +1,54 @@
+use poem::http::StatusCode;
+use serde_json::Value;
+use server::app::bootstrap::openapi_spec;
+
+use crate::harness::{conforming, TestApp};
+
+/// Operations that take parameters, a body or credentials can be refused, and must say how
+fn takes_input(operation: &Value) -> bool {
+    ["parameters", "requestBody", "security"]
+        .iter()
+        .any(|key| operation.get(key).is_some_and(|value| value.as_array().map_or(true, |items| !items.is_empty())))
+}
+
+#[test]
+fn should_document_every_operation_with_an_id_and_problem_responses_for_input() {
+    let spec: Value = serde_json::from_str(&openapi_spec()).unwrap();
+    let mut with_input = 0;
+    for (path, operations) in spec["paths"].as_object().unwrap() {
+        for (method, operation) in operations.as_object().unwrap() {
+            assert!(operation["operationId"].is_string(), "{} {} has no operationId", method, path);
+            assert!(
+                operation["responses"].as_object().is_some_and(|responses| !responses.is_empty()),
+                "{} {} documents no responses",
+                method,
+                path
+            );
+            if !takes_input(operation) {
+                continue;
+            }
+            with_input += 1;
+            assert!(
+                operation["responses"]
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|responses| responses.values())
+                    .any(|response| response["content"].get("application/problem+json").is_some()),
+                "{} {} documents no application/problem+json response",
+                method,
+                path
+            );
+        }
+    }
+    assert!(with_input > 10, "only {} operations take input", with_input);
+}
+
+#[tokio::test]
+async fn should_serve_probes_as_documented() {
+    let app = TestApp::new().await;
+    for path in ["/api/livez", "/api/readyz"] {
+        let resp = app.client.get(path).send().await;
+        let (status, _) = conforming("get", path, resp).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
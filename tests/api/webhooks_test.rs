@@ -0,0 +1,28 @@
+use poem::http::StatusCode;
+
+use crate::harness::{TestApp, UserFactory};
+
+#[tokio::test]
+async fn should_not_mount_postgres_only_operations_on_sqlite() {
+    let app = TestApp::with_config(|config| config.iam.admins = vec![String::from("admin@example.com")]).await;
+    let admin = UserFactory::default().email("admin@example.com").create(&app).await;
+
+    for (method, path) in [("GET", "/api/webhooks"), ("POST", "/api/webhooks"), ("GET", "/api/admin/scheduler")] {
+        let request = match method {
+            "GET" => app.client.get(path),
+            _ => app.client.post(path).body_json(&serde_json::json!({})),
+        };
+        let resp = request.header("authorization", admin.bearer()).send().await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+        resp.assert_content_type("application/problem+json");
+    }
+
+    // the other admin operations are still there
+    let resp = app
+        .client
+        .get("/api/admin/emails/welcome/preview")
+        .header("authorization", admin.bearer())
+        .send()
+        .await;
+    resp.assert_status_is_ok();
+}